@@ -0,0 +1,95 @@
+use anyhow::{anyhow, Result};
+use std::collections::HashSet;
+use std::time::SystemTime;
+
+use crate::blacklist::parse_address_lines;
+
+// Token mints an operator has pre-approved, in the blacklist's one-address-per-line format.
+// The file is re-read whenever its modification time changes, so mints can be added while the bot runs.
+pub struct ApproveList {
+    path: String,
+    entries: HashSet<String>,
+    modified: Option<SystemTime>,
+}
+
+impl ApproveList {
+    // A missing file is an empty approve list until it is created
+    pub fn load(path: &str) -> Result<Self> {
+        let mut approve_list = ApproveList { path: path.to_string(), entries: HashSet::new(), modified: None };
+        approve_list.reload_if_changed()?;
+        Ok(approve_list)
+    }
+
+    pub fn is_approved(&self, token_address: &str) -> bool {
+        self.entries.contains(token_address)
+    }
+
+    // Re-read the file if it changed since the last read, returning the mints that weren't approved before
+    pub fn reload_if_changed(&mut self) -> Result<Vec<String>> {
+        let modified = match std::fs::metadata(&self.path) {
+            Ok(metadata) => Some(metadata.modified()?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(anyhow!("Failed to read approve list {}: {}", self.path, e)),
+        };
+        if modified == self.modified {
+            return Ok(Vec::new());
+        }
+
+        let contents = match modified {
+            Some(_) => std::fs::read_to_string(&self.path)
+                .map_err(|e| anyhow!("Failed to read approve list {}: {}", self.path, e))?,
+            None => String::new(),
+        };
+        let entries = parse_address_lines(&contents);
+        let mut added: Vec<String> = entries.difference(&self.entries).cloned().collect();
+        added.sort();
+        self.entries = entries;
+        self.modified = modified;
+        Ok(added)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    // Rewrite the file with a later modification time than the last write, however coarse the filesystem's clock
+    fn write(path: &str, contents: &str, modified: SystemTime) {
+        std::fs::write(path, contents).unwrap();
+        std::fs::File::options().write(true).open(path).unwrap().set_modified(modified).unwrap();
+    }
+
+    #[test]
+    fn added_mints_are_reported_once_and_approved() {
+        let path = std::env::temp_dir().join(format!("approve-list-{}.txt", std::process::id())).to_str().unwrap().to_string();
+        let mut approve_list = ApproveList::load(&path).unwrap();
+        assert!(approve_list.is_empty());
+
+        let start = SystemTime::now();
+        write(&path, "mint1\n", start);
+        assert_eq!(approve_list.reload_if_changed().unwrap(), ["mint1"]);
+        assert!(approve_list.reload_if_changed().unwrap().is_empty());
+
+        write(&path, "mint1\nmint3  # launch\nmint2\n", start + Duration::from_secs(1));
+        assert_eq!(approve_list.reload_if_changed().unwrap(), ["mint2", "mint3"]);
+        assert!(approve_list.is_approved("mint3"));
+        assert!(!approve_list.is_approved("mint"));
+
+        write(&path, "mint2\n", start + Duration::from_secs(2));
+        assert!(approve_list.reload_if_changed().unwrap().is_empty());
+        assert!(!approve_list.is_approved("mint1"));
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(approve_list.reload_if_changed().unwrap().is_empty());
+        assert!(approve_list.is_empty());
+    }
+}
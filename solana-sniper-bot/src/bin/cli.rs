@@ -5,18 +5,45 @@ use std::fs;
 use std::path::Path;
 use solana_sdk::signature::{Keypair, Signer};
 use std::env;
+use std::io::Write;
 
-use solana_sniper_bot::{DexConfig, DexMonitor, MonitoringConfig, SafetyConfig, SniperConfig, SolanaSniperBot, TelegramConfig, TelegramSender, TradingConfig};
+use solana_sniper_bot::{dex_monitor, persistence, telegram, trace};
+use solana_sniper_bot::{default_blacklist_file, default_commitment, default_quote_mint, Blacklist, DexConfig, DumpDetectionConfig, FundingAmount, MonitoringConfig, PersistenceConfig, RetryPolicy, RpcUrls, SafetyConfig, SecretResolver, SecretString, SecretsConfig, SizingConfig, ClosedPosition, SniperConfig, SniperState, SolanaSniperBot, TelegramConfig, TradingConfig, WalletAllocation, WalletEntry, WalletFilter};
 
 fn create_default_config() -> SniperConfig {
-    SniperConfig {
-        rpc_url: env::var("RPC_URL").expect("RPC_URL must be set"),
-        wallets: env::var("WALLETS").unwrap_or_else(|_| "wallets/wallet1.json".to_string())
+    let secrets = SecretsConfig {
+        sources: env::var("SECRET_SOURCES").unwrap_or_else(|_| "env".to_string())
             .split(',').map(|s| s.trim().to_string()).collect(),
+        keyring_service: env::var("KEYRING_SERVICE").unwrap_or_else(|_| "solana-sniper-bot".to_string()),
+    };
+    let resolver = SecretResolver::from_config(&secrets).expect("SECRET_SOURCES is invalid");
+
+    let mut rpc_urls: Vec<String> = Vec::new();
+    for rpc_url in [env::var("RPC_URL").expect("RPC_URL must be set"), env::var("RPC_URLS").unwrap_or_default()].join(",").split(',') {
+        let rpc_url = rpc_url.trim().to_string();
+        if !rpc_url.is_empty() && !rpc_urls.contains(&rpc_url) {
+            rpc_urls.push(rpc_url);
+        }
+    }
+
+    SniperConfig {
+        // Comma-separated to fail over through several endpoints; RPC_URLS, if still set, just adds to the list
+        rpc_url: RpcUrls::Many(rpc_urls),
+        rpc_failover_threshold: env::var("RPC_FAILOVER_THRESHOLD").unwrap_or_else(|_| "3".to_string()).parse().unwrap(),
+        rpc_urls: Vec::new(),
+        broadcast_to_all_rpcs: env::var("BROADCAST_TO_ALL_RPCS").unwrap_or_else(|_| "false".to_string()).parse().unwrap(),
+        commitment: env::var("COMMITMENT").unwrap_or_else(|_| default_commitment()),
+        max_rpc_slot_lag: env::var("MAX_RPC_SLOT_LAG").ok().map(|v| v.parse().unwrap()),
+        rpc_lag_check_interval_ms: env::var("RPC_LAG_CHECK_INTERVAL_MS").unwrap_or_else(|_| "10000".to_string()).parse().unwrap(),
+        wallets: parse_wallets(
+            &env::var("WALLETS").unwrap_or_else(|_| "wallets/wallet1.json".to_string()),
+            &env::var("SHADOW_WALLETS").unwrap_or_default(),
+        ),
         min_sol_balance: env::var("MIN_SOL_BALANCE").unwrap_or_else(|_| "0.1".to_string()).parse().unwrap(),
         max_sol_per_trade: env::var("MAX_SOL_PER_TRADE").unwrap_or_else(|_| "0.05".to_string()).parse().unwrap(),
         slippage_tolerance: env::var("SLIPPAGE_TOLERANCE").unwrap_or_else(|_| "0.1".to_string()).parse().unwrap(),
         auto_sell: env::var("AUTO_SELL").unwrap_or_else(|_| "true".to_string()).parse().unwrap(),
+        auto_buy: env::var("AUTO_BUY").unwrap_or_else(|_| "false".to_string()).parse().unwrap(),
         anti_rug_check: env::var("ANTI_RUG_CHECK").unwrap_or_else(|_| "true".to_string()).parse().unwrap(),
         dex_config: DexConfig {
             raydium_api_url: env::var("RAYDIUM_API_URL").expect("RAYDIUM_API_URL must be set"),
@@ -24,6 +51,15 @@ fn create_default_config() -> SniperConfig {
             jupiter_api_url: env::var("JUPITER_API_URL").expect("JUPITER_API_URL must be set"),
             birdeye_api_url: env::var("BIRDEYE_API_URL").expect("BIRDEYE_API_URL must be set"),
             solscan_api_url: env::var("SOLSCAN_API_URL").expect("SOLSCAN_API_URL must be set"),
+            birdeye_api_key: resolver.resolve("BIRDEYE_API_KEY").map(SecretString::from).unwrap_or_default(),
+            jupiter_api_key: resolver.resolve("JUPITER_API_KEY").map(SecretString::from).unwrap_or_default(),
+            metadata_retry: RetryPolicy {
+                max_retries: env::var("METADATA_MAX_RETRIES").unwrap_or_else(|_| "3".to_string()).parse().unwrap(),
+                base_delay_ms: env::var("METADATA_RETRY_BASE_DELAY_MS").unwrap_or_else(|_| "200".to_string()).parse().unwrap(),
+                max_delay_ms: env::var("METADATA_RETRY_MAX_DELAY_MS").unwrap_or_else(|_| "5000".to_string()).parse().unwrap(),
+            },
+            metadata_cache_ttl_ms: env::var("METADATA_CACHE_TTL_MS").unwrap_or_else(|_| "5000".to_string()).parse().unwrap(),
+            jupiter_tokens_api_url: env::var("JUPITER_TOKENS_API_URL").unwrap_or_else(|_| "https://lite-api.jup.ag/tokens/v1".to_string()),
         },
         monitoring: MonitoringConfig {
             enable_raydium: env::var("ENABLE_RAYDIUM").unwrap_or_else(|_| "true".to_string()).parse().unwrap(),
@@ -31,35 +67,208 @@ fn create_default_config() -> SniperConfig {
             enable_jupiter: env::var("ENABLE_JUPITER").unwrap_or_else(|_| "true".to_string()).parse().unwrap(),
             check_interval_ms: env::var("CHECK_INTERVAL_MS").unwrap_or_else(|_| "1000".to_string()).parse().unwrap(),
             websocket_reconnect_delay_ms: env::var("WEBSOCKET_RECONNECT_DELAY_MS").unwrap_or_else(|_| "5000".to_string()).parse().unwrap(),
+            websocket_max_reconnect_delay_ms: env::var("WEBSOCKET_MAX_RECONNECT_DELAY_MS").unwrap_or_else(|_| "60000".to_string()).parse().unwrap(),
+            websocket_rate_limit_delay_ms: env::var("WEBSOCKET_RATE_LIMIT_DELAY_MS").unwrap_or_else(|_| "60000".to_string()).parse().unwrap(),
+            log_idle_timeout_ms: env::var("LOG_IDLE_TIMEOUT_MS").unwrap_or_else(|_| "60000".to_string()).parse().unwrap(),
+            seen_pool_cache_size: env::var("SEEN_POOL_CACHE_SIZE").unwrap_or_else(|_| "1000".to_string()).parse().unwrap(),
+            max_evaluations_per_second: env::var("MAX_EVALUATIONS_PER_SECOND").ok().map(|v| v.parse().unwrap()),
+            max_concurrent_snipes: env::var("MAX_CONCURRENT_SNIPES").ok().map(|v| v.parse().unwrap()),
+            max_monitors: env::var("MAX_MONITORS").ok().map(|v| v.parse().unwrap()),
+            raydium_websocket_url: env::var("RAYDIUM_WEBSOCKET_URL").ok(),
+            birdeye_price_feed: env::var("BIRDEYE_PRICE_FEED").unwrap_or_else(|_| "false".to_string()).parse().unwrap(),
+            birdeye_listing_feed: env::var("BIRDEYE_LISTING_FEED").unwrap_or_else(|_| "false".to_string()).parse().unwrap(),
+            birdeye_websocket_url: env::var("BIRDEYE_WEBSOCKET_URL").unwrap_or_else(|_| "wss://public-api.birdeye.so/socket/solana".to_string()),
+            emit_pool_details: env::var("EMIT_POOL_DETAILS").unwrap_or_else(|_| "true".to_string()).parse().unwrap(),
+            raydium_pool_variants: dex_monitor::default_raydium_pool_variants(),
+            orca_pool_variants: dex_monitor::default_orca_pool_variants(),
+            wallet_selection: env::var("WALLET_SELECTION").unwrap_or_else(|_| "round_robin".to_string()).parse().unwrap(),
+            designated_wallet: env::var("DESIGNATED_WALLET").unwrap_or_else(|_| "0".to_string()).parse().unwrap(),
         },
         safety: SafetyConfig {
             min_liquidity_sol: env::var("MIN_LIQUIDITY_SOL").unwrap_or_else(|_| "1.0".to_string()).parse().unwrap(),
             max_creator_holdings_percent: env::var("MAX_CREATOR_HOLDINGS_PERCENT").unwrap_or_else(|_| "50.0".to_string()).parse().unwrap(),
             blacklist_check: env::var("BLACKLIST_CHECK").unwrap_or_else(|_| "true".to_string()).parse().unwrap(),
+            blacklist_file: env::var("BLACKLIST_FILE").unwrap_or_else(|_| default_blacklist_file()),
+            verify_pool_account: env::var("VERIFY_POOL_ACCOUNT").unwrap_or_else(|_| "false".to_string()).parse().unwrap(),
+            approve_list_file: env::var("APPROVE_LIST_FILE").ok(),
+            daily_spend_limit_sol: env::var("DAILY_SPEND_LIMIT_SOL").ok().map(|v| v.parse().unwrap()),
+            per_wallet_spend_limit_sol: env::var("PER_WALLET_SPEND_LIMIT_SOL").ok().map(|v| v.parse().unwrap()),
+            snipe_approved_on_add: env::var("SNIPE_APPROVED_ON_ADD").unwrap_or_else(|_| "false".to_string()).parse().unwrap(),
+            approve_list_poll_interval_ms: env::var("APPROVE_LIST_POLL_INTERVAL_MS").unwrap_or_else(|_| "2000".to_string()).parse().unwrap(),
+            min_token_age_secs: env::var("MIN_TOKEN_AGE_SECS").ok().map(|v| v.parse().unwrap()),
+            max_token_age_secs: env::var("MAX_TOKEN_AGE_SECS").ok().map(|v| v.parse().unwrap()),
+            require_mint_authority_revoked: env::var("REQUIRE_MINT_AUTHORITY_REVOKED").unwrap_or_else(|_| "false".to_string()).parse().unwrap(),
+            require_freeze_authority_revoked: env::var("REQUIRE_FREEZE_AUTHORITY_REVOKED").unwrap_or_else(|_| "false".to_string()).parse().unwrap(),
+            require_lp_locked: env::var("REQUIRE_LP_LOCKED").unwrap_or_else(|_| "false".to_string()).parse().unwrap(),
+            min_lp_locked_percent: env::var("MIN_LP_LOCKED_PERCENT").unwrap_or_else(|_| "90".to_string()).parse().unwrap(),
+            min_liquidity_to_market_cap_ratio: env::var("MIN_LIQUIDITY_TO_MARKET_CAP_RATIO").ok().map(|v| v.parse().unwrap()),
             honeypot_check: env::var("HONEYPOT_CHECK").unwrap_or_else(|_| "true".to_string()).parse().unwrap(),
             min_market_cap: env::var("MIN_MARKET_CAP").unwrap_or_else(|_| "10000.0".to_string()).parse().unwrap(),
             min_holders: env::var("MIN_HOLDERS").unwrap_or_else(|_| "100".to_string()).parse().unwrap(),
             min_volume_24h: env::var("MIN_VOLUME_24H").unwrap_or_else(|_| "1000.0".to_string()).parse().unwrap(),
+            min_successful_sells: env::var("MIN_SUCCESSFUL_SELLS").unwrap_or_else(|_| "0".to_string()).parse().unwrap(),
+            sell_scan_limit: env::var("SELL_SCAN_LIMIT").unwrap_or_else(|_| "100".to_string()).parse().unwrap(),
+            wash_trade_top_traders: env::var("WASH_TRADE_TOP_TRADERS").ok().map(|v| v.parse().unwrap()),
+            max_token_tax_percent: env::var("MAX_TOKEN_TAX_PERCENT").ok().map(|v| v.parse().unwrap()),
+            max_sell_price_impact: env::var("MAX_SELL_PRICE_IMPACT").unwrap_or_else(|_| "0.25".to_string()).parse().unwrap(),
         },
         trading: TradingConfig {
             max_price_impact: env::var("MAX_PRICE_IMPACT").unwrap_or_else(|_| "0.05".to_string()).parse().unwrap(),
             min_slippage: env::var("MIN_SLIPPAGE").unwrap_or_else(|_| "0.001".to_string()).parse().unwrap(),
             max_slippage: env::var("MAX_SLIPPAGE").unwrap_or_else(|_| "0.1".to_string()).parse().unwrap(),
             gas_priority: env::var("GAS_PRIORITY").unwrap_or_else(|_| "medium".to_string()),
+            priority_fee_micro_lamports: env::var("PRIORITY_FEE_MICRO_LAMPORTS").ok().map(|v| v.parse().unwrap()),
             retry_failed_trades: env::var("RETRY_FAILED_TRADES").unwrap_or_else(|_| "true".to_string()).parse().unwrap(),
             max_retries: env::var("MAX_RETRIES").unwrap_or_else(|_| "3".to_string()).parse().unwrap(),
+            multi_wallet_snipe: parse_wallet_allocations(&env::var("MULTI_WALLET_SNIPE").unwrap_or_default()),
+            min_output_amount: env::var("MIN_OUTPUT_AMOUNT").unwrap_or_else(|_| "0".to_string()).parse().unwrap(),
+            take_profit_percent: env::var("TAKE_PROFIT_PERCENT").ok().map(|v| v.parse().unwrap()),
+            stop_loss_percent: env::var("STOP_LOSS_PERCENT").ok().map(|v| v.parse().unwrap()),
+            position_check_interval_ms: env::var("POSITION_CHECK_INTERVAL_MS").unwrap_or_else(|_| "5000".to_string()).parse().unwrap(),
+            exit_on_creator_liquidity_removal: env::var("EXIT_ON_CREATOR_LIQUIDITY_REMOVAL").unwrap_or_else(|_| "false".to_string()).parse().unwrap(),
+            snipe_confirmation_deadline_ms: env::var("SNIPE_CONFIRMATION_DEADLINE_MS").unwrap_or_else(|_| "30000".to_string()).parse().unwrap(),
+            dust_threshold_sol: env::var("DUST_THRESHOLD_SOL").unwrap_or_else(|_| "0.001".to_string()).parse().unwrap(),
+            quote_mint: env::var("QUOTE_MINT").unwrap_or_else(|_| default_quote_mint()),
+            sell_all_concurrency: env::var("SELL_ALL_CONCURRENCY").unwrap_or_else(|_| "4".to_string()).parse().unwrap(),
+            requote_before_take_profit: env::var("REQUOTE_BEFORE_TAKE_PROFIT").unwrap_or_else(|_| "false".to_string()).parse().unwrap(),
+            capital_floor_sol: env::var("CAPITAL_FLOOR_SOL").ok().map(|v| v.parse().unwrap()),
+            snipe_cooldown_secs: env::var("SNIPE_COOLDOWN_SECS").unwrap_or_else(|_| "0".to_string()).parse().unwrap(),
+            max_quote_divergence: env::var("MAX_QUOTE_DIVERGENCE").ok().map(|v| v.parse().unwrap()),
         },
         telegram: TelegramConfig {
-            bot_token: env::var("TELEGRAM_BOT_TOKEN").expect("TELEGRAM_BOT_TOKEN must be set"),
-            chat_id: env::var("TELEGRAM_CHAT_ID").expect("TELEGRAM_CHAT_ID must be set"),
+            bot_token: resolver.resolve("TELEGRAM_BOT_TOKEN").map(SecretString::from).unwrap_or_default(),
+            chat_id: resolver.resolve("TELEGRAM_CHAT_ID").map(|chat_id| chat_id.to_string()).unwrap_or_default(),
+            notify_failed_trades: env::var("TELEGRAM_NOTIFY_FAILED_TRADES").unwrap_or_else(|_| "false".to_string()).parse().unwrap(),
+            interactive_buttons: env::var("TELEGRAM_INTERACTIVE_BUTTONS").unwrap_or_else(|_| "false".to_string()).parse().unwrap(),
+            button_buy_amount_sol: env::var("TELEGRAM_BUTTON_BUY_AMOUNT_SOL").unwrap_or_else(|_| "0.05".to_string()).parse().unwrap(),
+            plain_text_fallback: env::var("TELEGRAM_PLAIN_TEXT_FALLBACK").unwrap_or_else(|_| "true".to_string()).parse().unwrap(),
+            api_url: env::var("TELEGRAM_API_URL").unwrap_or_else(|_| telegram::default_telegram_api_url()),
+        },
+        dump_detection: DumpDetectionConfig {
+            enabled: env::var("DUMP_DETECTION_ENABLED").unwrap_or_else(|_| "false".to_string()).parse().unwrap(),
+            holder_drop_percent: env::var("DUMP_HOLDER_DROP_PERCENT").unwrap_or_else(|_| "20.0".to_string()).parse().unwrap(),
+            sell_volume_spike_multiplier: env::var("DUMP_SELL_VOLUME_SPIKE_MULTIPLIER").unwrap_or_else(|_| "3.0".to_string()).parse().unwrap(),
+            window_secs: env::var("DUMP_WINDOW_SECS").unwrap_or_else(|_| "300".to_string()).parse().unwrap(),
+            exit_on_dump: env::var("DUMP_EXIT_ON_DUMP").unwrap_or_else(|_| "false".to_string()).parse().unwrap(),
+        },
+        secrets,
+        persistence: PersistenceConfig {
+            state_file: env::var("STATE_FILE").ok(),
+            trade_log_file: env::var("TRADE_LOG_FILE").ok(),
+            trade_log_buffer_size: env::var("TRADE_LOG_BUFFER_SIZE").unwrap_or_else(|_| "20".to_string()).parse().unwrap(),
+            shutdown_flush_timeout_ms: env::var("SHUTDOWN_FLUSH_TIMEOUT_MS").unwrap_or_else(|_| "5000".to_string()).parse().unwrap(),
+            pid_file: env::var("PID_FILE").ok(),
+            closed_positions_file: env::var("CLOSED_POSITIONS_FILE").ok(),
+            notified_pools_file: env::var("NOTIFIED_POOLS_FILE").ok(),
+            notified_pools_ttl_secs: env::var("NOTIFIED_POOLS_TTL_SECS").unwrap_or_else(|_| "3600".to_string()).parse().unwrap(),
+        },
+        run_id: env::var("RUN_ID").ok(),
+        expected_cluster: env::var("EXPECTED_CLUSTER").ok(),
+        metrics_port: env::var("METRICS_PORT").ok().map(|v| v.parse().unwrap()),
+        strategies: env::var("STRATEGIES").map(|v| serde_json::from_str(&v).expect("STRATEGIES must be a JSON list of strategies")).unwrap_or_default(),
+        maintenance_wallets: WalletFilter {
+            min_index: env::var("MAINTENANCE_WALLET_MIN_INDEX").ok().map(|v| v.parse().unwrap()),
+            max_index: env::var("MAINTENANCE_WALLET_MAX_INDEX").ok().map(|v| v.parse().unwrap()),
+            tags: env::var("MAINTENANCE_WALLET_TAGS").unwrap_or_default().split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect(),
+            exclude_tags: env::var("MAINTENANCE_WALLET_EXCLUDE_TAGS").unwrap_or_default().split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect(),
         },
+        sizing: SizingConfig {
+            enabled: env::var("SIZING_ENABLED").unwrap_or_else(|_| "false".to_string()).parse().unwrap(),
+            step_percent: env::var("SIZING_STEP_PERCENT").unwrap_or_else(|_| "10.0".to_string()).parse().unwrap(),
+            min_multiplier: env::var("SIZING_MIN_MULTIPLIER").unwrap_or_else(|_| "0.5".to_string()).parse().unwrap(),
+            max_multiplier: env::var("SIZING_MAX_MULTIPLIER").unwrap_or_else(|_| "2.0".to_string()).parse().unwrap(),
+            lookback: env::var("SIZING_LOOKBACK").unwrap_or_else(|_| "20".to_string()).parse().unwrap(),
+            max_portfolio_fraction: env::var("SIZING_MAX_PORTFOLIO_FRACTION").unwrap_or_else(|_| "1.0".to_string()).parse().unwrap(),
+            randomize: env::var("SIZING_RANDOMIZE").unwrap_or_else(|_| "false".to_string()).parse().unwrap(),
+            buy_amount_min: env::var("BUY_AMOUNT_MIN").unwrap_or_else(|_| "0.0".to_string()).parse().unwrap(),
+            buy_amount_max: env::var("BUY_AMOUNT_MAX").unwrap_or_else(|_| "0.0".to_string()).parse().unwrap(),
+        },
+    }
+}
+
+// Wallet paths from WALLETS, with the indices listed in SHADOW_WALLETS marked as shadow
+fn parse_wallets(paths: &str, shadow_indices: &str) -> Vec<WalletEntry> {
+    let shadow_indices: Vec<usize> = shadow_indices.split(',')
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse().unwrap())
+        .collect();
+    paths.split(',')
+        .map(|s| s.trim().to_string())
+        .enumerate()
+        .map(|(i, path)| {
+            if shadow_indices.contains(&i) {
+                WalletEntry::Detailed { path, shadow: true, tags: Vec::new() }
+            } else {
+                WalletEntry::Path(path)
+            }
+        })
+        .collect()
+}
+
+// Parses "index:amount" pairs such as "0:0.05,1:0.1"
+fn parse_wallet_allocations(value: &str) -> Vec<WalletAllocation> {
+    value.split(',')
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            let (wallet_index, amount_sol) = s.split_once(':').expect("MULTI_WALLET_SNIPE entries must be index:amount");
+            WalletAllocation {
+                wallet_index: wallet_index.trim().parse().unwrap(),
+                amount_sol: amount_sol.trim().parse().unwrap(),
+            }
+        })
+        .collect()
+}
+
+// Collects a record's structured fields (token, signature, ...) for either log format
+struct LogFields(Vec<(String, String)>);
+
+impl<'kvs> log::kv::VisitSource<'kvs> for LogFields {
+    fn visit_pair(&mut self, key: log::kv::Key<'kvs>, value: log::kv::Value<'kvs>) -> Result<(), log::kv::Error> {
+        self.0.push((key.to_string(), value.to_string()));
+        Ok(())
     }
 }
 
+// env_logger with the run ID in every line once the bot has started. LOG_FORMAT=json writes one JSON object
+// per line for log aggregators; otherwise lines are plain text with structured fields appended as key=value.
+// Fields come from the log crate's key-value support rather than tracing-subscriber, which the build doesn't have.
+fn init_logger() {
+    let json = env::var("LOG_FORMAT").is_ok_and(|format| format.eq_ignore_ascii_case("json"));
+    env_logger::Builder::from_default_env()
+        .format(move |buf, record| {
+            let mut fields = LogFields(Vec::new());
+            let _ = record.key_values().visit(&mut fields);
+            let timestamp = chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ");
+            let run_id = trace::current_run_id().unwrap_or("-");
+
+            if json {
+                let mut line = serde_json::Map::new();
+                line.insert("timestamp".to_string(), timestamp.to_string().into());
+                line.insert("level".to_string(), record.level().as_str().into());
+                line.insert("target".to_string(), record.target().into());
+                line.insert("run_id".to_string(), run_id.into());
+                line.insert("message".to_string(), record.args().to_string().into());
+                for (key, value) in fields.0 {
+                    line.insert(key, value.into());
+                }
+                return writeln!(buf, "{}", serde_json::Value::Object(line));
+            }
+
+            write!(buf, "[{} {} {} run={}] {}", timestamp, record.level(), record.target(), run_id, record.args())?;
+            for (key, value) in fields.0 {
+                write!(buf, " {}={}", key, value)?;
+            }
+            writeln!(buf)
+        })
+        .init();
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     dotenv::dotenv().ok();
-    env_logger::init();
+    init_logger();
     
     let matches = App::new("Solana Sniper Bot CLI")
         .version("1.0")
@@ -73,8 +282,30 @@ async fn main() -> Result<()> {
                 .value_name("FILE")
                 .help("Configuration file path")
                 .default_value("config.json")))
+        .subcommand(SubCommand::with_name("monitor")
+            .about("Watch for new pools and send alerts without ever buying or selling")
+            .arg(Arg::with_name("config")
+                .short('c')
+                .long("config")
+                .value_name("FILE")
+                .help("Configuration file path")
+                .default_value("config.json")))
         .subcommand(SubCommand::with_name("status")
-            .about("Show bot status"))
+            .about("Show bot status")
+            .arg(Arg::with_name("config")
+                .short('c')
+                .long("config")
+                .value_name("FILE")
+                .help("Configuration file path (for the state file location)")
+                .default_value("config.json")))
+        .subcommand(SubCommand::with_name("stop")
+            .about("Stop a running bot gracefully")
+            .arg(Arg::with_name("config")
+                .short('c')
+                .long("config")
+                .value_name("FILE")
+                .help("Configuration file path (for the PID file location)")
+                .default_value("config.json")))
         .subcommand(SubCommand::with_name("create-wallets")
             .about("Create multiple wallets")
             .arg(Arg::with_name("count")
@@ -91,6 +322,8 @@ async fn main() -> Result<()> {
                 .value_name("INDEX")
                 .help("Wallet index (0-based)")
                 .required(true)))
+        .subcommand(SubCommand::with_name("list-wallets")
+            .about("List every configured wallet with its SOL balance"))
         .subcommand(SubCommand::with_name("feed-wallet")
             .about("Feed wallet with small amount")
             .arg(Arg::with_name("from")
@@ -111,6 +344,35 @@ async fn main() -> Result<()> {
                 .value_name("SOL")
                 .help("Amount in SOL")
                 .default_value("0.01")))
+        .subcommand(SubCommand::with_name("distribute")
+            .about("Fund every other wallet from one wallet, batching the transfers")
+            .arg(Arg::with_name("from")
+                .short('f')
+                .long("from")
+                .value_name("INDEX")
+                .help("Source wallet index")
+                .required(true))
+            .arg(Arg::with_name("per-wallet")
+                .long("per-wallet")
+                .value_name("SOL")
+                .help("Amount to send each wallet")
+                .required_unless_present("total"))
+            .arg(Arg::with_name("total")
+                .long("total")
+                .value_name("SOL")
+                .help("Total amount to split evenly between the wallets")
+                .conflicts_with("per-wallet")))
+        .subcommand(SubCommand::with_name("sweep")
+            .about("Collect SOL from every other wallet into one wallet, keeping each wallet's rent-exempt reserve")
+            .arg(Arg::with_name("to")
+                .short('t')
+                .long("to")
+                .value_name("INDEX")
+                .help("Destination wallet index")
+                .default_value("0"))
+            .arg(Arg::with_name("sell-tokens")
+                .long("sell-tokens")
+                .help("Sell each wallet's token balances first")))
         .subcommand(SubCommand::with_name("snipe")
             .about("Manually snipe a token")
             .arg(Arg::with_name("token")
@@ -131,15 +393,160 @@ async fn main() -> Result<()> {
                 .value_name("SOL")
                 .help("Amount in SOL")
                 .default_value("0.01")))
+        .subcommand(SubCommand::with_name("sell")
+            .about("Manually sell a token")
+            .arg(Arg::with_name("token")
+                .short('t')
+                .long("token")
+                .value_name("ADDRESS")
+                .help("Token address")
+                .required(true))
+            .arg(Arg::with_name("wallet")
+                .short('w')
+                .long("wallet")
+                .value_name("INDEX")
+                .help("Wallet index")
+                .default_value("0"))
+            .arg(Arg::with_name("amount")
+                .short('a')
+                .long("amount")
+                .value_name("TOKENS")
+                .help("Amount in the token's base units")
+                .required_unless_present_any(["all", "exact-out", "recover-initial"]))
+            .arg(Arg::with_name("all")
+                .long("all")
+                .help("Sell the wallet's entire balance of the token")
+                .conflicts_with_all(&["amount", "exact-out", "recover-initial"]))
+            .arg(Arg::with_name("exact-out")
+                .long("exact-out")
+                .value_name("QUOTE_AMOUNT")
+                .help("Sell just enough to receive this much of the quote token (ExactOut)")
+                .conflicts_with_all(&["amount", "recover-initial"]))
+            .arg(Arg::with_name("recover-initial")
+                .long("recover-initial")
+                .help("Sell just enough of the open position to recover its cost (ExactOut)")
+                .conflicts_with("amount")))
+        .subcommand(SubCommand::with_name("analyze")
+            .about("Run the safety checks on a token and show what each one measured")
+            .arg(Arg::with_name("token")
+                .short('t')
+                .long("token")
+                .value_name("ADDRESS")
+                .help("Token address")
+                .required(true)))
+        .subcommand(SubCommand::with_name("token-balance")
+            .about("Show how much of a token a wallet holds")
+            .arg(Arg::with_name("token")
+                .short('t')
+                .long("token")
+                .value_name("ADDRESS")
+                .help("Token address")
+                .required(true))
+            .arg(Arg::with_name("wallet")
+                .short('w')
+                .long("wallet")
+                .value_name("INDEX")
+                .help("Wallet index")
+                .default_value("0")))
+        .subcommand(SubCommand::with_name("sell-all")
+            .about("Sell every open position, several at a time"))
+        .subcommand(SubCommand::with_name("sweep-dust")
+            .about("Swap tiny token balances to SOL and close empty token accounts")
+            .arg(Arg::with_name("wallet")
+                .short('w')
+                .long("wallet")
+                .value_name("INDEX")
+                .help("Wallet index")
+                .default_value("0"))
+            .arg(Arg::with_name("all")
+                .long("all")
+                .help("Sweep every wallet allowed by maintenance_wallets")
+                .conflicts_with("wallet"))
+            .arg(Arg::with_name("threshold")
+                .long("threshold")
+                .value_name("SOL")
+                .help("Balances quoted below this many SOL count as dust (default: DUST_THRESHOLD_SOL)")))
+        .subcommand(SubCommand::with_name("export-pnl")
+            .about("Export closed positions with cost basis and realized PnL as CSV")
+            .arg(Arg::with_name("config")
+                .short('c')
+                .long("config")
+                .value_name("FILE")
+                .help("Configuration file path (for the closed positions file location)")
+                .default_value("config.json"))
+            .arg(Arg::with_name("output")
+                .short('o')
+                .long("output")
+                .value_name("FILE")
+                .help("CSV file to write (default: stdout)"))
+            .arg(Arg::with_name("from")
+                .long("from")
+                .value_name("YYYY-MM-DD")
+                .help("Only positions closed on or after this date (UTC)"))
+            .arg(Arg::with_name("to")
+                .long("to")
+                .value_name("YYYY-MM-DD")
+                .help("Only positions closed on or before this date (UTC)"))
+            .arg(Arg::with_name("wallet")
+                .short('w')
+                .long("wallet")
+                .value_name("INDEX")
+                .help("Only positions of this wallet")))
+        .subcommand(SubCommand::with_name("blacklist")
+            .about("Add token mints or creator addresses to the blacklist")
+            .arg(Arg::with_name("config")
+                .short('c')
+                .long("config")
+                .value_name("FILE")
+                .help("Configuration file path (for the blacklist file location)")
+                .default_value("config.json"))
+            .arg(Arg::with_name("address")
+                .value_name("ADDRESS")
+                .help("Base58 token mint or creator address")
+                .required(true)
+                .multiple_values(true)))
+        .subcommand(SubCommand::with_name("import-wallet")
+            .about("Import a wallet from a BIP39 seed phrase")
+            .arg(Arg::with_name("name")
+                .short('n')
+                .long("name")
+                .value_name("NAME")
+                .help("Wallet file name (saved as wallets/<NAME>.json)")
+                .required(true))
+            .arg(Arg::with_name("mnemonic")
+                .short('m')
+                .long("mnemonic")
+                .value_name("PHRASE")
+                .help("Seed phrase (default: MNEMONIC, or prompt on stdin)"))
+            .arg(Arg::with_name("passphrase")
+                .short('p')
+                .long("passphrase")
+                .value_name("PASSPHRASE")
+                .help("Optional BIP39 passphrase (default: MNEMONIC_PASSPHRASE)"))
+            .arg(Arg::with_name("account")
+                .short('a')
+                .long("account")
+                .value_name("INDEX")
+                .help("Account index in m/44'/501'/<INDEX>'/0'")
+                .default_value("0")))
         .get_matches();
 
     match matches.subcommand() {
         Some(("start", args)) => {
             let config_file = args.value_of("config").unwrap();
-            start_bot(config_file).await?;
+            start_bot(config_file, false).await?;
+        }
+        Some(("monitor", args)) => {
+            let config_file = args.value_of("config").unwrap();
+            start_bot(config_file, true).await?;
+        }
+        Some(("status", args)) => {
+            let config_file = args.value_of("config").unwrap();
+            show_status(config_file).await?;
         }
-        Some(("status", _)) => {
-            show_status().await?;
+        Some(("stop", args)) => {
+            let config_file = args.value_of("config").unwrap();
+            stop_bot(config_file)?;
         }
         Some(("create-wallets", args)) => {
             let count: usize = args.value_of("count").unwrap().parse()?;
@@ -149,6 +556,21 @@ async fn main() -> Result<()> {
             let wallet_index: usize = args.value_of("wallet").unwrap().parse()?;
             check_balance(wallet_index).await?;
         }
+        Some(("list-wallets", _)) => {
+            list_wallets().await?;
+        }
+        Some(("distribute", args)) => {
+            let from_index: usize = args.value_of("from").unwrap().parse()?;
+            let amount = match args.value_of("per-wallet") {
+                Some(amount) => FundingAmount::PerWallet(amount.parse()?),
+                None => FundingAmount::Total(args.value_of("total").unwrap().parse()?),
+            };
+            distribute(from_index, amount).await?;
+        }
+        Some(("sweep", args)) => {
+            let to_index: usize = args.value_of("to").unwrap().parse()?;
+            sweep(to_index, args.is_present("sell-tokens")).await?;
+        }
         Some(("feed-wallet", args)) => {
             let from_index: usize = args.value_of("from").unwrap().parse()?;
             let to_index: usize = args.value_of("to").unwrap().parse()?;
@@ -161,6 +583,52 @@ async fn main() -> Result<()> {
             let amount: f64 = args.value_of("amount").unwrap().parse()?;
             snipe_token(token_address, wallet_index, amount).await?;
         }
+        Some(("sell", args)) => {
+            let token_address = args.value_of("token").unwrap();
+            let wallet_index: usize = args.value_of("wallet").unwrap().parse()?;
+            let amount = if args.is_present("recover-initial") {
+                SellAmount::RecoverInitial
+            } else if let Some(proceeds) = args.value_of("exact-out") {
+                SellAmount::ExactOut(proceeds.parse()?)
+            } else {
+                SellAmount::Tokens(args.value_of("amount").map(|amount| amount.parse()).transpose()?)
+            };
+            sell_token(token_address, wallet_index, amount).await?;
+        }
+        Some(("analyze", args)) => {
+            let token_address = args.value_of("token").unwrap();
+            analyze_token(token_address).await?;
+        }
+        Some(("token-balance", args)) => {
+            let token_address = args.value_of("token").unwrap();
+            let wallet_index: usize = args.value_of("wallet").unwrap().parse()?;
+            token_balance(token_address, wallet_index)?;
+        }
+        Some(("sell-all", _)) => {
+            sell_all().await?;
+        }
+        Some(("sweep-dust", args)) => {
+            let wallet_index: Option<usize> = if args.is_present("all") { None } else { Some(args.value_of("wallet").unwrap().parse()?) };
+            let threshold: Option<f64> = args.value_of("threshold").map(|threshold| threshold.parse()).transpose()?;
+            sweep_dust(wallet_index, threshold).await?;
+        }
+        Some(("export-pnl", args)) => {
+            let config_file = args.value_of("config").unwrap();
+            let from = args.value_of("from").map(|date| parse_date(date, false)).transpose()?;
+            let to = args.value_of("to").map(|date| parse_date(date, true)).transpose()?;
+            let wallet_index: Option<usize> = args.value_of("wallet").map(|wallet| wallet.parse()).transpose()?;
+            export_pnl(config_file, args.value_of("output"), from, to, wallet_index)?;
+        }
+        Some(("blacklist", args)) => {
+            let config_file = args.value_of("config").unwrap();
+            let addresses: Vec<&str> = args.values_of("address").unwrap().collect();
+            add_to_blacklist(config_file, &addresses)?;
+        }
+        Some(("import-wallet", args)) => {
+            let wallet_name = args.value_of("name").unwrap();
+            let account_index: u32 = args.value_of("account").unwrap().parse()?;
+            import_wallet(wallet_name, args.value_of("mnemonic"), args.value_of("passphrase"), account_index)?;
+        }
         _ => {
             println!("Use --help for usage information");
         }
@@ -169,42 +637,175 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
-async fn start_bot(config_file: &str) -> Result<()> {
+// Run the bot until shut down; watch_only runs detection and alerts with all trading forced off
+async fn start_bot(config_file: &str, watch_only: bool) -> Result<()> {
     info!("Starting Solana Sniper Bot with config: {}", config_file);
     
     // Load config
     let config_data = fs::read_to_string(config_file)?;
-    let config: SniperConfig = serde_json::from_str(&config_data)?;
+    let mut config: SniperConfig = serde_json::from_str(&config_data)?;
+    config.validate().map_err(|e| anyhow::anyhow!("Invalid config in {}: {}", config_file, e))?;
+    let resolver = SecretResolver::from_config(&config.secrets)?;
+    config.resolve_secrets(&resolver);
+    if watch_only {
+        config.auto_buy = false;
+        config.auto_sell = false;
+        config.telegram.interactive_buttons = false;
+        info!("Watch-only mode: buying and selling are disabled regardless of config");
+    }
     
     // Create bot
     let bot = SolanaSniperBot::new(config.clone())?;
     
+    // Record our PID so `stop` can signal this process
+    if let Some(pid_file) = &config.persistence.pid_file {
+        fs::write(pid_file, std::process::id().to_string())?;
+    }
+    
+    // Stop gracefully on Ctrl-C or SIGTERM
+    let shutdown = bot.shutdown_handle();
+    tokio::spawn(async move {
+        wait_for_shutdown_signal().await;
+        info!("Shutdown signal received, stopping...");
+        shutdown.shutdown();
+    });
+    
     // Start monitoring
-    let telegram_config = TelegramConfig {
-        bot_token: config.telegram.bot_token.clone(),
-        chat_id: config.telegram.chat_id.clone(),
+    let result = bot.start().await;
+    
+    if let Some(pid_file) = &config.persistence.pid_file {
+        let _ = fs::remove_file(pid_file);
+    }
+    result
+}
+
+#[cfg(unix)]
+async fn wait_for_shutdown_signal() {
+    use tokio::signal::unix::{signal, SignalKind};
+    let mut sigterm = signal(SignalKind::terminate()).expect("Failed to install SIGTERM handler");
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => {}
+        _ = sigterm.recv() => {}
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait_for_shutdown_signal() {
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+// Persistence settings from the config file when there is one, otherwise from the environment
+fn load_persistence_config(config_file: &str) -> Result<PersistenceConfig> {
+    match fs::read_to_string(config_file) {
+        Ok(config_data) => Ok(serde_json::from_str::<SniperConfig>(&config_data)?.persistence),
+        Err(_) => Ok(PersistenceConfig {
+            state_file: env::var("STATE_FILE").ok(),
+            pid_file: env::var("PID_FILE").ok(),
+            closed_positions_file: env::var("CLOSED_POSITIONS_FILE").ok(),
+            ..PersistenceConfig::default()
+        }),
+    }
+}
+
+fn add_to_blacklist(config_file: &str, addresses: &[&str]) -> Result<()> {
+    let blacklist_file = match fs::read_to_string(config_file) {
+        Ok(config_data) => serde_json::from_str::<SniperConfig>(&config_data)?.safety.blacklist_file,
+        Err(_) => env::var("BLACKLIST_FILE").unwrap_or_else(|_| default_blacklist_file()),
     };
-    let telegram_sender = TelegramSender::new(telegram_config);
-    let dex_config = solana_sniper_bot::dex_monitor::DexConfig {
-        raydium_api_url: config.dex_config.raydium_api_url.clone(),
-        orca_api_url: config.dex_config.orca_api_url.clone(),
-        jupiter_api_url: config.dex_config.jupiter_api_url.clone(),
-        birdeye_api_url: config.dex_config.birdeye_api_url.clone(),
-        solscan_api_url: config.dex_config.solscan_api_url.clone(),
+    let mut blacklist = Blacklist::load(&blacklist_file)?;
+    for address in addresses {
+        if blacklist.add(address)? {
+            println!("Blacklisted {}", address);
+        } else {
+            println!("{} is already blacklisted", address);
+        }
+    }
+    println!("{} now has {} entries", blacklist_file, blacklist.len());
+    Ok(())
+}
+
+// Signal a bot started with a pid_file to shut down gracefully
+fn stop_bot(config_file: &str) -> Result<()> {
+    let pid_file = match load_persistence_config(config_file)?.pid_file {
+        Some(pid_file) => pid_file,
+        None => {
+            println!("No PID file configured (set persistence.pid_file or PID_FILE); cannot find the bot to stop");
+            return Ok(());
+        }
     };
-    let _dex_monitor = DexMonitor::new(
-        dex_config,
-        config.rpc_url.clone(),
-        telegram_sender,
-    );
-    bot.start().await?;
-    
+    let pid = match fs::read_to_string(&pid_file) {
+        Ok(pid) => pid.trim().to_string(),
+        Err(_) => {
+            println!("No PID file at {}; the bot is not running", pid_file);
+            return Ok(());
+        }
+    };
+
+    let status = std::process::Command::new("kill").args(["-TERM", &pid]).status()?;
+    if status.success() {
+        println!("Sent stop signal to bot (pid {})", pid);
+    } else {
+        println!("Failed to signal pid {}; removing stale PID file {}", pid, pid_file);
+        fs::remove_file(&pid_file)?;
+    }
     Ok(())
 }
 
-async fn show_status() -> Result<()> {
-    info!("Bot status: Running");
-    // TODO: Implement status checking
+async fn show_status(config_file: &str) -> Result<()> {
+    let state_file = match load_persistence_config(config_file)?.state_file {
+        Some(state_file) => state_file,
+        None => {
+            println!("No state file configured (set persistence.state_file or STATE_FILE); bot status is unknown");
+            return Ok(());
+        }
+    };
+    let state: SniperState = match persistence::read_json_file(&state_file)? {
+        Some(state) => state,
+        None => {
+            println!("No state file at {}; the bot has not run with this configuration", state_file);
+            return Ok(());
+        }
+    };
+
+    let saved_at = fs::metadata(&state_file)?.modified().ok()
+        .map(|modified| chrono::DateTime::<chrono::Utc>::from(modified).format("%Y-%m-%d %H:%M:%S UTC").to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    let last_snipe = if state.last_snipe_time == 0 {
+        "never".to_string()
+    } else {
+        chrono::DateTime::<chrono::Utc>::from_timestamp(state.last_snipe_time as i64, 0)
+            .map(|time| time.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+            .unwrap_or_else(|| state.last_snipe_time.to_string())
+    };
+
+    println!("{:<20} {}", "State file", state_file);
+    println!("{:<20} {}", "Saved at", saved_at);
+    println!("{:<20} {}", "Status", if state.is_running { "Running (as of last save)" } else { "Stopped" });
+    println!("{:<20} {}", "Total trades", state.total_trades);
+    println!("{:<20} {}", "Successful trades", state.successful_trades);
+    println!("{:<20} {}", "Failed trades", state.failed_trades);
+    println!("{:<20} {:.6} SOL", "Total profit", state.total_profit);
+    let unrealized_pnl: f64 = state.positions.values().filter_map(|position| position.unrealized_pnl(position.last_price)).sum();
+    println!("{:<20} {:.6} SOL", "Unrealized PnL", unrealized_pnl);
+    println!("{:<20} {}", "Active monitors", state.active_monitors);
+    println!("{:<20} {}", "Last snipe", last_snipe);
+    println!("{:<20} {}", "Open positions", state.positions.len());
+    println!("{:<20} {}", "Auto-buy", if state.buying_paused { "Paused (below capital floor)" } else { "Active" });
+    let mut rpc_lags: Vec<_> = state.rpc_slot_lag.iter().collect();
+    rpc_lags.sort_by(|a, b| a.0.cmp(b.0));
+    for (rpc, lag) in rpc_lags {
+        println!("{:<20} {} slots behind", format!("RPC {}", rpc), lag);
+    }
+    for endpoint in &state.rpc_health {
+        println!("{:<20} {}{} errors in {} requests, {} in a row", format!("RPC {}", endpoint.endpoint),
+                 if endpoint.active { "active, " } else { "" }, endpoint.failures, endpoint.requests, endpoint.consecutive_failures);
+    }
+    let mut strategies: Vec<_> = state.strategy_profit.iter().collect();
+    strategies.sort_by(|a, b| a.0.cmp(b.0));
+    for (strategy, profit) in strategies {
+        let open = state.positions.values().filter(|position| position.strategy.as_deref() == Some(strategy.as_str())).count();
+        println!("{:<20} {:.6} SOL, {} open", format!("Strategy {}", strategy), profit, open);
+    }
     Ok(())
 }
 
@@ -238,10 +839,79 @@ async fn check_balance(wallet_index: usize) -> Result<()> {
     Ok(())
 }
 
+async fn list_wallets() -> Result<()> {
+    let config = create_default_config();
+    let wallet_entries = config.wallets.clone();
+    let min_sol_balance = config.min_sol_balance;
+
+    let bot = SolanaSniperBot::new(config)?;
+    bot.verify_cluster()?;
+
+    let mut total = 0.0;
+    println!("{:<6} {:<44} {:>16}  Path", "Index", "Pubkey", "Balance (SOL)");
+    for (wallet_index, (pubkey, balance)) in bot.wallet_balances().await.into_iter().enumerate() {
+        let wallet = &wallet_entries[wallet_index];
+        let mut notes = Vec::new();
+        if wallet.is_shadow() {
+            notes.push("shadow".to_string());
+        }
+        let balance = match balance {
+            Ok(balance) => {
+                total += balance;
+                if balance < min_sol_balance {
+                    notes.push(format!("below min_sol_balance {}", min_sol_balance));
+                }
+                format!("{:.6}", balance)
+            }
+            Err(e) => {
+                notes.push(format!("balance unavailable: {}", e));
+                "-".to_string()
+            }
+        };
+        let notes = if notes.is_empty() { String::new() } else { format!(" ({})", notes.join(", ")) };
+        println!("{:<6} {:<44} {:>16}  {}{}", wallet_index, pubkey, balance, wallet.path(), notes);
+    }
+    println!("{:<6} {:<44} {:>16.6}", "Total", "", total);
+    Ok(())
+}
+
+async fn distribute(from_index: usize, amount: FundingAmount) -> Result<()> {
+    let config = create_default_config();
+
+    let bot = SolanaSniperBot::new(config)?;
+    bot.verify_cluster()?;
+    let funded = bot.distribute_sol(from_index, amount).await?;
+
+    for (wallet_index, signature) in &funded {
+        println!("Wallet {}: {}", wallet_index, signature);
+    }
+    println!("Funded {} wallets", funded.len());
+    Ok(())
+}
+
+async fn sweep(to_index: usize, sell_tokens: bool) -> Result<()> {
+    let config = create_default_config();
+
+    let bot = SolanaSniperBot::new(config)?;
+    bot.verify_cluster()?;
+    let report = bot.consolidate_sol(to_index, sell_tokens).await?;
+    bot.flush().await?;
+
+    for (wallet_index, sol, signature) in &report.transfers {
+        println!("Wallet {}: {:.6} SOL ({})", wallet_index, sol, signature);
+    }
+    if sell_tokens {
+        println!("Sold {} token balances ({} failed)", report.tokens_sold, report.token_sell_failures);
+    }
+    println!("Swept {:.6} SOL from {} wallets into wallet {}", report.total_sol, report.transfers.len(), to_index);
+    Ok(())
+}
+
 async fn feed_wallet(from_index: usize, to_index: usize, amount: f64) -> Result<()> {
     let config = create_default_config();
     
     let bot = SolanaSniperBot::new(config)?;
+    bot.verify_cluster()?;
     let signature = bot.feed_wallet(from_index, to_index, amount).await?;
     
     info!("Fed wallet {} with {} SOL: {}", to_index, amount, signature);
@@ -252,6 +922,7 @@ async fn snipe_token(token_address: &str, wallet_index: usize, amount: f64) -> R
     let config = create_default_config();
     
     let bot = SolanaSniperBot::new(config)?;
+    bot.verify_cluster()?;
     
     match bot.snipe_token(wallet_index, token_address, amount).await {
         Ok(signature) => {
@@ -263,4 +934,269 @@ async fn snipe_token(token_address: &str, wallet_index: usize, amount: f64) -> R
     }
     
     Ok(())
-}
\ No newline at end of file
+}
+
+async fn analyze_token(token_address: &str) -> Result<()> {
+    let config = create_default_config();
+
+    let bot = SolanaSniperBot::new(config)?;
+    bot.verify_cluster()?;
+    let analysis = bot.analyze_token_detailed(token_address).await?;
+    print!("{}", analysis);
+    Ok(())
+}
+
+fn token_balance(token_address: &str, wallet_index: usize) -> Result<()> {
+    let config = create_default_config();
+
+    let bot = SolanaSniperBot::new(config)?;
+    bot.verify_cluster()?;
+    let balance = bot.get_token_balance(wallet_index, token_address)?;
+    println!("Wallet {} holds {} of {}", wallet_index, balance, token_address);
+    Ok(())
+}
+
+// What the sell command sells: a token amount (None for everything held), or enough to receive a fixed amount
+enum SellAmount {
+    Tokens(Option<u64>),
+    ExactOut(f64),
+    RecoverInitial,
+}
+
+async fn sell_token(token_address: &str, wallet_index: usize, amount: SellAmount) -> Result<()> {
+    let config = create_default_config();
+    
+    let bot = SolanaSniperBot::new(config)?;
+    bot.verify_cluster()?;
+    
+    let result = match amount {
+        SellAmount::Tokens(amount) => {
+            // Without an explicit amount, sell everything the wallet holds
+            let amount = match amount {
+                Some(amount) => amount,
+                None => bot.get_raw_token_balance(wallet_index, token_address)?,
+            };
+            if amount == 0 {
+                error!("Wallet {} holds no {} to sell", wallet_index, token_address);
+                return Ok(());
+            }
+            bot.sell_token(wallet_index, token_address, amount as f64).await
+        }
+        SellAmount::ExactOut(proceeds) => bot.sell_for_proceeds(wallet_index, token_address, proceeds).await,
+        SellAmount::RecoverInitial => bot.recover_initial(wallet_index, token_address).await,
+    };
+    
+    match result {
+        Ok(signature) => {
+            info!("Successfully sold token: {}", signature);
+            println!("{}", signature);
+        }
+        Err(e) => {
+            error!("Failed to sell token: {}", e);
+        }
+    }
+    
+    // One-shot command, so write out the buffered trade record now
+    bot.flush().await?;
+    Ok(())
+}
+
+async fn sell_all() -> Result<()> {
+    let config = create_default_config();
+    
+    let bot = SolanaSniperBot::new(config)?;
+    bot.verify_cluster()?;
+    
+    let results = bot.sell_all().await;
+    if results.is_empty() {
+        println!("No open positions to sell");
+    }
+    for (position, result) in &results {
+        match result {
+            Ok(signature) => println!("Sold {} from wallet {}: {}", position.token_address, position.wallet_index, signature),
+            Err(e) => println!("Failed to sell {} from wallet {}: {}", position.token_address, position.wallet_index, e),
+        }
+    }
+    let failed = results.iter().filter(|(_, result)| result.is_err()).count();
+    println!("Sold {} of {} positions", results.len() - failed, results.len());
+    
+    // One-shot command, so write out the buffered trade records now
+    bot.flush().await?;
+    Ok(())
+}
+
+// Sweep one wallet, or with no index every wallet maintenance_wallets allows
+async fn sweep_dust(wallet_index: Option<usize>, threshold: Option<f64>) -> Result<()> {
+    let config = create_default_config();
+    
+    let bot = SolanaSniperBot::new(config)?;
+    bot.verify_cluster()?;
+    
+    let threshold = threshold.unwrap_or_else(|| bot.dust_threshold_sol());
+    let wallet_indices = match wallet_index {
+        Some(wallet_index) => vec![wallet_index],
+        None => bot.maintenance_wallet_indices(),
+    };
+    for wallet_index in wallet_indices {
+        let report = bot.sweep_dust(wallet_index, threshold).await?;
+        println!("Wallet {}:", wallet_index);
+        println!("Swapped {} dust tokens to SOL", report.swept_tokens.len());
+        println!("Closed {} empty token accounts", report.closed_accounts);
+        println!("Skipped {} tokens with no route", report.skipped_no_route.len());
+        println!("Reclaimed ~{:.6} SOL", report.reclaimed_sol);
+    }
+    Ok(())
+}
+
+// Start (or end) of a UTC day as a unix timestamp
+fn parse_date(date: &str, end_of_day: bool) -> Result<u64> {
+    let date = chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")?;
+    let time = if end_of_day { date.and_hms_opt(23, 59, 59) } else { date.and_hms_opt(0, 0, 0) };
+    Ok(time.unwrap().and_utc().timestamp() as u64)
+}
+
+fn format_timestamp(timestamp: u64) -> String {
+    chrono::DateTime::<chrono::Utc>::from_timestamp(timestamp as i64, 0)
+        .map(|time| time.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+        .unwrap_or_else(|| timestamp.to_string())
+}
+
+// Closed positions sold within [from, to] by the given wallet, each bound optional
+fn select_closed_positions(closed_positions: &[ClosedPosition], from: Option<u64>, to: Option<u64>, wallet_index: Option<usize>) -> Vec<&ClosedPosition> {
+    closed_positions.iter()
+        .filter(|closed| from.is_none_or(|from| closed.closed_at >= from))
+        .filter(|closed| to.is_none_or(|to| closed.closed_at <= to))
+        .filter(|closed| wallet_index.is_none_or(|wallet_index| closed.wallet_index == wallet_index))
+        .collect()
+}
+
+// Header and one row per closed position, with timestamps in UTC and returns left blank where unknown
+fn closed_positions_csv(closed_positions: &[&ClosedPosition]) -> String {
+    let mut csv = String::from("opened_at,closed_at,wallet,token,token_amount,sol_in,sol_out,fees_sol,realized_pnl_sol,gross_return_pct,net_return_pct,signature\n");
+    let percent = |value: Option<f64>| value.map(|value| format!("{:.4}", value)).unwrap_or_default();
+    for closed in closed_positions {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{:.9},{:.9},{:.9},{:.9},{},{},{}\n",
+            format_timestamp(closed.opened_at), format_timestamp(closed.closed_at), closed.wallet_index,
+            closed.token_address, closed.token_amount, closed.sol_in, closed.sol_out, closed.fees_sol,
+            closed.realized_pnl, percent(closed.gross_return_percent()), percent(closed.net_return_percent()), closed.signature,
+        ));
+    }
+    csv
+}
+
+fn export_pnl(config_file: &str, output: Option<&str>, from: Option<u64>, to: Option<u64>, wallet_index: Option<usize>) -> Result<()> {
+    let closed_positions_file = match load_persistence_config(config_file)?.closed_positions_file {
+        Some(closed_positions_file) => closed_positions_file,
+        None => {
+            println!("No closed positions file configured (set persistence.closed_positions_file or CLOSED_POSITIONS_FILE)");
+            return Ok(());
+        }
+    };
+    let closed_positions: Vec<ClosedPosition> = persistence::read_json_lines(&closed_positions_file)?;
+
+    let selected = select_closed_positions(&closed_positions, from, to, wallet_index);
+    let csv = closed_positions_csv(&selected);
+
+    // Only positions with both returns, so the two averages compare like with like
+    let (gross_returns, net_returns): (Vec<f64>, Vec<f64>) = selected.iter()
+        .filter_map(|closed| Some((closed.gross_return_percent()?, closed.net_return_percent()?)))
+        .unzip();
+    if !gross_returns.is_empty() {
+        let average = |returns: &[f64]| returns.iter().sum::<f64>() / returns.len() as f64;
+        let (gross_return, net_return) = (average(&gross_returns), average(&net_returns));
+        info!("Across {} positions with known prices: average gross return {:+.2}%, net {:+.2}% ({:.2} points lost to fees and slippage)",
+              gross_returns.len(), gross_return, net_return, gross_return - net_return);
+    }
+
+    match output {
+        Some(output) => {
+            fs::write(output, csv)?;
+            info!("Exported {} closed positions to {}", selected.len(), output);
+        }
+        None => print!("{}", csv),
+    }
+    Ok(())
+}
+
+fn import_wallet(wallet_name: &str, mnemonic: Option<&str>, passphrase: Option<&str>, account_index: u32) -> Result<()> {
+    if !Path::new("wallets").exists() {
+        fs::create_dir("wallets")?;
+    }
+    
+    // Prefer the environment or a prompt so the phrase stays out of shell history
+    let mnemonic = match mnemonic.map(str::to_string).or_else(|| env::var("MNEMONIC").ok()) {
+        Some(mnemonic) => zeroize::Zeroizing::new(mnemonic),
+        None => {
+            print!("Seed phrase: ");
+            std::io::stdout().flush()?;
+            let mut mnemonic = zeroize::Zeroizing::new(String::new());
+            std::io::stdin().read_line(&mut mnemonic)?;
+            mnemonic
+        }
+    };
+    let passphrase = zeroize::Zeroizing::new(
+        passphrase.map(str::to_string).or_else(|| env::var("MNEMONIC_PASSPHRASE").ok()).unwrap_or_default(),
+    );
+    
+    let pubkey = SolanaSniperBot::import_wallet_from_mnemonic(wallet_name, &mnemonic, &passphrase, account_index)?;
+    info!("Imported wallet {} (account {}): {}", wallet_name, account_index, pubkey);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn closed_position(wallet_index: usize, token_address: &str, closed_at: u64) -> ClosedPosition {
+        ClosedPosition {
+            wallet_index,
+            token_address: token_address.to_string(),
+            opened_at: closed_at - 3_600,
+            closed_at,
+            token_amount: 1_000_000,
+            sol_in: 0.5,
+            sol_out: 0.6,
+            fees_sol: 0.01,
+            realized_pnl: 0.09,
+            signature: format!("sig-{}", token_address),
+            strategy: None,
+            entry_price: 0.0005,
+            exit_price: 0.0006,
+        }
+    }
+
+    #[test]
+    fn csv_has_a_row_per_closed_position() {
+        let closed_positions = [closed_position(0, "mint1", 1_700_003_600), closed_position(1, "mint2", 1_700_007_200)];
+        let selected = select_closed_positions(&closed_positions, None, None, None);
+        let csv = closed_positions_csv(&selected);
+        let lines: Vec<&str> = csv.lines().collect();
+
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], "opened_at,closed_at,wallet,token,token_amount,sol_in,sol_out,fees_sol,realized_pnl_sol,gross_return_pct,net_return_pct,signature");
+        assert_eq!(lines[1], "2023-11-14T22:13:20Z,2023-11-14T23:13:20Z,0,mint1,1000000,0.500000000,0.600000000,0.010000000,0.090000000,20.0000,18.0000,sig-mint1");
+        assert!(lines[2].starts_with("2023-11-14T23:13:20Z,2023-11-15T00:13:20Z,1,mint2,"));
+    }
+
+    #[test]
+    fn unknown_returns_are_left_blank() {
+        let mut closed = closed_position(0, "mint", 1_700_003_600);
+        closed.entry_price = 0.0;
+        let csv = closed_positions_csv(&[&closed]);
+        assert!(csv.lines().nth(1).unwrap().ends_with(",0.090000000,,18.0000,sig-mint"));
+    }
+
+    #[test]
+    fn export_filters_by_date_range_and_wallet() {
+        let closed_positions = [
+            closed_position(0, "early", 1_700_000_000),
+            closed_position(0, "inside", 1_700_050_000),
+            closed_position(1, "other-wallet", 1_700_050_000),
+            closed_position(0, "late", 1_700_100_000),
+        ];
+        let selected = select_closed_positions(&closed_positions, Some(1_700_010_000), Some(1_700_090_000), Some(0));
+        let tokens: Vec<&str> = selected.iter().map(|closed| closed.token_address.as_str()).collect();
+        assert_eq!(tokens, ["inside"]);
+    }
+}
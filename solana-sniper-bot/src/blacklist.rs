@@ -0,0 +1,106 @@
+use anyhow::{anyhow, Result};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashSet;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::str::FromStr;
+
+// One address per line, ignoring blank lines and anything after a '#'
+pub(crate) fn parse_address_lines(contents: &str) -> HashSet<String> {
+    contents.lines()
+        .map(|line| line.split('#').next().unwrap_or_default().trim())
+        .filter(|line| !line.is_empty())
+        .map(|line| line.to_string())
+        .collect()
+}
+
+// Token mints and creator addresses to never buy, one base58 address per line ('#' starts a comment)
+pub struct Blacklist {
+    path: String,
+    entries: HashSet<String>,
+}
+
+impl Blacklist {
+    // A missing file is an empty blacklist
+    pub fn load(path: &str) -> Result<Self> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(anyhow!("Failed to read blacklist {}: {}", path, e)),
+        };
+        Ok(Blacklist { path: path.to_string(), entries: parse_address_lines(&contents) })
+    }
+
+    pub fn is_blacklisted(&self, address: &str) -> bool {
+        self.entries.contains(address)
+    }
+
+    // Add an address and append it to the file; returns false if it was already listed
+    pub fn add(&mut self, address: &str) -> Result<bool> {
+        Pubkey::from_str(address).map_err(|_| anyhow!("{} is not a valid base58 address", address))?;
+        if self.is_blacklisted(address) {
+            return Ok(false);
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        writeln!(file, "{}", address)?;
+        self.entries.insert(address.to_string());
+        Ok(true)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_path(name: &str) -> String {
+        std::env::temp_dir().join(format!("{}-{}.txt", name, std::process::id())).to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn matches_listed_addresses_exactly() {
+        let mint = Pubkey::new_unique().to_string();
+        let path = scratch_path("blacklist-exact");
+        std::fs::write(&path, format!("# rugs\n\n  {}  # dev wallet\n", mint)).unwrap();
+        let blacklist = Blacklist::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(blacklist.is_blacklisted(&mint));
+        assert!(!blacklist.is_blacklisted(&mint.to_lowercase()));
+        assert!(!blacklist.is_blacklisted(&mint[..mint.len() - 1]));
+        assert!(!blacklist.is_blacklisted(&format!("{}1", mint)));
+        assert_eq!(blacklist.len(), 1);
+    }
+
+    #[test]
+    fn added_addresses_are_listed_and_saved() {
+        let mint = Pubkey::new_unique().to_string();
+        let path = scratch_path("blacklist-add");
+        let mut blacklist = Blacklist::load(&path).unwrap();
+        assert!(blacklist.is_empty());
+
+        assert!(blacklist.add(&mint).unwrap());
+        assert!(!blacklist.add(&mint).unwrap());
+        assert!(blacklist.is_blacklisted(&mint));
+        let reloaded = Blacklist::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(reloaded.is_blacklisted(&mint));
+        assert_eq!(reloaded.len(), 1);
+    }
+
+    #[test]
+    fn invalid_addresses_are_not_added() {
+        let path = scratch_path("blacklist-invalid");
+        let mut blacklist = Blacklist::load(&path).unwrap();
+        assert!(blacklist.add("not-an-address").is_err());
+        assert!(blacklist.is_empty());
+        assert!(!std::path::Path::new(&path).exists());
+    }
+}
@@ -0,0 +1,61 @@
+use anyhow::{anyhow, Result};
+
+const MAINNET_BETA_GENESIS_HASH: &str = "5eykt4UsFv8P8NJdTREpY1vzqKqZKvdpKuc147dw2N9d";
+const DEVNET_GENESIS_HASH: &str = "EtWTRABZaYq6iMfeYKouRu166VU2xqa1wcaWoxPkrZBG";
+const TESTNET_GENESIS_HASH: &str = "4uhcVJyU9pJkvQyS88uRDiswHXSCkY3zQawwpjk2NsNY";
+
+// Genesis hash for a cluster name; anything that isn't a known name is taken as a raw genesis hash
+pub fn expected_genesis_hash(cluster: &str) -> &str {
+    match cluster {
+        "mainnet" | "mainnet-beta" => MAINNET_BETA_GENESIS_HASH,
+        "devnet" => DEVNET_GENESIS_HASH,
+        "testnet" => TESTNET_GENESIS_HASH,
+        other => other,
+    }
+}
+
+fn cluster_name(genesis_hash: &str) -> &str {
+    match genesis_hash {
+        MAINNET_BETA_GENESIS_HASH => "mainnet-beta",
+        DEVNET_GENESIS_HASH => "devnet",
+        TESTNET_GENESIS_HASH => "testnet",
+        _ => "an unknown cluster",
+    }
+}
+
+// Fail if the RPC's genesis hash isn't the expected cluster's
+pub fn verify_genesis_hash(expected_cluster: &str, actual_genesis_hash: &str) -> Result<()> {
+    let expected = expected_genesis_hash(expected_cluster);
+    if expected != actual_genesis_hash {
+        return Err(anyhow!(
+            "RPC is on {} (genesis hash {}), but expected_cluster is {} (genesis hash {})",
+            cluster_name(actual_genesis_hash), actual_genesis_hash, expected_cluster, expected
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matching_cluster_passes() {
+        assert!(verify_genesis_hash("mainnet-beta", MAINNET_BETA_GENESIS_HASH).is_ok());
+        assert!(verify_genesis_hash("mainnet", MAINNET_BETA_GENESIS_HASH).is_ok());
+        assert!(verify_genesis_hash(DEVNET_GENESIS_HASH, DEVNET_GENESIS_HASH).is_ok());
+    }
+
+    #[test]
+    fn mismatched_cluster_fails_naming_both() {
+        let error = verify_genesis_hash("mainnet-beta", DEVNET_GENESIS_HASH).unwrap_err().to_string();
+        assert!(error.contains("RPC is on devnet"), "{}", error);
+        assert!(error.contains(MAINNET_BETA_GENESIS_HASH), "{}", error);
+    }
+
+    #[test]
+    fn unknown_genesis_hash_fails() {
+        let error = verify_genesis_hash("testnet", "unknown-hash").unwrap_err().to_string();
+        assert!(error.contains("an unknown cluster"), "{}", error);
+    }
+}
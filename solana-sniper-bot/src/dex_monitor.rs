@@ -1,20 +1,65 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use base64::Engine;
+use futures_util::StreamExt;
 use log::{debug, info, error, warn};
 use serde::{Deserialize, Serialize};
 use solana_client::{
-    rpc_client::RpcClient,
-    pubsub_client::PubsubClient,
-    rpc_config::{RpcTransactionLogsFilter, RpcTransactionLogsConfig, RpcTransactionConfig},
+    client_error::{ClientError, ClientErrorKind},
+    rpc_client::GetConfirmedSignaturesForAddress2Config,
+    rpc_request::{RpcError, RpcResponseErrorData},
+    nonblocking::pubsub_client::PubsubClient,
+    rpc_config::{RpcSendTransactionConfig, RpcTransactionLogsFilter, RpcTransactionLogsConfig, RpcTransactionConfig},
+    rpc_response::RpcConfirmedTransactionStatusWithSignature,
 };
-use solana_sdk::{signature::{Keypair, Signature}};
-use solana_transaction_status::{EncodedConfirmedTransactionWithStatusMeta, UiTransactionEncoding};
-use std::{str::FromStr};
-use tokio::sync::mpsc;
-use crate::websocket_monitor::{WebSocketMessage, PoolUpdate};
+use solana_sdk::{
+    address_lookup_table::{state::AddressLookupTable, AddressLookupTableAccount},
+    commitment_config::CommitmentConfig,
+    compute_budget::{self, ComputeBudgetInstruction},
+    instruction::{AccountMeta, Instruction},
+    message::{v0, Message, VersionedMessage},
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+    transaction::VersionedTransaction,
+};
+use solana_transaction_status::{EncodedConfirmedTransactionWithStatusMeta, UiPartiallyDecodedInstruction, UiTransactionEncoding};
+use std::{collections::{HashMap, HashSet, VecDeque}, str::FromStr, sync::Arc, time::{Duration, Instant}};
+use tokio::{sync::mpsc, task::JoinHandle};
+use crate::websocket_monitor::{WebSocketMessage, PoolDetails, PoolUpdate, TokenListing, UNKNOWN_LIQUIDITY};
 use crate::telegram::TelegramSender;
+use crate::error::SniperError;
+use crate::persistence::NotifiedPools;
+use crate::retry::RetryPolicy;
+use crate::secrets::SecretString;
+use crate::rpc_failover::RpcFailover;
+use crate::priority_fee;
+use crate::token_accounts::{self, ASSOCIATED_TOKEN_PROGRAM_ID, TOKEN_2022_PROGRAM_ID};
+use crate::MonitoringConfig;
 
-// const RAYDIUM_LIQUIDITY_POOL_V4_PROGRAM_ID: &str = "675kPX9MHTjS2zt1qfr1NYHuzeNSfTFE9yPZVvTZ6Qko";
+// Raydium AMM v4 on mainnet-beta
 const RAYDIUM_LIQUIDITY_POOL_V4_PROGRAM_ID: &str = "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8";
+const RAYDIUM_AUTHORITY_V4: &str = "5Q544fKrFoe6tsEbD7S8EmxGTJYAKtTVhAW5Q5pge4j1";
+const ORCA_WHIRLPOOL_PROGRAM_ID: &str = "whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc";
+// Raydium AMM v4 instruction tags of initialize and initialize2
+const RAYDIUM_INITIALIZE: u8 = 0;
+const RAYDIUM_INITIALIZE2: u8 = 1;
+const RAYDIUM_WITHDRAW: u8 = 4;
+// Anchor discriminators of the Whirlpool initialize_pool and initialize_pool_v2 instructions
+const ORCA_INITIALIZE_POOL: [u8; 8] = [95, 180, 10, 172, 84, 174, 232, 40];
+const ORCA_INITIALIZE_POOL_V2: [u8; 8] = [207, 45, 87, 242, 27, 63, 204, 67];
+pub const SOL_MINT: &str = "So11111111111111111111111111111111111111112";
+const USDC_MINT: &str = "EPjFWdd5AufqSSo4ZkjcPLZx2s8sVDHDiPHw6hUMHLv1";
+// Small amount used to probe a token with a simulated buy and sell
+const PROBE_LAMPORTS: u64 = 10_000_000;
+const CONFIRMATION_POLL_INTERVAL: Duration = Duration::from_millis(500);
+// How long a broadcast transaction without a deadline is polled for, about a blockhash's lifetime
+const BROADCAST_CONFIRMATION_TIMEOUT: Duration = Duration::from_secs(90);
+// Most signatures getSignaturesForAddress returns per call, and how many such pages to walk back through
+// looking for a mint's first transaction
+const SIGNATURES_PAGE_SIZE: usize = 1_000;
+const MINT_AGE_SCAN_PAGES: usize = 5;
+// Pool transactions fetched at once when scanning a pool's history
+const POOL_SCAN_CONCURRENCY: usize = 8;
 
 #[derive(Debug, Clone)]
 pub struct DexConfig {
@@ -23,6 +68,11 @@ pub struct DexConfig {
     pub jupiter_api_url: String,
     pub birdeye_api_url: String,
     pub solscan_api_url: String,
+    pub birdeye_api_key: SecretString,
+    pub metadata_retry: RetryPolicy,
+    pub metadata_cache_ttl: Duration,
+    pub jupiter_tokens_api_url: String,
+    pub jupiter_api_key: SecretString,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -39,6 +89,23 @@ pub struct PoolInfo {
     pub price_change_24h: f64,
 }
 
+// Which side of a Jupiter quote is fixed: the amount spent (ExactIn) or the amount received (ExactOut)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum SwapMode {
+    #[default]
+    ExactIn,
+    ExactOut,
+}
+
+impl SwapMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SwapMode::ExactIn => "ExactIn",
+            SwapMode::ExactOut => "ExactOut",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SwapInfo {
     pub input_token: String,
@@ -49,6 +116,41 @@ pub struct SwapInfo {
     pub route: Vec<String>,
     pub price_impact: f64,
     pub fee_amount: u64,
+    // Raw Jupiter quote, posted back verbatim to build the swap transaction
+    #[serde(default)]
+    pub quote_response: serde_json::Value,
+}
+
+impl SwapInfo {
+    // Output tokens per input token implied by the quote
+    pub fn implied_price(&self) -> f64 {
+        if self.input_amount == 0 {
+            return 0.0;
+        }
+        self.output_amount as f64 / self.input_amount as f64
+    }
+}
+
+// Relative difference between two quotes' implied prices, as a fraction of the first
+pub fn quote_price_divergence(quote: &SwapInfo, other: &SwapInfo) -> f64 {
+    let price = quote.implied_price();
+    if price <= 0.0 {
+        return f64::INFINITY;
+    }
+    (price - other.implied_price()).abs() / price
+}
+
+// Volume left after dropping the share of trades made by the `top_traders` most active addresses,
+// which is where self-trading to fake volume concentrates
+pub fn wash_discounted_volume(raw_volume: f64, trades_by_trader: &HashMap<String, usize>, top_traders: usize) -> f64 {
+    let total_trades: usize = trades_by_trader.values().sum();
+    if total_trades == 0 {
+        return 0.0;
+    }
+    let mut counts: Vec<usize> = trades_by_trader.values().copied().collect();
+    counts.sort_unstable_by(|a, b| b.cmp(a));
+    let top_trades: usize = counts.iter().take(top_traders).sum();
+    raw_volume * (total_trades - top_trades) as f64 / total_trades as f64
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -61,42 +163,224 @@ pub struct TokenMetadata {
     pub market_cap: f64,
     pub price: f64,
     pub volume_24h: f64,
+    #[serde(default)]
+    pub sell_volume_24h: f64,
     pub holders: u64,
     pub creator: String,
     pub is_verified: bool,
 }
 
+// Entry of Jupiter's recently-listed tokens endpoint
+#[derive(Debug, Clone, Deserialize)]
+struct JupiterNewToken {
+    mint: String,
+    #[serde(default)]
+    symbol: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    created_at: String,
+}
+
+// One way a program creates pools: the log line that flags it, the instruction data prefix
+// that identifies it, and where the pool and its mints sit in the instruction's accounts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PoolCreationVariant {
+    pub name: String,
+    pub log_marker: String,
+    pub discriminator: Vec<u8>,
+    pub pool_account: usize,
+    pub mint_a_account: usize,
+    pub mint_b_account: usize,
+    #[serde(default)]
+    pub creator_account: Option<usize>,
+    #[serde(default)]
+    pub lp_mint_account: Option<usize>,
+}
+
+impl PoolCreationVariant {
+    fn matches(&self, data: &[u8]) -> bool {
+        data.starts_with(&self.discriminator)
+    }
+
+    fn max_account_index(&self) -> usize {
+        self.pool_account.max(self.mint_a_account).max(self.mint_b_account)
+    }
+}
+
+pub fn default_raydium_pool_variants() -> Vec<PoolCreationVariant> {
+    vec![
+        PoolCreationVariant {
+            name: "initialize2".to_string(),
+            log_marker: "initialize2".to_string(),
+            discriminator: vec![RAYDIUM_INITIALIZE2],
+            pool_account: 4,
+            mint_a_account: 8,
+            mint_b_account: 9,
+            creator_account: Some(17),
+            lp_mint_account: Some(7),
+        },
+        PoolCreationVariant {
+            name: "initialize".to_string(),
+            log_marker: "initialize:".to_string(),
+            discriminator: vec![RAYDIUM_INITIALIZE],
+            pool_account: 3,
+            mint_a_account: 7,
+            mint_b_account: 8,
+            creator_account: Some(16),
+            lp_mint_account: Some(6),
+        },
+    ]
+}
+
+pub fn default_orca_pool_variants() -> Vec<PoolCreationVariant> {
+    vec![
+        PoolCreationVariant {
+            name: "initialize_pool".to_string(),
+            log_marker: "Instruction: InitializePool".to_string(),
+            discriminator: ORCA_INITIALIZE_POOL.to_vec(),
+            pool_account: 4,
+            mint_a_account: 1,
+            mint_b_account: 2,
+            creator_account: Some(3),
+            lp_mint_account: None,
+        },
+        PoolCreationVariant {
+            name: "initialize_pool_v2".to_string(),
+            log_marker: "Instruction: InitializePoolV2".to_string(),
+            discriminator: ORCA_INITIALIZE_POOL_V2.to_vec(),
+            pool_account: 6,
+            mint_a_account: 1,
+            mint_b_account: 2,
+            creator_account: Some(5),
+            lp_mint_account: None,
+        },
+    ]
+}
+
+// A DEX program to watch on-chain and how to turn its pool-creation transactions into pool updates
+#[derive(Clone, Copy)]
+struct PoolProgram {
+    name: &'static str,
+    program_id: &'static str,
+    decode: fn(&EncodedConfirmedTransactionWithStatusMeta, &[PoolCreationVariant], bool) -> Result<Vec<WebSocketMessage>>,
+}
+
+const RAYDIUM_POOL_PROGRAM: PoolProgram = PoolProgram {
+    name: "Raydium",
+    program_id: RAYDIUM_LIQUIDITY_POOL_V4_PROGRAM_ID,
+    decode: decode_transaction,
+};
+
+const ORCA_POOL_PROGRAM: PoolProgram = PoolProgram {
+    name: "Orca",
+    program_id: ORCA_WHIRLPOOL_PROGRAM_ID,
+    decode: decode_orca_transaction,
+};
+
 pub struct DexMonitor {
-    client: RpcClient,
+    config: DexConfig,
+    // Shared with the bot so both reuse the same HTTP connections
+    rpc: Arc<RpcFailover>,
+    http: reqwest::Client,
     telegram_sender: TelegramSender,
+    // Also send every signed transaction to the endpoints other than the active one
+    broadcast_to_all_rpcs: bool,
+    // Endpoints currently too far behind to be worth broadcasting to
+    lagging_rpc_urls: std::sync::RwLock<HashSet<String>>,
+    // Pools already announced on Telegram, shared by every on-chain monitor
+    notified_pools: Arc<std::sync::Mutex<NotifiedPools>>,
+    // Token metadata by mint with when it was fetched, reused for metadata_cache_ttl
+    metadata_cache: std::sync::Mutex<HashMap<String, (TokenMetadata, Instant)>>,
+    // Address lookup tables swaps were compiled against. Tables only ever grow, so an entry is refetched once a
+    // swap indexes past its end.
+    lookup_tables: std::sync::Mutex<HashMap<Pubkey, AddressLookupTableAccount>>,
 }
 
 impl DexMonitor {
-    pub fn new(_config: DexConfig, rpc_url: String, telegram_sender: TelegramSender) -> Self {
-        let client = RpcClient::new(rpc_url);
-        DexMonitor { client, telegram_sender }
+    pub fn new(config: DexConfig, rpc: Arc<RpcFailover>, telegram_sender: TelegramSender) -> Self {
+        DexMonitor {
+            config,
+            rpc,
+            http: reqwest::Client::new(),
+            telegram_sender,
+            broadcast_to_all_rpcs: false,
+            lagging_rpc_urls: std::sync::RwLock::new(HashSet::new()),
+            notified_pools: Arc::new(std::sync::Mutex::new(NotifiedPools::load(None, 0))),
+            metadata_cache: std::sync::Mutex::new(HashMap::new()),
+            lookup_tables: std::sync::Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn set_notified_pools(&mut self, notified_pools: NotifiedPools) {
+        self.notified_pools = Arc::new(std::sync::Mutex::new(notified_pools));
+    }
+
+    pub fn set_lagging_rpc_urls(&self, rpc_urls: HashSet<String>) {
+        *self.lagging_rpc_urls.write().unwrap() = rpc_urls;
+    }
+
+    // Also send every transaction to the other rpc_url endpoints, keeping the active one for confirmation and everything else
+    pub fn set_broadcast_to_all_rpcs(&mut self, broadcast_to_all_rpcs: bool) {
+        self.broadcast_to_all_rpcs = broadcast_to_all_rpcs;
+    }
+
+    pub async fn monitor_raydium_onchain(&self, tx: mpsc::Sender<WebSocketMessage>, monitoring: MonitoringConfig) -> Result<JoinHandle<()>> {
+        let variants = monitoring.raydium_pool_variants.clone();
+        self.monitor_program_onchain(RAYDIUM_POOL_PROGRAM, variants, tx, monitoring).await
+    }
+
+    pub async fn monitor_orca(&self, tx: mpsc::Sender<WebSocketMessage>, monitoring: MonitoringConfig) -> Result<JoinHandle<()>> {
+        let variants = monitoring.orca_pool_variants.clone();
+        self.monitor_program_onchain(ORCA_POOL_PROGRAM, variants, tx, monitoring).await
     }
 
-    pub async fn monitor_raydium_onchain(&self, tx: mpsc::Sender<WebSocketMessage>) -> Result<()> {
-        let rpc_ws_url = self.client.url().replace("http", "ws");
-        info!("Starting on-chain monitoring for Raydium pools at {}", rpc_ws_url);
+    async fn monitor_program_onchain(&self, program: PoolProgram, variants: Vec<PoolCreationVariant>, tx: mpsc::Sender<WebSocketMessage>, monitoring: MonitoringConfig) -> Result<JoinHandle<()>> {
+        let program_id = Pubkey::from_str(program.program_id)
+            .map_err(|e| anyhow!("Invalid {} program ID {}: {}", program.name, program.program_id, e))?;
+        info!("Starting on-chain monitoring for {} pools (program {})", program.name, program_id);
         let sender = tx.clone();
         let telegram_sender = self.telegram_sender.clone();
-        let rpc_url = self.client.url();
-        let rpc_client = RpcClient::new(rpc_url.clone());
+        let rpc = self.rpc.clone();
+        let notified_pools = self.notified_pools.clone();
+        let log_idle_timeout_ms = monitoring.log_idle_timeout_ms;
 
-        tokio::spawn(async move {
+        let handle = tokio::spawn(async move {
+            // Kept across re-subscribes, which can replay recent logs
+            let mut seen_signatures = RecentlySeen::new(monitoring.seen_pool_cache_size);
+            let mut seen_pools = RecentlySeen::new(monitoring.seen_pool_cache_size);
             loop {
-                match PubsubClient::logs_subscribe(
-                    &rpc_ws_url,
-                    RpcTransactionLogsFilter::Mentions(vec![RAYDIUM_LIQUIDITY_POOL_V4_PROGRAM_ID.to_string()]),
-                    RpcTransactionLogsConfig { commitment: None },
-                ) {
-                    Ok((_client, receiver)) => {
+                // Re-subscribe through whichever endpoint is active now, so a failover also moves the subscription
+                let rpc_ws_url = rpc.client().url().replace("http", "ws");
+                let pubsub_client = match PubsubClient::new(&rpc_ws_url).await {
+                    Ok(pubsub_client) => pubsub_client,
+                    Err(e) => {
+                        error!("{} on-chain monitor: failed to connect: {}. Retrying in 5s...", program.name, e);
+                        tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+                        continue;
+                    }
+                };
+                match pubsub_client.logs_subscribe(
+                    RpcTransactionLogsFilter::Mentions(vec![program.program_id.to_string()]),
+                    RpcTransactionLogsConfig { commitment: Some(rpc.client().commitment()) },
+                ).await {
+                    Ok((mut logs, unsubscribe)) => {
                         
-                        while let Ok(log_info) = receiver.recv() {
-                            if log_info.value.logs.iter().any(|log| log.contains("initialize2")) {
-                                info!("Detected potential new Raydium pool: {}", log_info.value.signature);
+                        loop {
+                            let log_info = match next_log(&mut logs, log_idle_timeout_ms).await {
+                                NextLog::Log(log_info) => log_info,
+                                NextLog::Ended => break,
+                                NextLog::Idle => {
+                                    warn!("{} on-chain monitor: no logs received in {}ms, forcing re-subscribe", program.name, log_idle_timeout_ms);
+                                    break;
+                                }
+                            };
+                            if log_info.value.logs.iter().any(|log| variants.iter().any(|variant| log.contains(&variant.log_marker))) {
+                                if !seen_signatures.insert(&log_info.value.signature) {
+                                    debug!("Skipping already processed {} transaction {}", program.name, log_info.value.signature);
+                                    continue;
+                                }
+                                info!(dex = program.name, signature = log_info.value.signature.as_str(); "Detected potential new pool");
                                 if let Ok(tx_signature) = Signature::from_str(&log_info.value.signature) {
                                     info!("tx_signature: {:?}", tx_signature);
                                     
@@ -106,9 +390,7 @@ impl DexMonitor {
                                     let mut fetched_tx = None;
                                     
                                     while retry_count < max_retries {
-                                        // Get transaction with retries
-                                        let tx_signature_clone = tx_signature;
-                                        match get_transaction(&rpc_client, &tx_signature_clone) {
+                                        match get_transaction(rpc.clone(), tx_signature).await {
                                             Ok(tx) => {
                                                 fetched_tx = Some(tx);
                                                 break;
@@ -122,7 +404,7 @@ impl DexMonitor {
                                                     warn!("Failed to get transaction (attempt {}/{}): {} (Error: {}). Retrying in {}ms...", 
                                                           retry_count, max_retries, log_info.value.signature, e, 
                                                           (2_u64.pow(retry_count as u32) * 100));
-                                                    std::thread::sleep(std::time::Duration::from_millis(2_u64.pow(retry_count as u32) * 100));
+                                                    tokio::time::sleep(std::time::Duration::from_millis(2_u64.pow(retry_count as u32) * 100)).await;
                                                 }
                                             }
                                         }
@@ -130,18 +412,28 @@ impl DexMonitor {
                                     
                                     if let Some(fetched_tx) = fetched_tx {
                                         // Decode transaction
-                                        match decode_transaction(&fetched_tx) {
+                                        match (program.decode)(&fetched_tx, &variants, monitoring.emit_pool_details) {
                                             Ok(pull_updates) => {
-                                                info!("===> Pull updates: {:?}", pull_updates);
                                                 for pull_update in pull_updates.clone() {
                                                     if let WebSocketMessage::PoolUpdate(pool_update) = pull_update.clone() {
-                                                        info!("===> Sending info about new pool: {:?}", pool_update);
+                                                        if !seen_pools.insert(&pool_update.pool_address) {
+                                                            info!("Skipping already emitted {} pool {}", program.name, pool_update.pool_address);
+                                                            continue;
+                                                        }
+                                                        info!(dex = program.name, pool = pool_update.pool_address.as_str(), token_a = pool_update.token_a.as_str(),
+                                                              token_b = pool_update.token_b.as_str(), liquidity_sol = pool_update.liquidity; "Emitting new pool");
                                                         let sender_clone = sender.clone();
                                                         let telegram_sender_clone = telegram_sender.clone();
                                                         if let Err(e) = sender_clone.send(pull_update).await {
                                                             error!("Failed to send pool update: {}", e);
                                                         }
-                                                        if let Err(e) = telegram_sender_clone.send_new_pool_telegram_message(&log_info.value.signature, &pool_update.pool_address, &pool_update.token_a, &pool_update.token_b).await {
+                                                        let first_notification = notified_pools.lock().unwrap().insert(&pool_update.pool_address).unwrap_or_else(|e| {
+                                                            warn!("Failed to save notified pools: {}", e);
+                                                            true
+                                                        });
+                                                        if !first_notification {
+                                                            info!("Pool {} was already announced, not notifying again", pool_update.pool_address);
+                                                        } else if let Err(e) = telegram_sender_clone.send_new_pool_telegram_message(program.name, &log_info.value.signature, &pool_update.pool_address, &pool_update.token_a, &pool_update.token_b, crate::new_token_mint(&pool_update)).await {
                                                             error!("{}", e);
                                                         }
                                                         // tokio::spawn(async move {
@@ -170,56 +462,786 @@ impl DexMonitor {
                                 }
                             } else {
                                 debug!("====> log_info: {:?}", log_info);
-                                debug!("No new Raydium pool detected");
+                                debug!("No new {} pool detected", program.name);
                             }
                         }
-                        error!("Raydium on-chain monitor: subscription ended, reconnecting in 5s...");
+                        drop(logs);
+                        unsubscribe().await;
+                        error!("{} on-chain monitor: subscription ended, reconnecting in 5s...", program.name);
                     }
                     Err(e) => {
-                        error!("Raydium on-chain monitor: failed to subscribe: {}. Retrying in 5s...", e);
+                        error!("{} on-chain monitor: failed to subscribe: {}. Retrying in 5s...", program.name, e);
                     }
                 }
-                std::thread::sleep(std::time::Duration::from_secs(5));
+                if let Err(e) = pubsub_client.shutdown().await {
+                    debug!("{} on-chain monitor: pubsub shutdown failed: {}", program.name, e);
+                }
+                tokio::time::sleep(std::time::Duration::from_secs(5)).await;
             }
         });
-        Ok(())
+        Ok(handle)
     }
 
-    pub async fn monitor_jupiter(&self, _tx: mpsc::Sender<WebSocketMessage>) -> Result<()> {
+    // Poll Jupiter's new-token list and announce mints that weren't routable on earlier polls.
+    // The first poll only seeds the seen set so startup doesn't replay the whole list.
+    pub async fn monitor_jupiter(&self, tx: mpsc::Sender<WebSocketMessage>, monitoring: MonitoringConfig) -> Result<JoinHandle<()>> {
+        let url = format!("{}/new", self.config.jupiter_tokens_api_url.trim_end_matches('/'));
+        info!("Starting Jupiter listing monitoring at {}", url);
+        let http = self.http.clone();
+        let interval = std::time::Duration::from_millis(monitoring.check_interval_ms);
+
+        let handle = tokio::spawn(async move {
+            let mut seen: HashSet<String> = HashSet::new();
+            let mut seeded = false;
+            loop {
+                match fetch_new_jupiter_tokens(&http, &url).await {
+                    Ok(tokens) => {
+                        for token in tokens {
+                            if !seen.insert(token.mint.clone()) || !seeded {
+                                continue;
+                            }
+                            info!(dex = "Jupiter", token = token.mint.as_str(), symbol = token.symbol.as_str(); "Detected newly routable token");
+                            let listing = TokenListing {
+                                token_address: token.mint,
+                                symbol: token.symbol,
+                                name: token.name,
+                                initial_liquidity: UNKNOWN_LIQUIDITY,
+                                timestamp: token.created_at.parse().unwrap_or_else(|_| chrono::Utc::now().timestamp() as u64),
+                            };
+                            if let Err(e) = tx.send(WebSocketMessage::TokenListing(listing)).await {
+                                error!("Jupiter monitor: listing channel closed, stopping: {}", e);
+                                return;
+                            }
+                        }
+                        if !seeded {
+                            debug!("Jupiter monitor: seeded with {} known tokens", seen.len());
+                            seeded = true;
+                        }
+                    }
+                    Err(e) => warn!("Jupiter monitor: failed to fetch new tokens: {}", e),
+                }
+                tokio::time::sleep(interval).await;
+            }
+        });
+        Ok(handle)
+    }
+
+    // A request to the configured Jupiter swap API (public or self-hosted), authenticated when a key is set
+    fn jupiter_request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let url = format!("{}/{}", self.config.jupiter_api_url.trim_end_matches('/'), path);
+        let request = self.http.request(method, url);
+        if self.config.jupiter_api_key.is_empty() {
+            request
+        } else {
+            request.header("x-api-key", self.config.jupiter_api_key.expose())
+        }
+    }
+
+    // Fail fast at startup if the Jupiter API can't be reached, rejects our key, or can't route a plain SOL/USDC quote
+    pub async fn check_jupiter_reachable(&self) -> Result<()> {
+        self.get_jupiter_quote(SOL_MINT, USDC_MINT, PROBE_LAMPORTS, 50).await
+            .map_err(|e| anyhow!("Jupiter API at {} is not usable: {}", self.config.jupiter_api_url, e))?;
+        info!("Jupiter API at {} is reachable", self.config.jupiter_api_url);
         Ok(())
     }
 
-    pub async fn get_jupiter_quote(&self, _input_token: &str, _output_token: &str, _amount: u64) -> Result<SwapInfo> {
-        unimplemented!()
+    pub async fn get_jupiter_quote(&self, input_token: &str, output_token: &str, amount: u64, slippage_bps: u16) -> Result<SwapInfo> {
+        self.get_jupiter_quote_with_mode(input_token, output_token, amount, slippage_bps, SwapMode::ExactIn).await
     }
 
-    pub async fn execute_jupiter_swap(&self, _keypair: &Keypair, _swap_info: &SwapInfo) -> Result<String> {
-        unimplemented!()
+    // Quote in the given swap mode; with ExactOut, `amount` is the output to receive rather than the input to spend
+    pub async fn get_jupiter_quote_with_mode(&self, input_token: &str, output_token: &str, amount: u64, slippage_bps: u16, swap_mode: SwapMode) -> Result<SwapInfo> {
+        let response = self.jupiter_request(reqwest::Method::GET, "quote")
+            .query(&[
+                ("inputMint", input_token.to_string()),
+                ("outputMint", output_token.to_string()),
+                ("amount", amount.to_string()),
+                ("slippageBps", slippage_bps.to_string()),
+                ("swapMode", swap_mode.as_str().to_string()),
+            ])
+            .send()
+            .await
+            .map_err(|e| SniperError::QuoteFailed(e.to_string()))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(SniperError::QuoteFailed(format!("{}: {}", status, body)).into());
+        }
+        let quote: serde_json::Value = response.json().await.map_err(|e| SniperError::QuoteFailed(e.to_string()))?;
+
+        let parse_u64 = |key: &str| quote.get(key).and_then(|v| v.as_str()).and_then(|v| v.parse::<u64>().ok());
+        let route_plan = quote.get("routePlan").and_then(|v| v.as_array()).cloned().unwrap_or_default();
+        let route = route_plan.iter()
+            .filter_map(|step| step.pointer("/swapInfo/label").and_then(|v| v.as_str()).map(|v| v.to_string()))
+            .collect();
+        let fee_amount = route_plan.iter()
+            .filter_map(|step| step.pointer("/swapInfo/feeAmount").and_then(|v| v.as_str()).and_then(|v| v.parse::<u64>().ok()))
+            .sum();
+
+        let (input_amount, output_amount) = match swap_mode {
+            SwapMode::ExactIn => (
+                parse_u64("inAmount").unwrap_or(amount),
+                parse_u64("outAmount").ok_or_else(|| SniperError::QuoteFailed("missing outAmount".to_string()))?,
+            ),
+            SwapMode::ExactOut => (
+                parse_u64("inAmount").ok_or_else(|| SniperError::QuoteFailed("missing inAmount".to_string()))?,
+                parse_u64("outAmount").unwrap_or(amount),
+            ),
+        };
+        Ok(SwapInfo {
+            input_token: input_token.to_string(),
+            output_token: output_token.to_string(),
+            input_amount,
+            output_amount,
+            slippage: slippage_bps as f64 / 10_000.0,
+            route,
+            price_impact: quote.get("priceImpactPct").and_then(|v| v.as_str()).and_then(|v| v.parse::<f64>().ok()).unwrap_or(0.0),
+            fee_amount,
+            quote_response: quote,
+        })
     }
 
-    pub async fn get_token_metadata(&self, _token_address: &str) -> Result<TokenMetadata> {
-        unimplemented!()
+    // Build, sign and send a Jupiter swap, given up as lost if it misses the confirmation deadline or, without one,
+    // outlives its blockhash. Jupiter's compute budget instructions are replaced by our own at compute_unit_price,
+    // keeping the unit limit Jupiter simulated for the swap.
+    pub async fn execute_jupiter_swap(&self, keypair: &Keypair, swap_info: &SwapInfo, compute_unit_price: u64, confirmation_deadline: Option<Duration>) -> Result<String> {
+        let request = serde_json::json!({
+            "quoteResponse": swap_info.quote_response,
+            "userPublicKey": keypair.pubkey().to_string(),
+            "wrapAndUnwrapSol": true,
+            "dynamicComputeUnitLimit": true,
+        });
+        let response = self.jupiter_request(reqwest::Method::POST, "swap").json(&request).send().await
+            .map_err(|e| SniperError::SwapFailed(format!("Jupiter swap request failed: {}", e)))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(SniperError::SwapFailed(format!("Jupiter swap request failed ({}): {}", status, body)).into());
+        }
+        let swap: serde_json::Value = response.json().await
+            .map_err(|e| SniperError::SwapFailed(format!("Jupiter swap response unreadable: {}", e)))?;
+        let swap_transaction = swap.get("swapTransaction")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| SniperError::SwapFailed("Jupiter swap response missing swapTransaction".to_string()))?;
+
+        let tx_bytes = base64::engine::general_purpose::STANDARD.decode(swap_transaction)?;
+        let unsigned_tx: VersionedTransaction = bincode::deserialize(&tx_bytes)?;
+        let message = self.prepare_swap_message(&keypair.pubkey(), &swap_info.output_token, unsigned_tx.message, Some(compute_unit_price)).await?;
+        let signed_tx = VersionedTransaction::try_new(message, &[keypair])?;
+        self.send_transaction(&signed_tx, confirmation_deadline).await
+    }
+
+    // Get a swap message ready to sign, in one transaction with whatever setup it is missing:
+    // - With a compute_unit_price, its compute budget instructions are replaced by ours at that price, keeping
+    //   the unit limit it asked for. The runtime rejects duplicate compute budget instructions.
+    // - A swap into a brand-new token fails if the wallet has no token account to receive it. Jupiter normally
+    //   adds an idempotent create to the swap itself; when a swap lacks one, it is added here. Program ids are
+    //   always static keys in a message, so an existing create can't hide behind a lookup table.
+    // The rebuilt message keeps the swap's blockhash and lookup tables.
+    pub async fn prepare_swap_message(&self, payer: &Pubkey, output_mint: &str, message: VersionedMessage, compute_unit_price: Option<u64>) -> Result<VersionedMessage> {
+        let associated_token_program = Pubkey::from_str(ASSOCIATED_TOKEN_PROGRAM_ID)?;
+        let keys = message.static_account_keys();
+        // Wrapped SOL is handled by the swap's own wrap and unwrap
+        let needs_token_account = output_mint != SOL_MINT
+            && !message.instructions().iter().any(|instruction| keys.get(instruction.program_id_index as usize) == Some(&associated_token_program));
+        if !needs_token_account && compute_unit_price.is_none() {
+            return Ok(message);
+        }
+
+        let mint = Pubkey::from_str(output_mint)?;
+        let rpc = self.rpc.clone();
+        let (token_program, lookup_tables) = tokio::try_join!(
+            async {
+                if !needs_token_account {
+                    return Ok(None);
+                }
+                tokio::task::spawn_blocking(move || rpc.call(|client| token_accounts::mint_program_id(client, &mint))).await?.map(Some)
+            },
+            self.address_lookup_tables(&message),
+        )?;
+        let mut instructions = decompile_instructions(&message, &lookup_tables)?;
+        if let Some(compute_unit_price) = compute_unit_price {
+            instructions = with_compute_unit_price(instructions, compute_unit_price);
+        }
+        if let Some(token_program) = token_program {
+            let create = token_accounts::create_associated_token_account_idempotent(payer, payer, &mint, &token_program)?;
+            instructions = with_setup_instruction(instructions, create);
+        }
+        compile_message(&message, payer, &instructions, &lookup_tables)
     }
 
-    pub async fn analyze_token_safety(&self, _token_address: &str) -> Result<bool> {
-        unimplemented!()
+    // The lookup tables a v0 message loads accounts from, fetching any not cached or cached too short for it
+    async fn address_lookup_tables(&self, message: &VersionedMessage) -> Result<Vec<AddressLookupTableAccount>> {
+        let lookups = message.address_table_lookups().unwrap_or_default();
+        let missing: Vec<Pubkey> = {
+            let cached = self.lookup_tables.lock().unwrap();
+            lookups.iter()
+                .filter(|lookup| !cached.get(&lookup.account_key).is_some_and(|table| {
+                    lookup.writable_indexes.iter().chain(&lookup.readonly_indexes).all(|index| (*index as usize) < table.addresses.len())
+                }))
+                .map(|lookup| lookup.account_key)
+                .collect()
+        };
+        if !missing.is_empty() {
+            let rpc = self.rpc.clone();
+            let accounts = tokio::task::spawn_blocking({
+                let missing = missing.clone();
+                move || rpc.call(|client| Ok(client.get_multiple_accounts(&missing)?))
+            }).await??;
+            let mut cached = self.lookup_tables.lock().unwrap();
+            for (key, account) in missing.into_iter().zip(accounts) {
+                let account = account.ok_or_else(|| anyhow!("Lookup table {} does not exist", key))?;
+                let table = AddressLookupTable::deserialize(&account.data).map_err(|e| anyhow!("Lookup table {} is invalid: {}", key, e))?;
+                cached.insert(key, AddressLookupTableAccount { key, addresses: table.addresses.to_vec() });
+            }
+        }
+        let cached = self.lookup_tables.lock().unwrap();
+        Ok(lookups.iter().filter_map(|lookup| cached.get(&lookup.account_key).cloned()).collect())
     }
 
-    pub async fn check_token_liquidity(&self, _token_address: &str) -> Result<bool> {
-        unimplemented!()
+    // Submit a signed transaction, then either block until confirmed or poll up to the deadline
+    pub async fn send_transaction(&self, signed_tx: &VersionedTransaction, confirmation_deadline: Option<Duration>) -> Result<String> {
+        if self.broadcast_to_all_rpcs && self.rpc.clients().len() > 1 {
+            return self.broadcast_transaction(signed_tx, confirmation_deadline).await;
+        }
+        let result = self.rpc.call(|client| Ok(match confirmation_deadline {
+            Some(_) => client.send_transaction(signed_tx)?,
+            None => client.send_and_confirm_transaction(signed_tx)?,
+        }));
+        let signature = match result {
+            Ok(signature) => signature,
+            Err(e) => {
+                let Some(client_error) = e.downcast_ref::<ClientError>() else {
+                    return Err(e);
+                };
+                if let Some(logs) = simulation_logs(client_error) {
+                    error!("Transaction simulation failed, logs:\n{}", logs.join("\n"));
+                }
+                return Err(send_error(client_error).into());
+            }
+        };
+        if let Some(deadline) = confirmation_deadline {
+            self.wait_for_confirmation(&signature, deadline).await?;
+        }
+        Ok(signature.to_string())
     }
+
+    // Send the same signed transaction to the active endpoint and every other one at once. They all carry one
+    // signature, so whichever endpoint accepts it first, the trade is confirmed (and counted) exactly once.
+    async fn broadcast_transaction(&self, signed_tx: &VersionedTransaction, confirmation_deadline: Option<Duration>) -> Result<String> {
+        let signature = signed_tx.signatures[0];
+
+        // The primary runs preflight, so the others just forward the transaction
+        let skip_preflight = RpcSendTransactionConfig { skip_preflight: true, ..RpcSendTransactionConfig::default() };
+        let primary_url = self.rpc.client().url();
+        let lagging_rpc_urls = self.lagging_rpc_urls.read().unwrap().clone();
+        let sends: Vec<_> = self.rpc.clients().into_iter()
+            .filter(|client| client.url() != primary_url && !lagging_rpc_urls.contains(&client.url()))
+            .map(|client| {
+                let signed_tx = signed_tx.clone();
+                tokio::task::spawn_blocking(move || (client.url(), client.send_transaction_with_config(&signed_tx, skip_preflight)))
+            })
+            .collect();
+        let sent_to = sends.len();
+        let primary_result = self.rpc.call(|client| Ok(client.send_transaction(signed_tx)?));
+        let mut accepted = primary_result.is_ok() as usize;
+        for send in futures_util::future::join_all(sends).await {
+            match send {
+                Ok((_, Ok(_))) => accepted += 1,
+                Ok((rpc_url, Err(e))) => debug!("Broadcast of {} to {} failed: {}", signature, rpc_url, e),
+                Err(e) => warn!("Broadcast task for {} failed: {}", signature, e),
+            }
+        }
+
+        if let Err(e) = primary_result {
+            if let Some(client_error) = e.downcast_ref::<ClientError>() {
+                if let Some(logs) = simulation_logs(client_error) {
+                    error!("Transaction simulation failed, logs:\n{}", logs.join("\n"));
+                    return Err(send_error(client_error).into());
+                }
+            }
+            if accepted == 0 {
+                return Err(SniperError::RpcError(format!("Failed to send transaction to any RPC: {}", e)).into());
+            }
+            warn!("Primary RPC rejected {} ({}), relying on the broadcast RPCs", signature, e);
+        }
+        info!("Broadcast {} to {}/{} RPCs", signature, accepted, sent_to + 1);
+
+        self.wait_for_confirmation(&signature, confirmation_deadline.unwrap_or(BROADCAST_CONFIRMATION_TIMEOUT)).await?;
+        Ok(signature.to_string())
+    }
+
+    // Poll a sent transaction until it confirms, fails on chain, or the deadline passes
+    async fn wait_for_confirmation(&self, signature: &Signature, deadline: Duration) -> Result<()> {
+        let started = std::time::Instant::now();
+        loop {
+            match self.rpc.call(|client| Ok(client.get_signature_status(signature)?))? {
+                Some(Ok(())) => return Ok(()),
+                Some(Err(e)) => return Err(SniperError::SwapFailed(format!("Transaction {} failed: {}", signature, e)).into()),
+                None if started.elapsed() >= deadline => {
+                    warn!("Transaction {} not confirmed within {}ms; treating it as lost (it may still land until its blockhash expires)",
+                          signature, deadline.as_millis());
+                    return Err(SniperError::SwapFailed(format!("Transaction {} not confirmed within {}ms", signature, deadline.as_millis())).into());
+                }
+                None => tokio::time::sleep(CONFIRMATION_POLL_INTERVAL).await,
+            }
+        }
+    }
+
+    // Token metadata, from the cache while it is younger than metadata_cache_ttl. Stale entries are only
+    // dropped when next looked up or when a fresh fetch is stored.
+    pub async fn get_token_metadata(&self, token_address: &str) -> Result<TokenMetadata> {
+        let ttl = self.config.metadata_cache_ttl;
+        if ttl.is_zero() {
+            return self.fetch_token_metadata(token_address).await;
+        }
+        {
+            let mut cache = self.metadata_cache.lock().unwrap();
+            match cache.get(token_address) {
+                Some((metadata, fetched_at)) if fetched_at.elapsed() < ttl => return Ok(metadata.clone()),
+                Some(_) => {
+                    cache.remove(token_address);
+                }
+                None => {}
+            }
+        }
+
+        let metadata = self.fetch_token_metadata(token_address).await?;
+        let mut cache = self.metadata_cache.lock().unwrap();
+        cache.retain(|_, (_, fetched_at)| fetched_at.elapsed() < ttl);
+        cache.insert(token_address.to_string(), (metadata.clone(), Instant::now()));
+        Ok(metadata)
+    }
+
+    async fn fetch_token_metadata(&self, token_address: &str) -> Result<TokenMetadata> {
+        let overview = self.birdeye_get("/defi/token_overview", token_address).await?;
+        let security = self.birdeye_get("/defi/token_security", token_address).await?;
+        let overview = overview.get("data").ok_or_else(|| anyhow!("Birdeye overview missing data for {}", token_address))?;
+        let security = security.get("data").cloned().unwrap_or_default();
+
+        let as_f64 = |value: &serde_json::Value, key: &str| value.get(key).and_then(|v| v.as_f64()).unwrap_or(0.0);
+        let as_str = |value: &serde_json::Value, key: &str| value.get(key).and_then(|v| v.as_str()).unwrap_or_default().to_string();
+        let decimals = overview.get("decimals").and_then(|v| v.as_u64()).unwrap_or(0) as u8;
+
+        Ok(TokenMetadata {
+            address: token_address.to_string(),
+            symbol: as_str(overview, "symbol"),
+            name: as_str(overview, "name"),
+            decimals,
+            total_supply: (as_f64(overview, "supply") * 10_f64.powi(decimals as i32)) as u64,
+            market_cap: as_f64(overview, "mc"),
+            price: as_f64(overview, "price"),
+            volume_24h: as_f64(overview, "v24hUSD"),
+            sell_volume_24h: as_f64(overview, "vSell24hUSD"),
+            holders: overview.get("holder").and_then(|v| v.as_u64()).unwrap_or(0),
+            creator: as_str(&security, "creatorAddress"),
+            is_verified: security.get("jupStrictList").and_then(|v| v.as_bool()).unwrap_or(false),
+        })
+    }
+
+    // GET a Birdeye endpoint, retrying 5xx responses and timeouts with backoff.
+    // Other failures (including 429 rate limits) are returned immediately.
+    async fn birdeye_get(&self, path: &str, token_address: &str) -> Result<serde_json::Value> {
+        let url = format!("{}{}", self.config.birdeye_api_url.trim_end_matches('/'), path);
+        let policy = &self.config.metadata_retry;
+        let mut attempt = 0;
+        loop {
+            let result = self.http.get(&url)
+                .query(&[("address", token_address)])
+                .header("X-API-KEY", self.config.birdeye_api_key.expose())
+                .header("x-chain", "solana")
+                .timeout(std::time::Duration::from_secs(10))
+                .send()
+                .await;
+
+            let transient_error = match result {
+                Ok(response) if response.status().is_success() => return Ok(response.json().await?),
+                Ok(response) if response.status().is_server_error() => format!("HTTP {}", response.status()),
+                Ok(response) => {
+                    let status = response.status();
+                    let body = response.text().await.unwrap_or_default();
+                    return Err(anyhow!("Birdeye request {} failed ({}): {}", path, status, body));
+                }
+                Err(e) if e.is_timeout() || e.is_connect() => e.to_string(),
+                Err(e) => return Err(e.into()),
+            };
+
+            if attempt >= policy.max_retries {
+                return Err(anyhow!("Birdeye request {} failed after {} retries: {}", path, attempt, transient_error));
+            }
+            attempt += 1;
+            let delay = policy.delay_for(attempt);
+            warn!("Birdeye request {} failed ({}), retrying in {}ms (attempt {}/{})",
+                  path, transient_error, delay.as_millis(), attempt, policy.max_retries);
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    // Honeypot check by quoting a small buy and then selling it back.
+    // Returns Ok(true) when the token is sellable and Ok(false) when it looks like a honeypot:
+    // the sell quote fails, returns zero output, or has a price impact above max_sell_price_impact.
+    // Only returns Err when the buy quote itself fails, since nothing can be concluded then.
+    pub async fn analyze_token_safety(&self, token_address: &str, slippage_bps: u16, max_sell_price_impact: f64) -> Result<bool> {
+        let buy = self.get_jupiter_quote(SOL_MINT, token_address, PROBE_LAMPORTS, slippage_bps).await?;
+
+        let sell = match self.get_jupiter_quote(token_address, SOL_MINT, buy.output_amount, slippage_bps).await {
+            Ok(sell) => sell,
+            Err(e) => {
+                warn!("No sell route for token {}: {}", token_address, e);
+                return Ok(false);
+            }
+        };
+
+        if sell.output_amount == 0 {
+            warn!("Sell route for token {} returns zero output", token_address);
+            return Ok(false);
+        }
+
+        if sell.price_impact > max_sell_price_impact {
+            warn!("Sell route for token {} has price impact {}% (max {}%)",
+                  token_address, sell.price_impact * 100.0, max_sell_price_impact * 100.0);
+            return Ok(false);
+        }
+
+        Ok(true)
+    }
+
+    // Returns Ok(true) when the token's deepest Raydium pool holds at least min_liquidity_sol on its quote side
+    pub async fn check_token_liquidity(&self, token_address: &str, min_liquidity_sol: f64) -> Result<bool> {
+        let liquidity_sol = self.get_pool_liquidity_sol(token_address).await?;
+        debug!("Token {} has {} SOL of quote-side liquidity", token_address, liquidity_sol);
+        Ok(liquidity_sol >= min_liquidity_sol)
+    }
+
+    // Quote-side reserves of the token's deepest Raydium pool, converted to SOL via Jupiter when not SOL-paired
+    pub async fn get_pool_liquidity_sol(&self, token_address: &str) -> Result<f64> {
+        let url = format!("{}/pools/info/mint", self.config.raydium_api_url.trim_end_matches('/'));
+        let response = self.http.get(&url)
+            .query(&[
+                ("mint1", token_address),
+                ("poolType", "all"),
+                ("poolSortField", "liquidity"),
+                ("sortType", "desc"),
+                ("pageSize", "1"),
+                ("page", "1"),
+            ])
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow!("Raydium pool lookup failed ({}): {}", status, body));
+        }
+        let body: serde_json::Value = response.json().await?;
+        let pool = body.pointer("/data/data/0")
+            .ok_or_else(|| anyhow!("No Raydium pool found for token {}", token_address))?;
+
+        let (quote_side, quote_amount_key) = if pool.pointer("/mintA/address").and_then(|v| v.as_str()) == Some(token_address) {
+            ("mintB", "mintAmountB")
+        } else {
+            ("mintA", "mintAmountA")
+        };
+        let quote_mint = pool.pointer(&format!("/{}/address", quote_side)).and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow!("Raydium pool for {} is missing its quote mint", token_address))?;
+        let quote_decimals = pool.pointer(&format!("/{}/decimals", quote_side)).and_then(|v| v.as_u64()).unwrap_or(0);
+        let quote_amount = pool.get(quote_amount_key).and_then(|v| v.as_f64()).unwrap_or(0.0);
+
+        if quote_mint == SOL_MINT {
+            return Ok(quote_amount);
+        }
+
+        let quote_amount_raw = (quote_amount * 10_f64.powi(quote_decimals as i32)) as u64;
+        let sol_quote = self.get_jupiter_quote(quote_mint, SOL_MINT, quote_amount_raw, 100).await
+            .map_err(|e| anyhow!("Failed to price {} reserves of {} in SOL: {}", quote_mint, token_address, e))?;
+        Ok(sol_quote.output_amount as f64 / LAMPORTS_PER_SOL as f64)
+    }
+
+    // Quote a small buy and then selling everything it would return
+    pub async fn round_trip_quote(&self, token_address: &str, slippage_bps: u16) -> Result<(SwapInfo, SwapInfo)> {
+        let buy = self.get_jupiter_quote(SOL_MINT, token_address, PROBE_LAMPORTS, slippage_bps).await?;
+        let sell = self.get_jupiter_quote(token_address, SOL_MINT, buy.output_amount, slippage_bps).await?;
+        Ok((buy, sell))
+    }
+
+    // Estimate the combined buy+sell tax as the worse of the round-trip loss beyond price impact
+    // and twice the Token-2022 transfer fee
+    pub async fn estimate_token_tax_percent(&self, token_address: &str, slippage_bps: u16) -> Result<f64> {
+        let transfer_fee_percent = self.get_transfer_fee_bps(token_address)? as f64 / 100.0;
+
+        let (buy, sell) = self.round_trip_quote(token_address, slippage_bps).await?;
+        let round_trip_loss_percent = (1.0 - sell.output_amount as f64 / buy.input_amount as f64) * 100.0;
+        let price_impact_percent = (buy.price_impact + sell.price_impact) * 100.0;
+        let round_trip_tax_percent = (round_trip_loss_percent - price_impact_percent).max(0.0);
+
+        Ok(round_trip_tax_percent.max(transfer_fee_percent * 2.0))
+    }
+
+    // Whether a pool account currently exists on-chain and, when given, is owned by the expected program
+    pub fn pool_account_is_live(&self, pool_address: &str, program_id: Option<&str>) -> Result<bool> {
+        let pool = Pubkey::from_str(pool_address)?;
+        let Some(account) = self.rpc.call(|client| Ok(client.get_account_with_commitment(&pool, client.commitment())?.value))? else {
+            return Ok(false);
+        };
+        Ok(program_id.is_none_or(|program_id| account.owner.to_string() == program_id))
+    }
+
+    // Transfer fee in basis points from a Token-2022 mint's TransferFeeConfig extension, 0 otherwise
+    pub fn get_transfer_fee_bps(&self, token_address: &str) -> Result<u16> {
+        let mint = Pubkey::from_str(token_address)?;
+        let account = self.rpc.call(|client| Ok(client.get_account(&mint)?))?;
+        if account.owner.to_string() != TOKEN_2022_PROGRAM_ID {
+            return Ok(0);
+        }
+        Ok(parse_transfer_fee_bps(&account.data).unwrap_or(0))
+    }
+
+    // Fee paid by a confirmed transaction, in lamports
+    pub async fn transaction_fee(&self, signature: &str) -> Result<u64> {
+        let transaction = get_transaction(self.rpc.clone(), Signature::from_str(signature)?).await?;
+        transaction.transaction.meta.map(|meta| meta.fee)
+            .ok_or_else(|| anyhow!("Transaction {} has no status meta", signature))
+    }
+
+    // Count recent successful transactions on a pool in which someone other than the creator sold the token
+    pub async fn count_successful_sells(&self, pool_address: &str, token_mint: &str, creator: Option<&str>, scan_limit: usize) -> Result<usize> {
+        let pool = Pubkey::from_str(pool_address)?;
+        let signatures = self.signatures_for_address(&pool, None, None, scan_limit).await?;
+        let transactions: Vec<_> = self.successful_transactions(signatures).map(|(_, tx)| tx).collect().await;
+
+        let sells = count_non_creator_sells(&transactions, token_mint, creator);
+        debug!("Pool {} has {} successful non-creator sells of {}", pool_address, sells, token_mint);
+        Ok(sells)
+    }
+
+    // Number of recent successful trades of the token in a pool per trading address
+    pub async fn count_trades_by_trader(&self, pool_address: &str, token_mint: &str, scan_limit: usize) -> Result<HashMap<String, usize>> {
+        let pool = Pubkey::from_str(pool_address)?;
+        let signatures = self.signatures_for_address(&pool, None, None, scan_limit).await?;
+
+        let mut trades_by_trader = HashMap::new();
+        let mut transactions = std::pin::pin!(self.successful_transactions(signatures));
+        while let Some((_, tx)) = transactions.next().await {
+            if let Some(trader) = trader_of(&tx, token_mint) {
+                *trades_by_trader.entry(trader).or_insert(0) += 1;
+            }
+        }
+        debug!("Pool {} has trades of {} from {} addresses", pool_address, token_mint, trades_by_trader.len());
+        Ok(trades_by_trader)
+    }
+
+    // Block time of the mint's earliest transaction, taken as its creation time. For a mint with more history than
+    // MINT_AGE_SCAN_PAGES pages this is the oldest block time scanned, so the age it gives is a lower bound.
+    pub async fn mint_created_at(&self, mint: &str) -> Result<Option<u64>> {
+        let mint = Pubkey::from_str(mint)?;
+        let mut before = None;
+        let mut created_at = None;
+        for _ in 0..MINT_AGE_SCAN_PAGES {
+            let signatures = self.signatures_for_address(&mint, before, None, SIGNATURES_PAGE_SIZE).await?;
+            let Some(oldest) = signatures.last() else {
+                break;
+            };
+            created_at = oldest.block_time.map(|block_time| block_time as u64).or(created_at);
+            if signatures.len() < SIGNATURES_PAGE_SIZE {
+                break;
+            }
+            before = Some(Signature::from_str(&oldest.signature)?);
+        }
+        Ok(created_at)
+    }
+
+    // Scan a Raydium pool's transactions newer than `until` for a liquidity withdrawal signed by the creator.
+    // Returns the withdrawal's signature, if any, and the newest signature seen so the next scan can resume there.
+    pub async fn find_creator_liquidity_removal(&self, pool_address: &str, creator: &str, until: Option<&str>, scan_limit: usize) -> Result<(Option<String>, Option<String>)> {
+        let pool = Pubkey::from_str(pool_address)?;
+        let until = until.map(Signature::from_str).transpose()?;
+        let signatures = self.signatures_for_address(&pool, None, until, scan_limit).await?;
+        let newest_signature = signatures.first().map(|status| status.signature.clone());
+
+        // Fetched ahead in order, so the scan can still stop at the newest removal
+        let mut transactions = std::pin::pin!(self.successful_transactions(signatures));
+        while let Some((signature, tx)) = transactions.next().await {
+            if is_creator_liquidity_removal(&tx, creator) {
+                return Ok((Some(signature), newest_signature));
+            }
+        }
+        Ok((None, newest_signature))
+    }
+
+    // Up to `limit` of an address's signatures, newest first, between the optional `until` and `before` signatures
+    async fn signatures_for_address(&self, address: &Pubkey, before: Option<Signature>, until: Option<Signature>, limit: usize) -> Result<Vec<RpcConfirmedTransactionStatusWithSignature>> {
+        let (rpc, address) = (self.rpc.clone(), *address);
+        tokio::task::spawn_blocking(move || rpc.call(|client| Ok(client.get_signatures_for_address_with_config(&address, GetConfirmedSignaturesForAddress2Config {
+            before,
+            until,
+            limit: Some(limit),
+            commitment: None,
+        })?))).await?
+    }
+
+    // The successful transactions among `signatures` with their signatures, fetched POOL_SCAN_CONCURRENCY at a time
+    // and yielded in order. One that can't be fetched is logged and skipped.
+    fn successful_transactions(&self, signatures: Vec<RpcConfirmedTransactionStatusWithSignature>) -> impl futures_util::Stream<Item = (String, EncodedConfirmedTransactionWithStatusMeta)> {
+        let rpc = self.rpc.clone();
+        futures_util::stream::iter(signatures.into_iter().filter(|status| status.err.is_none()))
+            .map(move |status| {
+                let rpc = rpc.clone();
+                async move {
+                    let result = match Signature::from_str(&status.signature) {
+                        Ok(signature) => get_transaction(rpc, signature).await,
+                        Err(e) => Err(e.into()),
+                    };
+                    (status.signature, result)
+                }
+            })
+            .buffered(POOL_SCAN_CONCURRENCY)
+            .filter_map(|(signature, result)| async move {
+                match result {
+                    Ok(tx) => Some((signature, tx)),
+                    Err(e) => {
+                        warn!("Skipping pool transaction {}: {}", signature, e);
+                        None
+                    }
+                }
+            })
+    }
+}
+
+// Token-2022 mints are a 165-byte base, an account type byte, then TLV extensions (u16 type, u16 length).
+// TransferFeeConfig is type 1 and its newer transfer fee basis points sit at value offset 106.
+fn parse_transfer_fee_bps(data: &[u8]) -> Option<u16> {
+    const EXTENSIONS_OFFSET: usize = 166;
+    const TRANSFER_FEE_CONFIG: u16 = 1;
+    const NEWER_FEE_BPS_OFFSET: usize = 106;
+
+    let mut offset = EXTENSIONS_OFFSET;
+    while offset + 4 <= data.len() {
+        let extension_type = u16::from_le_bytes([data[offset], data[offset + 1]]);
+        let length = u16::from_le_bytes([data[offset + 2], data[offset + 3]]) as usize;
+        let value = data.get(offset + 4..offset + 4 + length)?;
+        if extension_type == TRANSFER_FEE_CONFIG {
+            let bps = value.get(NEWER_FEE_BPS_OFFSET..NEWER_FEE_BPS_OFFSET + 2)?;
+            return Some(u16::from_le_bytes([bps[0], bps[1]]));
+        }
+        offset += 4 + length;
+    }
+    None
+}
+
+// A successful Raydium withdraw instruction in a transaction the creator signed
+fn is_creator_liquidity_removal(tx: &EncodedConfirmedTransactionWithStatusMeta, creator: &str) -> bool {
+    if !matches!(&tx.transaction.meta, Some(meta) if meta.err.is_none()) {
+        return false;
+    }
+    let signed_by_creator = match &tx.transaction.transaction {
+        solana_transaction_status::EncodedTransaction::Json(ui_transaction) => match &ui_transaction.message {
+            solana_transaction_status::UiMessage::Parsed(parsed_message) => parsed_message.account_keys.iter()
+                .any(|account| account.signer && account.pubkey == creator),
+            _ => false,
+        },
+        _ => false,
+    };
+    if !signed_by_creator {
+        return false;
+    }
+
+    partially_decoded_instructions(tx).unwrap_or_default().iter().any(|instruction| {
+        instruction.program_id == RAYDIUM_LIQUIDITY_POOL_V4_PROGRAM_ID
+            && bs58::decode(&instruction.data).into_vec().ok().and_then(|data| data.first().copied()) == Some(RAYDIUM_WITHDRAW)
+    })
+}
+
+// How many of a pool's transactions are sells by someone other than the creator
+fn count_non_creator_sells(transactions: &[EncodedConfirmedTransactionWithStatusMeta], token_mint: &str, creator: Option<&str>) -> usize {
+    transactions.iter().filter(|tx| is_non_creator_sell(tx, token_mint, creator)).count()
 }
 
-fn get_transaction(client: &RpcClient, tx_signature: &Signature) -> Result<EncodedConfirmedTransactionWithStatusMeta> {
+// A sell is any owner other than the creator or the pool authority whose balance of the mint went down
+fn is_non_creator_sell(tx: &EncodedConfirmedTransactionWithStatusMeta, token_mint: &str, creator: Option<&str>) -> bool {
+    let meta = match &tx.transaction.meta {
+        Some(meta) if meta.err.is_none() => meta,
+        _ => return false,
+    };
+    let pre_balances: Vec<_> = Option::from(meta.pre_token_balances.clone()).unwrap_or_default();
+    let post_balances: Vec<_> = Option::from(meta.post_token_balances.clone()).unwrap_or_default();
+
+    post_balances.iter().filter(|post| post.mint == token_mint).any(|post| {
+        let owner: Option<String> = post.owner.clone().into();
+        let owner = match owner {
+            Some(owner) => owner,
+            None => return false,
+        };
+        if owner == RAYDIUM_AUTHORITY_V4 || Some(owner.as_str()) == creator {
+            return false;
+        }
+        let post_amount = post.ui_token_amount.amount.parse::<u64>().unwrap_or(0);
+        let pre_amount = pre_balances.iter()
+            .find(|pre| pre.account_index == post.account_index)
+            .and_then(|pre| pre.ui_token_amount.amount.parse::<u64>().ok())
+            .unwrap_or(0);
+        post_amount < pre_amount
+    })
+}
+
+// The address whose balance of the token moved in a successful swap, other than the pool authority
+fn trader_of(tx: &EncodedConfirmedTransactionWithStatusMeta, token_mint: &str) -> Option<String> {
+    let meta = match &tx.transaction.meta {
+        Some(meta) if meta.err.is_none() => meta,
+        _ => return None,
+    };
+    let pre_balances: Vec<_> = Option::from(meta.pre_token_balances.clone()).unwrap_or_default();
+    let post_balances: Vec<_> = Option::from(meta.post_token_balances.clone()).unwrap_or_default();
+
+    post_balances.iter().filter(|post| post.mint == token_mint).find_map(|post| {
+        let owner: Option<String> = post.owner.clone().into();
+        let owner = owner.filter(|owner| owner != RAYDIUM_AUTHORITY_V4)?;
+        let pre_amount = pre_balances.iter()
+            .find(|pre| pre.account_index == post.account_index)
+            .map(|pre| pre.ui_token_amount.amount.as_str())
+            .unwrap_or("0");
+        (post.ui_token_amount.amount != pre_amount).then_some(owner)
+    })
+}
+
+async fn fetch_new_jupiter_tokens(http: &reqwest::Client, url: &str) -> Result<Vec<JupiterNewToken>> {
+    let response = http.get(url).send().await?;
+    if !response.status().is_success() {
+        return Err(anyhow!("Jupiter token list returned {}", response.status()));
+    }
+    Ok(response.json().await?)
+}
+
+// Program logs from a preflight simulation failure, if that's what the RPC rejected the transaction with
+fn simulation_logs(err: &ClientError) -> Option<Vec<String>> {
+    match err.kind() {
+        ClientErrorKind::RpcError(RpcError::RpcResponseError {
+            data: RpcResponseErrorData::SendTransactionPreflightFailure(result),
+            ..
+        }) => result.logs.clone(),
+        _ => None,
+    }
+}
+
+// A rejected send: the swap itself failed if simulation or the chain refused it, otherwise the RPC did
+fn send_error(err: &ClientError) -> SniperError {
+    if simulation_logs(err).is_some() || err.get_transaction_error().is_some() {
+        SniperError::SwapFailed(format!("Failed to send transaction: {}", err))
+    } else {
+        SniperError::RpcError(format!("Failed to send transaction: {}", err))
+    }
+}
+
+// Fetched off the async workers, since RpcClient blocks for the whole round trip
+async fn get_transaction(rpc: Arc<RpcFailover>, tx_signature: Signature) -> Result<EncodedConfirmedTransactionWithStatusMeta> {
     info!("===> Getting transaction: {:?}", tx_signature);
-    // let rpc_url = client.url();
+    let commitment = rpc.client().commitment();
     let config = RpcTransactionConfig {
         encoding: Some(UiTransactionEncoding::JsonParsed),
-        commitment: None,
+        // getTransaction doesn't serve processed transactions
+        commitment: Some(if commitment.is_at_least_confirmed() { commitment } else { CommitmentConfig::confirmed() }),
         max_supported_transaction_version: Some(0),
     };
-    // let rpc_client = RpcClient::new(rpc_url.clone());
-    match client.get_transaction_with_config(tx_signature, config) {
+    let result = tokio::task::spawn_blocking(move || rpc.call(|client| Ok(client.get_transaction_with_config(&tx_signature, config)?))).await?;
+    match result {
         Ok(tx) => {
             Ok(tx)
         }
@@ -230,75 +1252,766 @@ fn get_transaction(client: &RpcClient, tx_signature: &Signature) -> Result<Encod
     }
 }
 
-fn decode_transaction(fetched_tx: &EncodedConfirmedTransactionWithStatusMeta) -> Result<Vec<WebSocketMessage>> {
-    // List of pull updates
-    let mut pull_updates = Vec::new();
-    
-    match &fetched_tx.transaction.transaction {
-        solana_transaction_status::EncodedTransaction::Json(ui_transaction) => {
-            info!("===> Processing JsonParsed transaction: {:?}", ui_transaction.signatures);
-            
-            // Extract account keys from the UiParsedMessage
-            match &ui_transaction.message.clone() {
-                solana_transaction_status::UiMessage::Parsed(parsed_message) => {
-                    // let account_keys: Vec<String> = parsed_message.account_keys.iter()
-                    //     .map(|account| account.pubkey.clone())
-                    //     .collect();
-                    // Process each instruction
-                    for instruction in parsed_message.instructions.iter() {
-                        match instruction {
-                            solana_transaction_status::UiInstruction::Parsed(parsed_instruction) => {
-                                match parsed_instruction {
-                                    solana_transaction_status::UiParsedInstruction::PartiallyDecoded(partially_decoded) => {
-                                        // Handle partially decoded instructions (like Raydium instructions)
-                                        if partially_decoded.program_id == RAYDIUM_LIQUIDITY_POOL_V4_PROGRAM_ID && partially_decoded.accounts.len() > 9 {
-                                            // For partially decoded instructions, we need to use the account indices
-                                                let pool_addr = partially_decoded.accounts[4].clone();
-                                                let token_a = partially_decoded.accounts[8].clone();
-                                                let token_b = partially_decoded.accounts[9].clone();
-                                                
-                                                let pool_update = WebSocketMessage::PoolUpdate(PoolUpdate {
-                                                    pool_address: pool_addr.clone(),
-                                                    token_a: token_a.clone(),
-                                                    token_b: token_b.clone(),
-                                                    liquidity: 0.0, 
-                                                    volume_24h: 0.0,
-                                                    timestamp: chrono::Utc::now().timestamp() as u64,
-                                                });
-                                                pull_updates.push(pool_update);
-                                                info!("===> Created pool update for pool: {}, token_a: {}, token_b: {}", 
-                                                      pool_addr, token_a, token_b);
-                                        } else {
-                                            info!("===> Partially decoded instruction is not a Raydium instruction: {:?}", partially_decoded.program_id);
-                                        }
-                                    }
-                                    _ => {
-                                        error!("Instruction is not PartiallyDecoded type: {:?}", instruction);
-                                    }
-                                }
-                            }
-                            // solana_transaction_status::UiInstruction::Compiled(compiled_instruction) => {
-                            //     info!("===> Compiled instruction: {:?}", compiled_instruction);
-                            // }
-                            // solana_transaction_status::UiInstruction::PartiallyDecoded(partially_decoded) => {
-                            //     info!("===> Partially decoded instruction: {:?}", partially_decoded);
-                            // }
-                            _ => {
-                                error!("Instruction is not Parsed type: {:?}", instruction);
-                            }
-                        }
-                    }
-                }
-                _ => {
-                    error!("Message is not a UiParsedMessage: {:?}", ui_transaction.message);
+// A message's instructions with their accounts resolved, loaded ones included, so they can be compiled again
+fn decompile_instructions(message: &VersionedMessage, lookup_tables: &[AddressLookupTableAccount]) -> Result<Vec<Instruction>> {
+    // Loaded accounts follow the static keys: every table's writable ones, then every table's readonly ones
+    let mut keys = message.static_account_keys().to_vec();
+    let lookups = message.address_table_lookups().unwrap_or_default();
+    for writable in [true, false] {
+        for lookup in lookups {
+            let table = lookup_tables.iter().find(|table| table.key == lookup.account_key)
+                .ok_or_else(|| anyhow!("Lookup table {} was not loaded", lookup.account_key))?;
+            let indexes = if writable { &lookup.writable_indexes } else { &lookup.readonly_indexes };
+            for index in indexes {
+                keys.push(*table.addresses.get(*index as usize)
+                    .ok_or_else(|| anyhow!("Lookup table {} has no address {}", lookup.account_key, index))?);
+            }
+        }
+    }
+
+    let key = |index: usize| keys.get(index).copied().ok_or_else(|| anyhow!("Message has no account {}", index));
+    message.instructions().iter().map(|instruction| Ok(Instruction {
+        program_id: key(instruction.program_id_index as usize)?,
+        accounts: instruction.accounts.iter().map(|index| {
+            let index = *index as usize;
+            Ok(AccountMeta { pubkey: key(index)?, is_signer: message.is_signer(index), is_writable: message.is_maybe_writable(index) })
+        }).collect::<Result<_>>()?,
+        data: instruction.data.clone(),
+    })).collect()
+}
+
+// What waiting on a log subscription produced
+#[derive(Debug, PartialEq)]
+enum NextLog<T> {
+    Log(T),
+    // The subscription closed
+    Ended,
+    // Nothing arrived within the idle timeout
+    Idle,
+}
+
+// Wait for a subscription's next log, giving up after idle_timeout_ms of silence (0 waits forever). A healthy
+// subscription always sees traffic, so an idle window means it silently died and should be re-subscribed.
+async fn next_log<S: futures_util::Stream + Unpin>(logs: &mut S, idle_timeout_ms: u64) -> NextLog<S::Item> {
+    let next = if idle_timeout_ms > 0 {
+        match tokio::time::timeout(Duration::from_millis(idle_timeout_ms), logs.next()).await {
+            Ok(next) => next,
+            Err(_) => return NextLog::Idle,
+        }
+    } else {
+        logs.next().await
+    };
+    next.map_or(NextLog::Ended, NextLog::Log)
+}
+
+// Replace a swap's compute budget instructions with our own at compute_unit_price, keeping the unit limit it set
+fn with_compute_unit_price(instructions: Vec<Instruction>, compute_unit_price: u64) -> Vec<Instruction> {
+    let compute_unit_limit = instructions.iter().find_map(priority_fee::requested_compute_unit_limit)
+        .unwrap_or(priority_fee::SWAP_COMPUTE_UNIT_LIMIT);
+    let mut with_price = priority_fee::compute_budget_instructions(compute_unit_price, compute_unit_limit);
+    with_price.extend(instructions.into_iter().filter(|instruction| instruction.program_id != compute_budget::id()));
+    with_price
+}
+
+// Add a setup instruction after a swap's compute budget instructions, raising any unit limit they set to cover it
+fn with_setup_instruction(mut instructions: Vec<Instruction>, setup: Instruction) -> Vec<Instruction> {
+    for instruction in &mut instructions {
+        if let Some(limit) = priority_fee::requested_compute_unit_limit(instruction) {
+            *instruction = ComputeBudgetInstruction::set_compute_unit_limit(limit.saturating_add(priority_fee::CREATE_TOKEN_ACCOUNT_COMPUTE_UNIT_LIMIT));
+        }
+    }
+    let position = instructions.iter().position(|instruction| instruction.program_id != compute_budget::id()).unwrap_or(instructions.len());
+    instructions.insert(position, setup);
+    instructions
+}
+
+// Compile instructions into a message of the same version and blockhash as `original`
+fn compile_message(original: &VersionedMessage, payer: &Pubkey, instructions: &[Instruction], lookup_tables: &[AddressLookupTableAccount]) -> Result<VersionedMessage> {
+    let recent_blockhash = *original.recent_blockhash();
+    Ok(match original {
+        VersionedMessage::Legacy(_) => VersionedMessage::Legacy(Message::new_with_blockhash(instructions, Some(payer), &recent_blockhash)),
+        VersionedMessage::V0(_) => VersionedMessage::V0(v0::Message::try_compile(payer, instructions, lookup_tables, recent_blockhash)?),
+    })
+}
+
+// Raydium initialize2 data: discriminator u8, nonce u8, open_time u64, init_pc_amount u64, init_coin_amount u64
+fn decode_initialize2_data(data: &str) -> Option<(u64, u64, u64)> {
+    let bytes = bs58::decode(data).into_vec().ok()?;
+    if bytes.len() < 26 {
+        return None;
+    }
+    let read_u64 = |offset: usize| u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap());
+    Some((read_u64(2), read_u64(10), read_u64(18)))
+}
+
+fn decode_transaction(fetched_tx: &EncodedConfirmedTransactionWithStatusMeta, variants: &[PoolCreationVariant], emit_pool_details: bool) -> Result<Vec<WebSocketMessage>> {
+    let mut pool_updates = Vec::new();
+    for instruction in partially_decoded_instructions(fetched_tx)? {
+        if instruction.program_id != RAYDIUM_LIQUIDITY_POOL_V4_PROGRAM_ID {
+            info!("===> Partially decoded instruction is not a Raydium instruction: {:?}", instruction.program_id);
+            continue;
+        }
+        let Some(variant) = match_pool_variant(fetched_tx, instruction, variants) else {
+            continue;
+        };
+
+        // Only initialize2 carries the initial amounts; decode_initialize2_data rejects the shorter initialize payload
+        let initialize2 = decode_initialize2_data(&instruction.data);
+        let liquidity = initialize2
+            .and_then(|(_, init_pc_amount, init_coin_amount)| sol_side_liquidity(instruction, variant, init_coin_amount, init_pc_amount))
+            .unwrap_or(UNKNOWN_LIQUIDITY);
+        let details = if emit_pool_details {
+            initialize2.map(|(open_time, init_pc_amount, init_coin_amount)| PoolDetails {
+                creator: variant.creator_account.and_then(|index| instruction.accounts.get(index).cloned()),
+                lp_mint: variant.lp_mint_account.and_then(|index| instruction.accounts.get(index).cloned()),
+                open_time,
+                init_base_amount: init_coin_amount,
+                init_quote_amount: init_pc_amount,
+            })
+        } else {
+            None
+        };
+        pool_updates.push(pool_update_for_variant(instruction, variant, liquidity, details));
+    }
+    Ok(dedup_pool_updates(pool_updates))
+}
+
+// The most recent `capacity` keys seen, forgetting the oldest first; a capacity of 0 remembers nothing
+struct RecentlySeen {
+    capacity: usize,
+    order: VecDeque<String>,
+    keys: HashSet<String>,
+}
+
+impl RecentlySeen {
+    fn new(capacity: usize) -> Self {
+        RecentlySeen { capacity, order: VecDeque::new(), keys: HashSet::new() }
+    }
+
+    // True the first time a key is seen within the window
+    fn insert(&mut self, key: &str) -> bool {
+        if self.capacity == 0 {
+            return true;
+        }
+        if self.keys.contains(key) {
+            return false;
+        }
+        if self.order.len() == self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.keys.remove(&oldest);
+            }
+        }
+        self.order.push_back(key.to_string());
+        self.keys.insert(key.to_string());
+        true
+    }
+}
+
+// One update per pool: a transaction can carry several matching instructions for the same pool
+fn dedup_pool_updates(messages: Vec<WebSocketMessage>) -> Vec<WebSocketMessage> {
+    let mut seen_pools = HashSet::new();
+    messages.into_iter()
+        .filter(|message| match message {
+            WebSocketMessage::PoolUpdate(pool) => {
+                let first = seen_pools.insert(pool.pool_address.clone());
+                if !first {
+                    debug!("Dropping duplicate update for pool {} from the same transaction", pool.pool_address);
                 }
+                first
+            }
+            _ => true,
+        })
+        .collect()
+}
+
+// SOL deposited into a new Raydium pool (coin is mint A, pc is mint B), or None when neither side is SOL
+fn sol_side_liquidity(instruction: &UiPartiallyDecodedInstruction, variant: &PoolCreationVariant, init_coin_amount: u64, init_pc_amount: u64) -> Option<f64> {
+    let lamports = if instruction.accounts[variant.mint_b_account] == SOL_MINT {
+        init_pc_amount
+    } else if instruction.accounts[variant.mint_a_account] == SOL_MINT {
+        init_coin_amount
+    } else {
+        return None;
+    };
+    Some(lamports as f64 / LAMPORTS_PER_SOL as f64)
+}
+
+// The variant whose discriminator starts the instruction data, if the accounts at its indices look like a real pool
+fn match_pool_variant<'a>(fetched_tx: &EncodedConfirmedTransactionWithStatusMeta, instruction: &UiPartiallyDecodedInstruction, variants: &'a [PoolCreationVariant]) -> Option<&'a PoolCreationVariant> {
+    let data = bs58::decode(&instruction.data).into_vec().unwrap_or_default();
+    let variant = variants.iter().find(|variant| variant.matches(&data))?;
+    if instruction.accounts.len() <= variant.max_account_index() {
+        warn!("{} pool creation has too few accounts: {}", variant.name, instruction.accounts.len());
+        return None;
+    }
+    if let Err(reason) = check_pool_accounts(fetched_tx, instruction, variant) {
+        warn!("Skipping {} instruction with an unexpected account layout: {}", variant.name, reason);
+        return None;
+    }
+    Some(variant)
+}
+
+// A pool's two mints are distinct from each other and the pool, and each backs a token account
+// (the pool's vaults) in the creating transaction; anything else means the indices don't fit this layout
+fn check_pool_accounts(fetched_tx: &EncodedConfirmedTransactionWithStatusMeta, instruction: &UiPartiallyDecodedInstruction, variant: &PoolCreationVariant) -> Result<(), String> {
+    let pool = &instruction.accounts[variant.pool_account];
+    let mint_a = &instruction.accounts[variant.mint_a_account];
+    let mint_b = &instruction.accounts[variant.mint_b_account];
+    if mint_a == mint_b || pool == mint_a || pool == mint_b {
+        return Err(format!("pool {} and mints {} / {} are not distinct", pool, mint_a, mint_b));
+    }
+
+    let post_balances: Vec<_> = fetched_tx.transaction.meta.as_ref()
+        .and_then(|meta| Option::from(meta.post_token_balances.clone()))
+        .unwrap_or_default();
+    for mint in [mint_a, mint_b] {
+        if !post_balances.iter().any(|balance| &balance.mint == mint) {
+            return Err(format!("{} is not the mint of any token account in the transaction", mint));
+        }
+    }
+    Ok(())
+}
+
+fn pool_update_for_variant(instruction: &UiPartiallyDecodedInstruction, variant: &PoolCreationVariant, liquidity: f64, details: Option<PoolDetails>) -> WebSocketMessage {
+    let pool_addr = instruction.accounts[variant.pool_account].clone();
+    let token_a = instruction.accounts[variant.mint_a_account].clone();
+    let token_b = instruction.accounts[variant.mint_b_account].clone();
+    info!("===> Created pool update ({}) for pool: {}, token_a: {}, token_b: {}", variant.name, pool_addr, token_a, token_b);
+    WebSocketMessage::PoolUpdate(PoolUpdate {
+        pool_address: pool_addr,
+        token_a,
+        token_b,
+        liquidity,
+        volume_24h: 0.0,
+        timestamp: chrono::Utc::now().timestamp() as u64,
+        details,
+        program_id: Some(instruction.program_id.clone()),
+    })
+}
+
+// Top-level instructions of a JsonParsed transaction that the RPC could only partially decode (i.e. non-native programs)
+fn partially_decoded_instructions(fetched_tx: &EncodedConfirmedTransactionWithStatusMeta) -> Result<Vec<&UiPartiallyDecodedInstruction>> {
+    let ui_transaction = match &fetched_tx.transaction.transaction {
+        solana_transaction_status::EncodedTransaction::Json(ui_transaction) => ui_transaction,
+        _ => return Err(anyhow!("Transaction is not in JsonParsed format")),
+    };
+    let parsed_message = match &ui_transaction.message {
+        solana_transaction_status::UiMessage::Parsed(parsed_message) => parsed_message,
+        _ => return Err(anyhow!("Message is not a UiParsedMessage")),
+    };
+    Ok(parsed_message.instructions.iter()
+        .filter_map(|instruction| match instruction {
+            solana_transaction_status::UiInstruction::Parsed(
+                solana_transaction_status::UiParsedInstruction::PartiallyDecoded(partially_decoded),
+            ) => Some(partially_decoded),
+            _ => None,
+        })
+        .collect())
+}
+
+fn decode_orca_transaction(fetched_tx: &EncodedConfirmedTransactionWithStatusMeta, variants: &[PoolCreationVariant], _emit_pool_details: bool) -> Result<Vec<WebSocketMessage>> {
+    let mut pool_updates = Vec::new();
+    for instruction in partially_decoded_instructions(fetched_tx)? {
+        if instruction.program_id != ORCA_WHIRLPOOL_PROGRAM_ID {
+            continue;
+        }
+        if let Some(variant) = match_pool_variant(fetched_tx, instruction, variants) {
+            // A Whirlpool is created empty; liquidity arrives in later position instructions
+            pool_updates.push(pool_update_for_variant(instruction, variant, UNKNOWN_LIQUIDITY, None));
+        }
+    }
+    Ok(dedup_pool_updates(pool_updates))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+    use solana_sdk::transaction::TransactionError;
+    use crate::mock_server::MockServer;
+
+    // A DexMonitor whose HTTP APIs and RPC endpoint are all served from url
+    fn dex_monitor(url: &str) -> DexMonitor {
+        dex_monitor_with_rpcs(url, &[url.to_string()])
+    }
+
+    // A DexMonitor whose HTTP APIs are served from url, failing over between rpc_urls
+    fn dex_monitor_with_rpcs(url: &str, rpc_urls: &[String]) -> DexMonitor {
+        let config = DexConfig {
+            raydium_api_url: url.to_string(),
+            orca_api_url: url.to_string(),
+            jupiter_api_url: url.to_string(),
+            birdeye_api_url: url.to_string(),
+            solscan_api_url: url.to_string(),
+            birdeye_api_key: SecretString::from("test-key"),
+            metadata_retry: RetryPolicy { max_retries: 2, base_delay_ms: 1, max_delay_ms: 1 },
+            metadata_cache_ttl: Duration::from_secs(60),
+            jupiter_tokens_api_url: url.to_string(),
+            jupiter_api_key: SecretString::default(),
+        };
+        let rpc = Arc::new(RpcFailover::new(rpc_urls, CommitmentConfig::confirmed(), 3));
+        let telegram: crate::telegram::TelegramConfig = serde_json::from_value(json!({ "bot_token": "", "chat_id": "" })).unwrap();
+        DexMonitor::new(config, rpc, TelegramSender::new(telegram))
+    }
+
+    // Birdeye's overview and security endpoints for one token
+    fn birdeye_token(path: &str) -> (u16, Value) {
+        if path.starts_with("/defi/token_overview") {
+            (200, json!({ "success": true, "data": { "symbol": "NEW", "name": "New Token", "decimals": 6, "supply": 1_000_000.0, "mc": 50_000.0, "holder": 120 } }))
+        } else {
+            (200, json!({ "success": true, "data": { "creatorAddress": "Creator111", "jupStrictList": false } }))
+        }
+    }
+
+    fn unique_keys(count: usize) -> Vec<String> {
+        (0..count).map(|_| Pubkey::new_unique().to_string()).collect()
+    }
+
+    fn instruction(program_id: &str, accounts: &[String], data: &[u8]) -> Value {
+        json!({ "programId": program_id, "accounts": accounts, "data": bs58::encode(data).into_string(), "stackHeight": null })
+    }
+
+    // A successful JsonParsed transaction signed by `signers`, with a token account for each of `mints`
+    fn transaction(signers: &[&str], instructions: Vec<Value>, mints: &[&str]) -> EncodedConfirmedTransactionWithStatusMeta {
+        let account_keys: Vec<Value> = signers.iter()
+            .map(|pubkey| json!({ "pubkey": pubkey, "writable": true, "signer": true, "source": "transaction" }))
+            .collect();
+        let post_token_balances: Vec<Value> = mints.iter().enumerate()
+            .map(|(index, mint)| json!({
+                "accountIndex": index,
+                "mint": mint,
+                "uiTokenAmount": { "uiAmount": 1.0, "decimals": 9, "amount": "1000000000", "uiAmountString": "1" },
+            }))
+            .collect();
+        serde_json::from_value(json!({
+            "slot": 1,
+            "blockTime": null,
+            "transaction": {
+                "signatures": [Signature::default().to_string()],
+                "message": { "accountKeys": account_keys, "recentBlockhash": Pubkey::default().to_string(), "instructions": instructions },
+            },
+            "meta": {
+                "err": null,
+                "status": { "Ok": null },
+                "fee": 5_000,
+                "preBalances": [],
+                "postBalances": [],
+                "postTokenBalances": post_token_balances,
+            },
+        })).unwrap()
+    }
+
+    // initialize2 data: tag, nonce, open_time, init_pc_amount, init_coin_amount
+    fn initialize2_data(open_time: u64, init_pc_amount: u64, init_coin_amount: u64) -> Vec<u8> {
+        let mut data = vec![RAYDIUM_INITIALIZE2, 254];
+        for value in [open_time, init_pc_amount, init_coin_amount] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data
+    }
+
+    fn quote(input_amount: u64, output_amount: u64) -> SwapInfo {
+        SwapInfo {
+            input_token: SOL_MINT.to_string(),
+            output_token: "mint".to_string(),
+            input_amount,
+            output_amount,
+            slippage: 0.0,
+            route: Vec::new(),
+            price_impact: 0.0,
+            fee_amount: 0,
+            quote_response: Value::Null,
+        }
+    }
+
+    // A successful transaction moving each (owner, pre, post) token account of `mint` from pre to post
+    fn balance_changes(mint: &str, changes: &[(&str, u64, u64)]) -> EncodedConfirmedTransactionWithStatusMeta {
+        let balances = |amount: fn(&(&str, u64, u64)) -> u64| -> Value {
+            changes.iter().enumerate()
+                .map(|(index, change)| json!({
+                    "accountIndex": index,
+                    "mint": mint,
+                    "owner": change.0,
+                    "uiTokenAmount": { "uiAmount": null, "decimals": 6, "amount": amount(change).to_string(), "uiAmountString": "" },
+                }))
+                .collect()
+        };
+        let mut tx = transaction(&[changes[0].0], Vec::new(), &[]);
+        let meta = tx.transaction.meta.as_mut().unwrap();
+        meta.pre_token_balances = serde_json::from_value(balances(|change| change.1)).unwrap();
+        meta.post_token_balances = serde_json::from_value(balances(|change| change.2)).unwrap();
+        tx
+    }
+
+    fn pool_updates(messages: Vec<WebSocketMessage>) -> Vec<PoolUpdate> {
+        messages.into_iter()
+            .filter_map(|message| match message {
+                WebSocketMessage::PoolUpdate(pool) => Some(pool),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn matching_quotes_agree() {
+        assert_eq!(quote_price_divergence(&quote(1_000, 5_000), &quote(2_000, 10_000)), 0.0);
+    }
+
+    #[test]
+    fn diverging_quotes_are_measured_against_the_first() {
+        assert_eq!(quote_price_divergence(&quote(1_000, 5_000), &quote(1_000, 4_000)), 0.2);
+        assert_eq!(quote_price_divergence(&quote(1_000, 4_000), &quote(1_000, 5_000)), 0.25);
+    }
+
+    #[test]
+    fn an_empty_quote_diverges_from_everything() {
+        assert_eq!(quote_price_divergence(&quote(0, 5_000), &quote(1_000, 5_000)), f64::INFINITY);
+    }
+
+    #[test]
+    fn top_traders_volume_is_discounted() {
+        let trades_by_trader = HashMap::from([("wash".to_string(), 6), ("other".to_string(), 3), ("third".to_string(), 1)]);
+        assert_eq!(wash_discounted_volume(100.0, &trades_by_trader, 1), 40.0);
+        assert_eq!(wash_discounted_volume(100.0, &trades_by_trader, 2), 10.0);
+        assert_eq!(wash_discounted_volume(100.0, &trades_by_trader, 0), 100.0);
+    }
+
+    #[test]
+    fn no_trades_leave_no_volume() {
+        assert_eq!(wash_discounted_volume(100.0, &HashMap::new(), 1), 0.0);
+    }
+
+    #[test]
+    fn initialize2_is_decoded_with_its_amounts() {
+        let mut accounts = unique_keys(18);
+        accounts[9] = SOL_MINT.to_string();
+        let data = initialize2_data(1_700_000_000, 5 * LAMPORTS_PER_SOL, 1_000_000);
+        let tx = transaction(&[&accounts[17]], vec![instruction(RAYDIUM_LIQUIDITY_POOL_V4_PROGRAM_ID, &accounts, &data)], &[&accounts[8], SOL_MINT]);
+
+        let updates = pool_updates(decode_transaction(&tx, &default_raydium_pool_variants(), true).unwrap());
+        assert_eq!(updates.len(), 1);
+        let pool = &updates[0];
+        assert_eq!(pool.pool_address, accounts[4]);
+        assert_eq!(pool.token_a, accounts[8]);
+        assert_eq!(pool.token_b, SOL_MINT);
+        assert_eq!(pool.liquidity, 5.0);
+        let details = pool.details.as_ref().unwrap();
+        assert_eq!(details.creator.as_deref(), Some(accounts[17].as_str()));
+        assert_eq!(details.lp_mint.as_deref(), Some(accounts[7].as_str()));
+        assert_eq!(details.open_time, 1_700_000_000);
+        assert_eq!(details.init_base_amount, 1_000_000);
+        assert_eq!(details.init_quote_amount, 5 * LAMPORTS_PER_SOL);
+    }
+
+    #[test]
+    fn initialize_is_decoded_without_amounts() {
+        let accounts = unique_keys(17);
+        let tx = transaction(&[&accounts[16]], vec![instruction(RAYDIUM_LIQUIDITY_POOL_V4_PROGRAM_ID, &accounts, &[RAYDIUM_INITIALIZE, 254])], &[&accounts[7], &accounts[8]]);
+
+        let updates = pool_updates(decode_transaction(&tx, &default_raydium_pool_variants(), true).unwrap());
+        assert_eq!(updates.len(), 1);
+        let pool = &updates[0];
+        assert_eq!(pool.pool_address, accounts[3]);
+        assert_eq!(pool.token_a, accounts[7]);
+        assert_eq!(pool.token_b, accounts[8]);
+        assert_eq!(pool.liquidity, UNKNOWN_LIQUIDITY);
+        assert!(pool.details.is_none());
+    }
+
+    #[test]
+    fn other_programs_and_instructions_are_ignored() {
+        let accounts = unique_keys(18);
+        let tx = transaction(&[&accounts[17]], vec![
+            instruction(ORCA_WHIRLPOOL_PROGRAM_ID, &accounts, &initialize2_data(0, 1, 1)),
+            instruction(RAYDIUM_LIQUIDITY_POOL_V4_PROGRAM_ID, &accounts, &[RAYDIUM_WITHDRAW]),
+        ], &[&accounts[8], &accounts[9]]);
+
+        assert!(decode_transaction(&tx, &default_raydium_pool_variants(), true).unwrap().is_empty());
+    }
+
+    #[test]
+    fn repeated_instructions_for_one_pool_emit_one_update() {
+        let accounts = unique_keys(18);
+        let raydium_instruction = instruction(RAYDIUM_LIQUIDITY_POOL_V4_PROGRAM_ID, &accounts, &initialize2_data(0, 1, 1));
+        let tx = transaction(&[&accounts[17]], vec![raydium_instruction.clone(), raydium_instruction], &[&accounts[8], &accounts[9]]);
+
+        let updates = pool_updates(decode_transaction(&tx, &default_raydium_pool_variants(), true).unwrap());
+        assert_eq!(updates.len(), 1);
+        assert_eq!(updates[0].pool_address, accounts[4]);
+    }
+
+    #[test]
+    fn a_signature_is_only_new_once() {
+        let mut seen_signatures = RecentlySeen::new(2);
+        assert!(seen_signatures.insert("sig1"));
+        assert!(!seen_signatures.insert("sig1"));
+        assert!(seen_signatures.insert("sig2"));
+        assert!(!seen_signatures.insert("sig1"));
+    }
+
+    #[test]
+    fn the_oldest_signature_is_forgotten_first() {
+        let mut seen_signatures = RecentlySeen::new(2);
+        seen_signatures.insert("sig1");
+        seen_signatures.insert("sig2");
+        assert!(seen_signatures.insert("sig3"));
+        assert!(seen_signatures.insert("sig1"));
+        assert!(!seen_signatures.insert("sig3"));
+    }
+
+    #[test]
+    fn zero_capacity_remembers_nothing() {
+        let mut seen_signatures = RecentlySeen::new(0);
+        assert!(seen_signatures.insert("sig1"));
+        assert!(seen_signatures.insert("sig1"));
+    }
+
+    #[test]
+    fn creator_signed_withdraw_is_a_liquidity_removal() {
+        let accounts = unique_keys(3);
+        let creator = &accounts[2];
+        let tx = transaction(&[creator], vec![instruction(RAYDIUM_LIQUIDITY_POOL_V4_PROGRAM_ID, &accounts, &[RAYDIUM_WITHDRAW, 0, 1])], &[]);
+
+        assert!(is_creator_liquidity_removal(&tx, creator));
+    }
+
+    #[test]
+    fn withdraw_not_signed_by_the_creator_is_not_a_removal() {
+        let accounts = unique_keys(3);
+        let creator = &accounts[2];
+        let tx = transaction(&[&accounts[0]], vec![instruction(RAYDIUM_LIQUIDITY_POOL_V4_PROGRAM_ID, &accounts, &[RAYDIUM_WITHDRAW])], &[]);
+
+        assert!(!is_creator_liquidity_removal(&tx, creator));
+    }
+
+    #[test]
+    fn failed_or_non_withdraw_transactions_are_not_removals() {
+        let accounts = unique_keys(3);
+        let creator = &accounts[2];
+        let mut failed = transaction(&[creator], vec![instruction(RAYDIUM_LIQUIDITY_POOL_V4_PROGRAM_ID, &accounts, &[RAYDIUM_WITHDRAW])], &[]);
+        failed.transaction.meta.as_mut().unwrap().err = Some(TransactionError::InsufficientFundsForFee);
+        let deposit = transaction(&[creator], vec![instruction(RAYDIUM_LIQUIDITY_POOL_V4_PROGRAM_ID, &accounts, &[3])], &[]);
+        let other_program = transaction(&[creator], vec![instruction(ORCA_WHIRLPOOL_PROGRAM_ID, &accounts, &[RAYDIUM_WITHDRAW])], &[]);
+
+        assert!(!is_creator_liquidity_removal(&failed, creator));
+        assert!(!is_creator_liquidity_removal(&deposit, creator));
+        assert!(!is_creator_liquidity_removal(&other_program, creator));
+    }
+
+    #[test]
+    fn sells_by_the_creator_alone_count_for_nothing() {
+        let [mint, creator, trader] = [Pubkey::new_unique().to_string(), Pubkey::new_unique().to_string(), Pubkey::new_unique().to_string()];
+        let creator_sells = vec![
+            balance_changes(&mint, &[(&creator, 1_000, 400), (RAYDIUM_AUTHORITY_V4, 5_000, 5_600)]),
+            balance_changes(&mint, &[(&creator, 400, 0), (RAYDIUM_AUTHORITY_V4, 5_600, 6_000)]),
+        ];
+        let buy = balance_changes(&mint, &[(&trader, 0, 300), (RAYDIUM_AUTHORITY_V4, 6_000, 5_700)]);
+
+        let transactions: Vec<_> = creator_sells.into_iter().chain([buy]).collect();
+        assert_eq!(count_non_creator_sells(&transactions, &mint, Some(&creator)), 0);
+    }
+
+    #[test]
+    fn sells_by_anyone_else_are_counted() {
+        let [mint, creator, trader] = [Pubkey::new_unique().to_string(), Pubkey::new_unique().to_string(), Pubkey::new_unique().to_string()];
+        let mut failed_sell = balance_changes(&mint, &[(&trader, 300, 0)]);
+        failed_sell.transaction.meta.as_mut().unwrap().err = Some(TransactionError::InsufficientFundsForFee);
+        let transactions = vec![
+            balance_changes(&mint, &[(&creator, 1_000, 400)]),
+            balance_changes(&mint, &[(&trader, 300, 100), (RAYDIUM_AUTHORITY_V4, 5_700, 5_900)]),
+            balance_changes(&Pubkey::new_unique().to_string(), &[(&trader, 300, 100)]),
+            failed_sell,
+        ];
+
+        assert_eq!(count_non_creator_sells(&transactions, &mint, Some(&creator)), 1);
+        // Without a known creator, its sells count too
+        assert_eq!(count_non_creator_sells(&transactions, &mint, None), 2);
+    }
+
+    #[test]
+    fn setup_goes_after_the_compute_budget_and_raises_its_limit() {
+        let payer = Pubkey::new_unique();
+        let swap = Instruction::new_with_bytes(Pubkey::new_unique(), &[9], vec![AccountMeta::new(payer, true)]);
+        let setup = Instruction::new_with_bytes(Pubkey::new_unique(), &[1], vec![AccountMeta::new(payer, true)]);
+        let instructions = with_setup_instruction(vec![
+            ComputeBudgetInstruction::set_compute_unit_limit(200_000),
+            ComputeBudgetInstruction::set_compute_unit_price(1_000),
+            swap.clone(),
+        ], setup.clone());
+
+        assert_eq!(instructions, vec![
+            ComputeBudgetInstruction::set_compute_unit_limit(200_000 + priority_fee::CREATE_TOKEN_ACCOUNT_COMPUTE_UNIT_LIMIT),
+            ComputeBudgetInstruction::set_compute_unit_price(1_000),
+            setup.clone(),
+            swap.clone(),
+        ]);
+        assert_eq!(with_setup_instruction(vec![swap.clone()], setup.clone()), vec![setup, swap]);
+    }
+
+    #[test]
+    fn messages_round_trip_through_their_lookup_tables() {
+        let (payer, program, static_account, loaded_writable, loaded_readonly) =
+            (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let lookup_tables = vec![AddressLookupTableAccount { key: Pubkey::new_unique(), addresses: vec![loaded_readonly, loaded_writable] }];
+        let instructions = vec![Instruction::new_with_bytes(program, &[7, 7], vec![
+            AccountMeta::new(payer, true),
+            AccountMeta::new_readonly(static_account, false),
+            AccountMeta::new(loaded_writable, false),
+            AccountMeta::new_readonly(loaded_readonly, false),
+        ])];
+        let message = VersionedMessage::V0(v0::Message::try_compile(&payer, &instructions, &lookup_tables, Default::default()).unwrap());
+        assert_eq!(message.address_table_lookups().unwrap().len(), 1);
+
+        assert_eq!(decompile_instructions(&message, &lookup_tables).unwrap(), instructions);
+        assert!(decompile_instructions(&message, &[]).is_err());
+        let recompiled = compile_message(&message, &payer, &instructions, &lookup_tables).unwrap();
+        assert_eq!(recompiled, message);
+    }
+
+    #[test]
+    fn our_compute_budget_replaces_the_swaps_own() {
+        let payer = Pubkey::new_unique();
+        let swap = Instruction::new_with_bytes(Pubkey::new_unique(), &[9], vec![AccountMeta::new(payer, true)]);
+        let jupiter = vec![
+            ComputeBudgetInstruction::set_compute_unit_limit(180_000),
+            ComputeBudgetInstruction::set_compute_unit_price(5),
+            swap.clone(),
+        ];
+
+        assert_eq!(with_compute_unit_price(jupiter, 100_000), vec![
+            ComputeBudgetInstruction::set_compute_unit_limit(180_000),
+            ComputeBudgetInstruction::set_compute_unit_price(100_000),
+            swap.clone(),
+        ]);
+        assert_eq!(with_compute_unit_price(vec![swap.clone()], 100_000), vec![
+            ComputeBudgetInstruction::set_compute_unit_limit(priority_fee::SWAP_COMPUTE_UNIT_LIMIT),
+            ComputeBudgetInstruction::set_compute_unit_price(100_000),
+            swap,
+        ]);
+    }
+
+    #[tokio::test]
+    async fn metadata_within_the_ttl_comes_from_the_cache() {
+        let birdeye = MockServer::start(|request| birdeye_token(&request.path)).await;
+        let dex_monitor = dex_monitor(birdeye.url());
+
+        let first = dex_monitor.get_token_metadata("Mint111").await.unwrap();
+        let second = dex_monitor.get_token_metadata("Mint111").await.unwrap();
+
+        assert_eq!((second.symbol.as_str(), second.holders, second.creator.as_str()), ("NEW", 120, "Creator111"));
+        assert_eq!(second.total_supply, first.total_supply);
+        // One overview and one security request, both for the first lookup
+        assert_eq!(birdeye.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn a_birdeye_server_error_is_retried() {
+        let overview_requests = std::sync::atomic::AtomicUsize::new(0);
+        let birdeye = MockServer::start(move |request| {
+            if request.path.starts_with("/defi/token_overview") && overview_requests.fetch_add(1, std::sync::atomic::Ordering::SeqCst) == 0 {
+                return (503, json!({ "success": false, "message": "Service Unavailable" }));
             }
-            
-            // Process each instruction
-            Ok(pull_updates)
+            birdeye_token(&request.path)
+        }).await;
+
+        let overview = dex_monitor(birdeye.url()).birdeye_get("/defi/token_overview", "Mint111").await.unwrap();
+
+        assert_eq!(overview["data"]["symbol"], "NEW");
+        let requests = birdeye.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests.iter().all(|request| request.path == "/defi/token_overview?address=Mint111"));
+    }
+
+    #[tokio::test]
+    async fn birdeye_client_errors_are_not_retried() {
+        let birdeye = MockServer::start(|_| (429, json!({ "success": false, "message": "Too many requests" }))).await;
+
+        let error = dex_monitor(birdeye.url()).birdeye_get("/defi/token_overview", "Mint111").await.unwrap_err();
+
+        assert!(error.to_string().contains("429"), "{}", error);
+        assert_eq!(birdeye.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn a_subscription_idle_past_the_timeout_is_given_up_for_a_resubscribe() {
+        let mut silent = futures_util::stream::pending::<u32>();
+        assert_eq!(next_log(&mut silent, 20).await, NextLog::Idle);
+
+        let mut quiet_then_busy = Box::pin(futures_util::stream::once(async {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            7
+        }));
+        assert_eq!(next_log(&mut quiet_then_busy, 1_000).await, NextLog::Log(7));
+        assert_eq!(next_log(&mut quiet_then_busy, 1_000).await, NextLog::Ended);
+    }
+
+    #[tokio::test]
+    async fn without_an_idle_timeout_a_subscription_is_waited_on_until_it_ends() {
+        let mut logs = futures_util::stream::iter([1, 2]);
+
+        assert_eq!(next_log(&mut logs, 0).await, NextLog::Log(1));
+        assert_eq!(next_log(&mut logs, 0).await, NextLog::Log(2));
+        assert_eq!(next_log(&mut logs, 0).await, NextLog::Ended);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn broadcast_sends_to_every_endpoint_and_confirms_once() {
+        let endpoints = [
+            MockServer::start(crate::mock_server::healthy_cluster).await,
+            MockServer::start(crate::mock_server::healthy_cluster).await,
+            MockServer::start(crate::mock_server::healthy_cluster).await,
+        ];
+        let rpc_urls: Vec<String> = endpoints.iter().map(|endpoint| endpoint.url().to_string()).collect();
+        let mut monitor = dex_monitor_with_rpcs(&rpc_urls[0], &rpc_urls);
+        monitor.set_broadcast_to_all_rpcs(true);
+        let payer = Keypair::new();
+        let transfer = solana_sdk::system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 1);
+        let transaction = VersionedTransaction::from(solana_sdk::transaction::Transaction::new_signed_with_payer(
+            &[transfer], Some(&payer.pubkey()), &[&payer], solana_sdk::hash::Hash::new_unique(),
+        ));
+
+        let signature = monitor.send_transaction(&transaction, Some(Duration::from_secs(5))).await.unwrap();
+
+        assert_eq!(signature, transaction.signatures[0].to_string());
+        let calls = |endpoint: &MockServer, method: &str| endpoint.requests().into_iter()
+            .filter(|request| crate::mock_server::rpc_method(request) == Some(method))
+            .collect::<Vec<_>>();
+        for endpoint in &endpoints {
+            let sent = calls(endpoint, "sendTransaction");
+            assert_eq!(sent.len(), 1);
+            assert_eq!(crate::mock_server::sent_transaction(&sent[0]).signatures, transaction.signatures);
         }
-        _ => {
-            Err(anyhow::anyhow!("Transaction is not in JsonParsed format"))
+        // Only the primary is polled, and polling stops at the first confirmation
+        assert_eq!(calls(&endpoints[0], "getSignatureStatuses").len(), 1);
+        assert!(endpoints[1..].iter().all(|endpoint| calls(endpoint, "getSignatureStatuses").is_empty()));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn jupiter_requests_go_to_the_configured_base_url() {
+        // A self-hosted Jupiter API mounted under a path, configured with a trailing slash, next to the RPC node
+        let server = MockServer::start(|request| match request.path.strip_prefix("/jupiter/v6") {
+            Some(path) => crate::mock_server::healthy_cluster(&crate::mock_server::MockRequest { path: path.to_string(), ..request.clone() }),
+            None if request.path == "/" => crate::mock_server::healthy_cluster(request),
+            None => (404, json!({ "error": "not found" })),
+        }).await;
+        let mut monitor = dex_monitor(server.url());
+        monitor.config.jupiter_api_url = format!("{}/jupiter/v6/", server.url());
+
+        monitor.check_jupiter_reachable().await.unwrap();
+        let swap_info = monitor.get_jupiter_quote(SOL_MINT, USDC_MINT, 1_000, 50).await.unwrap();
+        monitor.execute_jupiter_swap(&Keypair::new(), &swap_info, 1_000, Some(Duration::from_secs(5))).await.unwrap();
+
+        let paths: Vec<String> = server.requests().iter()
+            .filter(|request| request.path != "/")
+            .map(|request| request.path.split('?').next().unwrap().to_string())
+            .collect();
+        assert_eq!(paths, vec!["/jupiter/v6/quote", "/jupiter/v6/quote", "/jupiter/v6/swap"]);
+    }
+
+    #[test]
+    fn program_ids_and_mints_are_valid_pubkeys() {
+        for address in [RAYDIUM_LIQUIDITY_POOL_V4_PROGRAM_ID, RAYDIUM_AUTHORITY_V4, ORCA_WHIRLPOOL_PROGRAM_ID, SOL_MINT, USDC_MINT] {
+            assert!(Pubkey::from_str(address).is_ok(), "{}", address);
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,162 @@
+use std::collections::{HashMap, VecDeque};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DumpDetectionConfig {
+    pub enabled: bool,
+    pub holder_drop_percent: f64,
+    pub sell_volume_spike_multiplier: f64,
+    pub window_secs: u64,
+    pub exit_on_dump: bool,
+}
+
+impl Default for DumpDetectionConfig {
+    fn default() -> Self {
+        DumpDetectionConfig {
+            enabled: false,
+            holder_drop_percent: 20.0,
+            sell_volume_spike_multiplier: 3.0,
+            window_secs: 300,
+            exit_on_dump: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct HolderSample {
+    holders: u64,
+    sell_volume: f64,
+    timestamp: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DumpSignal {
+    HolderDrop { from: u64, to: u64, drop_percent: f64 },
+    SellVolumeSpike { baseline: f64, current: f64, multiplier: f64 },
+}
+
+impl std::fmt::Display for DumpSignal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DumpSignal::HolderDrop { from, to, drop_percent } => {
+                write!(f, "holders dropped from {} to {} ({:.2}%)", from, to, drop_percent)
+            }
+            DumpSignal::SellVolumeSpike { baseline, current, multiplier } => {
+                write!(f, "sell volume spiked from {} to {} ({:.2}x)", baseline, current, multiplier)
+            }
+        }
+    }
+}
+
+// Tracks holder counts and sell volume per token over a sliding window
+pub struct DumpDetector {
+    config: DumpDetectionConfig,
+    samples: HashMap<String, VecDeque<HolderSample>>,
+}
+
+impl DumpDetector {
+    pub fn new(config: DumpDetectionConfig) -> Self {
+        DumpDetector {
+            config,
+            samples: HashMap::new(),
+        }
+    }
+
+    // Record a new sample and compare it against the oldest sample still in the window
+    pub fn record(&mut self, token_address: &str, holders: u64, sell_volume: f64, timestamp: u64) -> Option<DumpSignal> {
+        if !self.config.enabled {
+            return None;
+        }
+
+        let window_secs = self.config.window_secs;
+        let samples = self.samples.entry(token_address.to_string()).or_default();
+        while let Some(oldest) = samples.front() {
+            if timestamp.saturating_sub(oldest.timestamp) > window_secs {
+                samples.pop_front();
+            } else {
+                break;
+            }
+        }
+
+        let signal = samples.front().and_then(|baseline| {
+            if baseline.holders > 0 && holders < baseline.holders {
+                let drop_percent = (baseline.holders - holders) as f64 / baseline.holders as f64 * 100.0;
+                if drop_percent >= self.config.holder_drop_percent {
+                    return Some(DumpSignal::HolderDrop {
+                        from: baseline.holders,
+                        to: holders,
+                        drop_percent,
+                    });
+                }
+            }
+            if baseline.sell_volume > 0.0 {
+                let multiplier = sell_volume / baseline.sell_volume;
+                if multiplier >= self.config.sell_volume_spike_multiplier {
+                    return Some(DumpSignal::SellVolumeSpike {
+                        baseline: baseline.sell_volume,
+                        current: sell_volume,
+                        multiplier,
+                    });
+                }
+            }
+            None
+        });
+
+        samples.push_back(HolderSample { holders, sell_volume, timestamp });
+        signal
+    }
+
+    // Forget the history of a token once its position is closed
+    pub fn clear(&mut self, token_address: &str) {
+        self.samples.remove(token_address);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detector() -> DumpDetector {
+        DumpDetector::new(DumpDetectionConfig { enabled: true, ..DumpDetectionConfig::default() })
+    }
+
+    #[test]
+    fn holder_drop_fires() {
+        let mut detector = detector();
+        assert_eq!(detector.record("mint", 1_000, 10.0, 0), None);
+        assert_eq!(detector.record("mint", 900, 10.0, 60), None);
+        let signal = detector.record("mint", 700, 10.0, 120);
+        assert_eq!(signal, Some(DumpSignal::HolderDrop { from: 1_000, to: 700, drop_percent: 30.0 }));
+    }
+
+    #[test]
+    fn sell_volume_spike_fires() {
+        let mut detector = detector();
+        assert_eq!(detector.record("mint", 1_000, 10.0, 0), None);
+        let signal = detector.record("mint", 1_000, 40.0, 60);
+        assert_eq!(signal, Some(DumpSignal::SellVolumeSpike { baseline: 10.0, current: 40.0, multiplier: 4.0 }));
+    }
+
+    #[test]
+    fn samples_outside_the_window_are_not_a_baseline() {
+        let mut detector = detector();
+        detector.record("mint", 1_000, 10.0, 0);
+        detector.record("mint", 700, 10.0, 301);
+        assert_eq!(detector.record("mint", 700, 10.0, 400), None);
+    }
+
+    #[test]
+    fn disabled_detector_never_fires() {
+        let mut detector = DumpDetector::new(DumpDetectionConfig::default());
+        detector.record("mint", 1_000, 10.0, 0);
+        assert_eq!(detector.record("mint", 100, 100.0, 60), None);
+    }
+
+    #[test]
+    fn clear_forgets_the_baseline() {
+        let mut detector = detector();
+        detector.record("mint", 1_000, 10.0, 0);
+        detector.clear("mint");
+        assert_eq!(detector.record("mint", 100, 10.0, 60), None);
+    }
+}
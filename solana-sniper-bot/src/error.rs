@@ -0,0 +1,38 @@
+use thiserror::Error;
+
+use crate::retry;
+
+// Failures callers need to tell apart. They travel inside anyhow::Error like any other error and are
+// recovered with downcast_ref, so the binary and everything above the library keep using anyhow.
+#[derive(Debug, Error)]
+pub enum SniperError {
+    #[error("Insufficient balance: {balance} of {mint}, need {needed}")]
+    InsufficientBalance { balance: f64, mint: String, needed: f64 },
+    #[error("Token failed safety check: {0}")]
+    SafetyCheckFailed(String),
+    #[error("Jupiter quote failed: {0}")]
+    QuoteFailed(String),
+    #[error("Swap failed: {0}")]
+    SwapFailed(String),
+    #[error("RPC error: {0}")]
+    RpcError(String),
+    #[error("Token {0} is not sellable")]
+    TokenNotSellable(String),
+}
+
+impl SniperError {
+    // RPC errors never reached the chain, so they are always worth another try. Quote and swap failures are
+    // only when their upstream was briefly unavailable; the rest are decided and retrying can't change them.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            SniperError::RpcError(_) => true,
+            SniperError::QuoteFailed(message) | SniperError::SwapFailed(message) => retry::is_transient_message(message),
+            SniperError::InsufficientBalance { .. } | SniperError::SafetyCheckFailed(_) | SniperError::TokenNotSellable(_) => false,
+        }
+    }
+}
+
+// The SniperError behind an error, if any
+pub fn sniper_error(error: &anyhow::Error) -> Option<&SniperError> {
+    error.chain().find_map(|cause| cause.downcast_ref::<SniperError>())
+}
@@ -1,42 +1,337 @@
-use anyhow::{anyhow, Result};
-use log::{info, warn, error};
+use anyhow::{anyhow, ensure, Result};
+use log::{debug, info, warn, error};
 use serde::{Deserialize, Serialize};
-use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_request::TokenAccountsFilter;
 use solana_sdk::{
     commitment_config::CommitmentConfig,
     signature::{Keypair, Signer},
     system_instruction,
-    transaction::Transaction,
+    transaction::{Transaction, VersionedTransaction},
     native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
 };
-use solana_sdk::signature::read_keypair_file;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokio::sync::{watch, Mutex, Semaphore};
 
+pub mod approve_list;
+pub mod blacklist;
+pub mod cluster;
 pub mod dex_monitor;
+pub mod dump_detector;
+pub mod error;
+pub mod metrics;
+#[cfg(test)]
+mod mock_server;
+pub mod persistence;
+pub mod positions;
+pub mod priority_fee;
+pub mod retry;
+pub mod rpc_failover;
+pub mod rpc_lag;
+pub mod safety_checks;
+pub mod secrets;
+pub mod sizing;
+pub mod strategy;
 pub mod websocket_monitor;
 pub mod telegram;
+pub mod throttle;
+pub mod token_accounts;
+pub mod trace;
+pub mod tx_builder;
+pub mod wallet_file;
 
 // use dex_monitor::{DexMonitor, TokenMetadata};
-use websocket_monitor::{DexWebSocketManager, WebSocketMessage};
+use dex_monitor::SOL_MINT;
+use futures_util::StreamExt;
+use rpc_failover::RpcFailover;
+use websocket_monitor::{BirdeyePriceMonitor, DexWebSocketManager, PoolUpdate, PriceUpdate, SubscriptionKind, WebSocketMessage, UNKNOWN_LIQUIDITY};
 
-pub use crate::dex_monitor::{DexMonitor, TokenMetadata};
-pub use crate::telegram::{TelegramConfig, TelegramSender};
+pub use crate::approve_list::ApproveList;
+pub use crate::blacklist::Blacklist;
+pub use crate::dex_monitor::{DexMonitor, PoolCreationVariant, SwapInfo, SwapMode, TokenMetadata};
+pub use crate::dump_detector::{DumpDetectionConfig, DumpDetector, DumpSignal};
+pub use crate::error::SniperError;
+pub use crate::persistence::{PersistenceConfig, TradeLog, TradeRecord};
+pub use crate::positions::{ClosedPosition, ExitReason, Position};
+pub use crate::retry::RetryPolicy;
+pub use crate::rpc_failover::EndpointHealth;
+pub use crate::safety_checks::{CheckContext, CheckOutcome, CheckResult, SafetyCheck, TokenAnalysis};
+pub use crate::secrets::{SecretResolver, SecretSource, SecretString, SecretsConfig};
+pub use crate::sizing::SizingConfig;
+pub use crate::strategy::StrategyConfig;
+pub use crate::telegram::{PoolDecision, TelegramConfig, TelegramSender, TradeAlert};
+pub use crate::tx_builder::{BuiltTransaction, SnipeRequest, TransactionBuilder};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SniperConfig {
-    pub rpc_url: String,
-    pub wallets: Vec<String>,
+    // One RPC URL, or a list to fail over through in order
+    pub rpc_url: RpcUrls,
+    // Consecutive endpoint errors (timeouts, refused connections, unhealthy nodes) before failing over
+    #[serde(default = "default_rpc_failover_threshold")]
+    pub rpc_failover_threshold: u32,
+    // Superseded by rpc_url, which is now the one endpoint list. Kept so old configs still parse; validate()
+    // rejects entries missing from rpc_url rather than silently dropping them.
+    #[serde(default)]
+    pub rpc_urls: Vec<String>,
+    // Send every transaction to all of rpc_url at once, not just the active endpoint
+    #[serde(default)]
+    pub broadcast_to_all_rpcs: bool,
+    // Commitment for RPC reads, log subscriptions and transaction fetches: processed, confirmed or finalized
+    #[serde(default = "default_commitment")]
+    pub commitment: String,
+    // Alert on, and stop broadcasting to, any of rpc_url this many slots behind the others
+    #[serde(default)]
+    pub max_rpc_slot_lag: Option<u64>,
+    #[serde(default = "default_rpc_lag_check_interval_ms")]
+    pub rpc_lag_check_interval_ms: u64,
+    pub wallets: Vec<WalletEntry>,
     pub min_sol_balance: f64,
     pub max_sol_per_trade: f64,
     pub slippage_tolerance: f64,
     pub auto_sell: bool,
+    // Buy detected listings automatically; auto_sell only governs exits
+    #[serde(default)]
+    pub auto_buy: bool,
     pub anti_rug_check: bool,
     pub dex_config: DexConfig,
     pub monitoring: MonitoringConfig,
     pub safety: SafetyConfig,
     pub trading: TradingConfig,
     pub telegram: TelegramConfig,
+    #[serde(default)]
+    pub dump_detection: DumpDetectionConfig,
+    #[serde(default)]
+    pub secrets: SecretsConfig,
+    #[serde(default)]
+    pub persistence: PersistenceConfig,
+    #[serde(default)]
+    pub run_id: Option<String>,
+    #[serde(default)]
+    pub sizing: SizingConfig,
+    // Wallets that maintenance operations (feeding, dust sweeps) may act on
+    #[serde(default)]
+    pub maintenance_wallets: WalletFilter,
+    #[serde(default)]
+    pub expected_cluster: Option<String>,
+    // Serve Prometheus metrics at http://0.0.0.0:<port>/metrics while the bot runs
+    #[serde(default)]
+    pub metrics_port: Option<u16>,
+    // Strategies fanned out over every detected listing; none means all wallets snipe as one
+    #[serde(default)]
+    pub strategies: Vec<StrategyConfig>,
+}
+
+// rpc_url is either a single endpoint, as in older configs, or a list of them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum RpcUrls {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl RpcUrls {
+    pub fn urls(&self) -> &[String] {
+        match self {
+            RpcUrls::One(url) => std::slice::from_ref(url),
+            RpcUrls::Many(urls) => urls,
+        }
+    }
+}
+
+// A wallet is either a bare keypair path or an object with per-wallet options
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum WalletEntry {
+    Path(String),
+    Detailed {
+        path: String,
+        #[serde(default)]
+        shadow: bool,
+        // Free-form labels (e.g. "cold", "funding") that wallet filters can match
+        #[serde(default)]
+        tags: Vec<String>,
+    },
+}
+
+impl WalletEntry {
+    pub fn path(&self) -> &str {
+        match self {
+            WalletEntry::Path(path) => path,
+            WalletEntry::Detailed { path, .. } => path,
+        }
+    }
+
+    // Shadow wallets run the full decision pipeline but only paper-trade
+    pub fn is_shadow(&self) -> bool {
+        matches!(self, WalletEntry::Detailed { shadow: true, .. })
+    }
+
+    pub fn tags(&self) -> &[String] {
+        match self {
+            WalletEntry::Path(_) => &[],
+            WalletEntry::Detailed { tags, .. } => tags,
+        }
+    }
+}
+
+// How much distribute_sol sends: a fixed amount to each wallet, or a total split evenly between them
+#[derive(Debug, Clone, Copy)]
+pub enum FundingAmount {
+    PerWallet(f64),
+    Total(f64),
+}
+
+// Which wallets an operation may touch, by index range and tags; the default allows every wallet
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WalletFilter {
+    #[serde(default)]
+    pub min_index: Option<usize>,
+    #[serde(default)]
+    pub max_index: Option<usize>,
+    // When non-empty, a wallet needs at least one of these tags
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub exclude_tags: Vec<String>,
+}
+
+impl WalletFilter {
+    pub fn allows(&self, wallet_index: usize, wallet: &WalletEntry) -> bool {
+        let tags = wallet.tags();
+        self.min_index.is_none_or(|min_index| wallet_index >= min_index)
+            && self.max_index.is_none_or(|max_index| wallet_index <= max_index)
+            && (self.tags.is_empty() || self.tags.iter().any(|tag| tags.contains(tag)))
+            && !self.exclude_tags.iter().any(|tag| tags.contains(tag))
+    }
+}
+
+impl SniperConfig {
+    pub fn commitment_config(&self) -> Result<CommitmentConfig> {
+        match self.commitment.as_str() {
+            "processed" => Ok(CommitmentConfig::processed()),
+            "confirmed" => Ok(CommitmentConfig::confirmed()),
+            "finalized" => Ok(CommitmentConfig::finalized()),
+            other => Err(anyhow!("commitment must be processed, confirmed or finalized (got {:?})", other)),
+        }
+    }
+
+    // Fill secrets left empty in the config from the configured secret sources
+    pub fn resolve_secrets(&mut self, resolver: &SecretResolver) {
+        if self.telegram.bot_token.is_empty() {
+            if let Some(bot_token) = resolver.resolve("TELEGRAM_BOT_TOKEN") {
+                self.telegram.bot_token = bot_token.into();
+            }
+        }
+        if self.dex_config.birdeye_api_key.is_empty() {
+            if let Some(api_key) = resolver.resolve("BIRDEYE_API_KEY") {
+                self.dex_config.birdeye_api_key = api_key.into();
+            }
+        }
+        if self.dex_config.jupiter_api_key.is_empty() {
+            if let Some(api_key) = resolver.resolve("JUPITER_API_KEY") {
+                self.dex_config.jupiter_api_key = api_key.into();
+            }
+        }
+        if self.telegram.chat_id.is_empty() {
+            if let Some(chat_id) = resolver.resolve("TELEGRAM_CHAT_ID") {
+                self.telegram.chat_id = chat_id.to_string();
+            }
+        }
+    }
+
+    // Reject values that would only misbehave later, naming the field and the rule it breaks
+    pub fn validate(&self) -> Result<()> {
+        ensure!(!self.wallets.is_empty() || !self.auto_buy, "auto_buy needs at least one wallet in wallets");
+        self.commitment_config()?;
+        ensure!(!self.rpc_url.urls().is_empty(), "rpc_url needs at least one endpoint");
+        if let Some(rpc_url) = self.rpc_urls.iter().find(|rpc_url| !self.rpc_url.urls().contains(rpc_url)) {
+            return Err(anyhow!("rpc_urls entry {} is not in rpc_url; list every endpoint in rpc_url instead", rpc_url));
+        }
+        ensure!(self.rpc_failover_threshold >= 1, "rpc_failover_threshold must be at least 1");
+        ensure!(self.max_sol_per_trade > 0.0, "max_sol_per_trade must be greater than 0 (got {})", self.max_sol_per_trade);
+        ensure!(self.min_sol_balance >= 0.0, "min_sol_balance must not be negative (got {})", self.min_sol_balance);
+        ensure!(self.slippage_tolerance > 0.0 && self.slippage_tolerance <= 1.0,
+                "slippage_tolerance must be a fraction in (0, 1] (got {})", self.slippage_tolerance);
+
+        let trading = &self.trading;
+        ensure!(trading.min_slippage >= 0.0 && trading.max_slippage <= 1.0,
+                "trading.min_slippage and trading.max_slippage must be fractions in [0, 1] (got {} and {})", trading.min_slippage, trading.max_slippage);
+        ensure!(trading.min_slippage <= trading.max_slippage,
+                "trading.min_slippage ({}) must not exceed trading.max_slippage ({})", trading.min_slippage, trading.max_slippage);
+        ensure!(trading.max_price_impact >= 0.0, "trading.max_price_impact must not be negative (got {})", trading.max_price_impact);
+        ensure!(trading.position_check_interval_ms > 0, "trading.position_check_interval_ms must be greater than 0");
+        ensure!(trading.take_profit_percent.is_none_or(|percent| percent > 0.0),
+                "trading.take_profit_percent must be greater than 0 (got {:?})", trading.take_profit_percent);
+        ensure!(trading.stop_loss_percent.is_none_or(|percent| percent > 0.0 && percent <= 100.0),
+                "trading.stop_loss_percent must be in (0, 100] (got {:?})", trading.stop_loss_percent);
+        ensure!(trading.sell_all_concurrency > 0, "trading.sell_all_concurrency must be at least 1");
+        ensure!(trading.dust_threshold_sol >= 0.0, "trading.dust_threshold_sol must not be negative (got {})", trading.dust_threshold_sol);
+        ensure!(trading.capital_floor_sol.is_none_or(|floor| floor >= 0.0),
+                "trading.capital_floor_sol must not be negative (got {:?})", trading.capital_floor_sol);
+        ensure!(trading.max_quote_divergence.is_none_or(|divergence| divergence >= 0.0),
+                "trading.max_quote_divergence must not be negative (got {:?})", trading.max_quote_divergence);
+        priority_fee::compute_unit_price(&trading.gas_priority, trading.priority_fee_micro_lamports)
+            .map_err(|e| anyhow!("trading.gas_priority: {}", e))?;
+        for allocation in &trading.multi_wallet_snipe {
+            ensure!(allocation.wallet_index < self.wallets.len(),
+                    "trading.multi_wallet_snipe uses wallet {}, but only {} wallets are configured", allocation.wallet_index, self.wallets.len());
+            ensure!(allocation.amount_sol > 0.0,
+                    "trading.multi_wallet_snipe amount for wallet {} must be greater than 0 (got {})", allocation.wallet_index, allocation.amount_sol);
+        }
+
+        ensure!(self.safety.approve_list_file.is_none() || self.safety.approve_list_poll_interval_ms > 0,
+                "safety.approve_list_poll_interval_ms must be greater than 0");
+        ensure!(self.max_rpc_slot_lag.is_none() || self.rpc_lag_check_interval_ms > 0,
+                "rpc_lag_check_interval_ms must be greater than 0");
+        ensure!(self.monitoring.check_interval_ms > 0, "monitoring.check_interval_ms must be greater than 0");
+        ensure!(self.monitoring.max_evaluations_per_second != Some(0), "monitoring.max_evaluations_per_second must be at least 1");
+        ensure!(self.monitoring.max_concurrent_snipes != Some(0), "monitoring.max_concurrent_snipes must be at least 1");
+        ensure!(self.monitoring.websocket_reconnect_delay_ms <= self.monitoring.websocket_max_reconnect_delay_ms,
+                "monitoring.websocket_reconnect_delay_ms ({}) must not exceed monitoring.websocket_max_reconnect_delay_ms ({})",
+                self.monitoring.websocket_reconnect_delay_ms, self.monitoring.websocket_max_reconnect_delay_ms);
+        ensure!(self.wallets.is_empty() || self.monitoring.wallet_selection != WalletSelection::Single || self.monitoring.designated_wallet < self.wallets.len(),
+                "monitoring.designated_wallet {} is out of range for {} wallets", self.monitoring.designated_wallet, self.wallets.len());
+
+        let safety = &self.safety;
+        ensure!(safety.max_creator_holdings_percent >= 0.0 && safety.max_creator_holdings_percent <= 100.0,
+                "safety.max_creator_holdings_percent must be in [0, 100] (got {})", safety.max_creator_holdings_percent);
+        ensure!(safety.min_liquidity_sol >= 0.0, "safety.min_liquidity_sol must not be negative (got {})", safety.min_liquidity_sol);
+        if let (Some(min_token_age_secs), Some(max_token_age_secs)) = (safety.min_token_age_secs, safety.max_token_age_secs) {
+            ensure!(min_token_age_secs <= max_token_age_secs,
+                    "safety.min_token_age_secs ({}) must not exceed safety.max_token_age_secs ({})", min_token_age_secs, max_token_age_secs);
+        }
+        ensure!(safety.min_lp_locked_percent >= 0.0 && safety.min_lp_locked_percent <= 100.0,
+                "safety.min_lp_locked_percent must be between 0 and 100 (got {})", safety.min_lp_locked_percent);
+        ensure!(safety.daily_spend_limit_sol.is_none_or(|limit| limit > 0.0),
+                "safety.daily_spend_limit_sol must be greater than 0 (got {:?})", safety.daily_spend_limit_sol);
+        ensure!(safety.per_wallet_spend_limit_sol.is_none_or(|limit| limit > 0.0),
+                "safety.per_wallet_spend_limit_sol must be greater than 0 (got {:?})", safety.per_wallet_spend_limit_sol);
+
+        let sizing = &self.sizing;
+        ensure!(sizing.min_multiplier > 0.0 && sizing.min_multiplier <= sizing.max_multiplier,
+                "sizing.min_multiplier ({}) must be greater than 0 and not exceed sizing.max_multiplier ({})", sizing.min_multiplier, sizing.max_multiplier);
+        ensure!(sizing.max_portfolio_fraction > 0.0 && sizing.max_portfolio_fraction <= 1.0,
+                "sizing.max_portfolio_fraction must be in (0, 1] (got {})", sizing.max_portfolio_fraction);
+        if sizing.randomize {
+            ensure!(sizing.buy_amount_min > 0.0 && sizing.buy_amount_min <= sizing.buy_amount_max,
+                    "sizing.buy_amount_min ({}) must be greater than 0 and not exceed sizing.buy_amount_max ({})", sizing.buy_amount_min, sizing.buy_amount_max);
+            ensure!(sizing.buy_amount_max <= self.max_sol_per_trade,
+                    "sizing.buy_amount_max ({}) must not exceed max_sol_per_trade ({})", sizing.buy_amount_max, self.max_sol_per_trade);
+        }
+
+        if self.telegram.interactive_buttons {
+            let amount = self.telegram.button_buy_amount_sol;
+            ensure!(amount > 0.0 && amount <= self.max_sol_per_trade,
+                    "telegram.button_buy_amount_sol must be in (0, max_sol_per_trade] (got {})", amount);
+        }
+
+        strategy::validate_strategies(&self.strategies, self.wallets.len())
+            .map_err(|e| anyhow!("strategies: {}", e))?;
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -46,6 +341,43 @@ pub struct DexConfig {
     pub jupiter_api_url: String,
     pub birdeye_api_url: String,
     pub solscan_api_url: String,
+    #[serde(default)]
+    pub birdeye_api_key: SecretString,
+    #[serde(default)]
+    pub metadata_retry: RetryPolicy,
+    // How long fetched token metadata is reused before asking Birdeye again; 0 disables the cache
+    #[serde(default = "default_metadata_cache_ttl_ms")]
+    pub metadata_cache_ttl_ms: u64,
+    #[serde(default = "default_jupiter_tokens_api_url")]
+    pub jupiter_tokens_api_url: String,
+    // Sent as x-api-key to jupiter_api_url, for paid or self-hosted instances behind auth
+    #[serde(default)]
+    pub jupiter_api_key: SecretString,
+}
+
+fn default_metadata_cache_ttl_ms() -> u64 {
+    5_000
+}
+
+fn default_jupiter_tokens_api_url() -> String {
+    "https://lite-api.jup.ag/tokens/v1".to_string()
+}
+
+impl From<&DexConfig> for dex_monitor::DexConfig {
+    fn from(config: &DexConfig) -> Self {
+        dex_monitor::DexConfig {
+            raydium_api_url: config.raydium_api_url.clone(),
+            orca_api_url: config.orca_api_url.clone(),
+            jupiter_api_url: config.jupiter_api_url.clone(),
+            birdeye_api_url: config.birdeye_api_url.clone(),
+            solscan_api_url: config.solscan_api_url.clone(),
+            birdeye_api_key: config.birdeye_api_key.clone(),
+            metadata_retry: config.metadata_retry.clone(),
+            metadata_cache_ttl: std::time::Duration::from_millis(config.metadata_cache_ttl_ms),
+            jupiter_tokens_api_url: config.jupiter_tokens_api_url.clone(),
+            jupiter_api_key: config.jupiter_api_key.clone(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -55,6 +387,110 @@ pub struct MonitoringConfig {
     pub enable_jupiter: bool,
     pub check_interval_ms: u64,
     pub websocket_reconnect_delay_ms: u64,
+    // Cap on the reconnect delay, which doubles from websocket_reconnect_delay_ms after each failed connection
+    #[serde(default = "default_websocket_max_reconnect_delay_ms")]
+    pub websocket_max_reconnect_delay_ms: u64,
+    // Reconnect delay after a provider closes or refuses the connection for rate limiting
+    #[serde(default = "default_websocket_rate_limit_delay_ms")]
+    pub websocket_rate_limit_delay_ms: u64,
+    #[serde(default = "default_log_idle_timeout_ms")]
+    pub log_idle_timeout_ms: u64,
+    #[serde(default = "default_true")]
+    pub emit_pool_details: bool,
+    // How many recent pool transactions and addresses to remember so replays aren't emitted twice (0 disables)
+    #[serde(default = "default_seen_pool_cache_size")]
+    pub seen_pool_cache_size: usize,
+    // Most detected pools and listings to fully evaluate per second across all tokens; the excess is skipped
+    #[serde(default)]
+    pub max_evaluations_per_second: Option<usize>,
+    // Most snipes in flight at once across all wallets; further snipes wait for a slot
+    #[serde(default)]
+    pub max_concurrent_snipes: Option<usize>,
+    // Most DEX monitors to run at once, to stay within RPC limits; enabled sources past the cap are skipped in order
+    #[serde(default)]
+    pub max_monitors: Option<usize>,
+    // Streaming feed of Raydium pool, listing and price updates, alongside the on-chain monitor; raydium_api_url is REST only
+    #[serde(default)]
+    pub raydium_websocket_url: Option<String>,
+    // Stream prices of open positions from Birdeye's WebSocket, so exits react without waiting for the next poll
+    #[serde(default)]
+    pub birdeye_price_feed: bool,
+    // Stream new token listings and pairs from Birdeye's WebSocket; both Birdeye feeds need an API key
+    #[serde(default)]
+    pub birdeye_listing_feed: bool,
+    #[serde(default = "default_birdeye_websocket_url")]
+    pub birdeye_websocket_url: String,
+    #[serde(default = "dex_monitor::default_raydium_pool_variants")]
+    pub raydium_pool_variants: Vec<PoolCreationVariant>,
+    #[serde(default = "dex_monitor::default_orca_pool_variants")]
+    pub orca_pool_variants: Vec<PoolCreationVariant>,
+    // Which wallet buys a listing when neither strategies nor multi_wallet_snipe are configured
+    #[serde(default)]
+    pub wallet_selection: WalletSelection,
+    // Wallet used by the single wallet selection
+    #[serde(default)]
+    pub designated_wallet: usize,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WalletSelection {
+    // Rotate through the wallets, one per listing
+    #[default]
+    RoundRobin,
+    // Always buy from designated_wallet
+    Single,
+    // Buy from the emptiest wallet that can still afford a trade
+    LowestBalanceFirst,
+}
+
+impl FromStr for WalletSelection {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "round_robin" => Ok(WalletSelection::RoundRobin),
+            "single" => Ok(WalletSelection::Single),
+            "lowest_balance_first" => Ok(WalletSelection::LowestBalanceFirst),
+            other => Err(anyhow!("Unknown wallet selection {:?}, expected round_robin, single or lowest_balance_first", other)),
+        }
+    }
+}
+
+fn default_log_idle_timeout_ms() -> u64 {
+    60_000
+}
+
+fn default_birdeye_websocket_url() -> String {
+    "wss://public-api.birdeye.so/socket/solana".to_string()
+}
+
+fn default_websocket_max_reconnect_delay_ms() -> u64 {
+    60_000
+}
+
+fn default_websocket_rate_limit_delay_ms() -> u64 {
+    60_000
+}
+
+fn default_seen_pool_cache_size() -> usize {
+    1000
+}
+
+pub fn default_commitment() -> String {
+    "confirmed".to_string()
+}
+
+fn default_rpc_failover_threshold() -> u32 {
+    3
+}
+
+fn default_rpc_lag_check_interval_ms() -> u64 {
+    10_000
+}
+
+fn default_true() -> bool {
+    true
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -66,6 +502,74 @@ pub struct SafetyConfig {
     pub min_market_cap: f64,
     pub min_holders: u64,
     pub min_volume_24h: f64,
+    #[serde(default)]
+    pub min_successful_sells: usize,
+    #[serde(default = "default_sell_scan_limit")]
+    pub sell_scan_limit: usize,
+    #[serde(default)]
+    pub max_token_tax_percent: Option<f64>,
+    #[serde(default = "default_max_sell_price_impact")]
+    pub max_sell_price_impact: f64,
+    #[serde(default = "default_blacklist_file")]
+    pub blacklist_file: String,
+    // Minimum pool liquidity (in USD) divided by market cap
+    #[serde(default)]
+    pub min_liquidity_to_market_cap_ratio: Option<f64>,
+    // Discount min_volume_24h checks of detected pools by the trades of this many most active addresses
+    #[serde(default)]
+    pub wash_trade_top_traders: Option<usize>,
+    // Confirm a detected pool account exists and is owned by its DEX program before sniping
+    #[serde(default)]
+    pub verify_pool_account: bool,
+    // Most SOL real buys may spend in any rolling 24 hours, across all wallets and per wallet
+    #[serde(default)]
+    pub daily_spend_limit_sol: Option<f64>,
+    #[serde(default)]
+    pub per_wallet_spend_limit_sol: Option<f64>,
+    // Pre-approved mints, one per line and reloaded while running; they skip the safety checks but not the blacklist
+    #[serde(default)]
+    pub approve_list_file: Option<String>,
+    // Snipe mints as soon as they are added to the approve list rather than when their pool or listing appears
+    #[serde(default)]
+    pub snipe_approved_on_add: bool,
+    #[serde(default = "default_approve_list_poll_interval_ms")]
+    pub approve_list_poll_interval_ms: u64,
+    // Only buy mints at least / at most this many seconds old, dated by their first transaction
+    #[serde(default)]
+    pub min_token_age_secs: Option<u64>,
+    #[serde(default)]
+    pub max_token_age_secs: Option<u64>,
+    // Reject mints whose creator can still mint more supply or freeze holders' token accounts
+    #[serde(default)]
+    pub require_mint_authority_revoked: bool,
+    #[serde(default)]
+    pub require_freeze_authority_revoked: bool,
+    // Reject detected pools whose creator still holds more LP tokens than min_lp_locked_percent allows;
+    // burned LP leaves the supply and locked LP leaves the creator's wallet, so both count as locked
+    #[serde(default)]
+    pub require_lp_locked: bool,
+    #[serde(default = "default_min_lp_locked_percent")]
+    pub min_lp_locked_percent: f64,
+}
+
+fn default_min_lp_locked_percent() -> f64 {
+    90.0
+}
+
+fn default_sell_scan_limit() -> usize {
+    100
+}
+
+fn default_max_sell_price_impact() -> f64 {
+    0.25
+}
+
+pub fn default_blacklist_file() -> String {
+    "blacklist.txt".to_string()
+}
+
+fn default_approve_list_poll_interval_ms() -> u64 {
+    2_000
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -74,8 +578,91 @@ pub struct TradingConfig {
     pub min_slippage: f64,
     pub max_slippage: f64,
     pub gas_priority: String,
+    // Explicit compute unit price in micro-lamports, used instead of the gas_priority tier
+    #[serde(default)]
+    pub priority_fee_micro_lamports: Option<u64>,
     pub retry_failed_trades: bool,
     pub max_retries: u32,
+    #[serde(default)]
+    pub multi_wallet_snipe: Vec<WalletAllocation>,
+    #[serde(default)]
+    pub min_output_amount: u64,
+    #[serde(default)]
+    pub take_profit_percent: Option<f64>,
+    #[serde(default)]
+    pub stop_loss_percent: Option<f64>,
+    #[serde(default = "default_position_check_interval_ms")]
+    pub position_check_interval_ms: u64,
+    #[serde(default)]
+    pub exit_on_creator_liquidity_removal: bool,
+    #[serde(default = "default_snipe_confirmation_deadline_ms")]
+    pub snipe_confirmation_deadline_ms: u64,
+    #[serde(default = "default_dust_threshold_sol")]
+    pub dust_threshold_sol: f64,
+    // Most positions sell_all sells at once
+    #[serde(default = "default_sell_all_concurrency")]
+    pub sell_all_concurrency: usize,
+    // Mint buys are paid in and sells are paid out in; amounts, cost basis and PnL are in its units
+    #[serde(default = "default_quote_mint")]
+    pub quote_mint: String,
+    // Re-quote the sell before acting on a take-profit and hold if the quote misses the target
+    #[serde(default)]
+    pub requote_before_take_profit: bool,
+    // Pause auto-buys while the real wallets together hold less than this many SOL
+    #[serde(default)]
+    pub capital_floor_sol: Option<f64>,
+    // Refuse to buy a token again, from any wallet, within this many seconds of the last buy (0 disables)
+    #[serde(default)]
+    pub snipe_cooldown_secs: u64,
+    // Also quote half the buy and skip it when the implied prices differ by more than this fraction
+    #[serde(default)]
+    pub max_quote_divergence: Option<f64>,
+}
+
+fn default_sell_all_concurrency() -> usize {
+    4
+}
+
+pub fn default_quote_mint() -> String {
+    SOL_MINT.to_string()
+}
+
+fn default_position_check_interval_ms() -> u64 {
+    5_000
+}
+
+fn default_snipe_confirmation_deadline_ms() -> u64 {
+    30_000
+}
+
+fn default_dust_threshold_sol() -> f64 {
+    0.001
+}
+
+// One wallet's share of a coordinated multi-wallet snipe
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WalletAllocation {
+    pub wallet_index: usize,
+    pub amount_sol: f64,
+}
+
+// Outcome of a dust sweep; swap proceeds are the quoted amounts
+#[derive(Debug, Clone, Default)]
+pub struct DustSweepReport {
+    pub swept_tokens: Vec<String>,
+    pub skipped_no_route: Vec<String>,
+    pub closed_accounts: usize,
+    pub reclaimed_sol: f64,
+}
+
+// Outcome of consolidating SOL into one wallet
+#[derive(Debug, Clone, Default)]
+pub struct ConsolidationReport {
+    // Source wallet, SOL moved and the transfer's signature
+    pub transfers: Vec<(usize, f64, String)>,
+    pub tokens_sold: usize,
+    pub token_sell_failures: usize,
+    pub total_sol: f64,
 }
 
 // Enhanced token information
@@ -92,81 +679,355 @@ pub struct TokenInfo {
 }
 
 // Enhanced sniper state
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct SniperState {
     pub is_running: bool,
     pub total_trades: u64,
     pub successful_trades: u64,
     pub failed_trades: u64,
+    // Realized PnL of real sells, net of their transaction fees
     pub total_profit: f64,
     pub last_snipe_time: u64,
     pub active_monitors: u32,
+    #[serde(default)]
+    pub shadow_trades: u64,
+    #[serde(default)]
+    pub shadow_profit: f64,
+    #[serde(default)]
+    pub shadow_cost_basis: HashMap<String, f64>,
+    #[serde(default)]
+    pub recent_pnl: Vec<f64>,
+    #[serde(default)]
+    pub positions: HashMap<String, Position>,
+    // Realized PnL of real sells per strategy
+    #[serde(default)]
+    pub strategy_profit: HashMap<String, f64>,
+    // Set while auto-buys are held back by the capital floor
+    #[serde(default)]
+    pub buying_paused: bool,
+    // Slots each RPC trailed the furthest-ahead one by at the last lag check, keyed by host
+    #[serde(default)]
+    pub rpc_slot_lag: HashMap<String, u64>,
+    // Failover health of each rpc_url endpoint as of the last save
+    #[serde(default)]
+    pub rpc_health: Vec<EndpointHealth>,
+    // Open positions marked to their latest known price, as of the last get_status
+    #[serde(default)]
+    pub unrealized_pnl: f64,
+    // Real buys within the last 24 hours, for the daily spend limits
+    #[serde(default)]
+    pub recent_spend: Vec<SpendRecord>,
+    // When each token was last bought by any wallet, for the snipe cooldown
+    #[serde(default)]
+    pub last_snipe_by_token: HashMap<String, u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpendRecord {
+    pub timestamp: u64,
+    pub wallet_index: usize,
+    pub amount_sol: f64,
+}
+
+// Window the daily spend limits are measured over
+const SPEND_WINDOW_SECS: u64 = 24 * 60 * 60;
+
+// How many closed-trade results to keep for adaptive sizing
+const RECENT_PNL_HISTORY: usize = 100;
+// SOL transfers batched into one funding transaction, well within the transaction size limit
+const TRANSFERS_PER_TRANSACTION: usize = 20;
+// Balance lookups in flight at once when listing every wallet
+const WALLET_BALANCE_CONCURRENCY: usize = 16;
+// Long-poll duration of each Telegram getUpdates call
+const TELEGRAM_POLL_TIMEOUT_SECS: u64 = 30;
+
+impl SniperState {
+    // Count a buy toward the rolling 24h spend, refusing it if it would exceed either daily limit
+    pub fn reserve_spend(&mut self, now: u64, wallet_index: usize, amount_sol: f64, safety: &SafetyConfig) -> Result<()> {
+        self.recent_spend.retain(|spend| now.saturating_sub(spend.timestamp) < SPEND_WINDOW_SECS);
+
+        if let Some(limit) = safety.daily_spend_limit_sol {
+            let spent: f64 = self.recent_spend.iter().map(|spend| spend.amount_sol).sum();
+            ensure!(spent + amount_sol <= limit,
+                    "Buying {} SOL would exceed the daily spend limit: {:.4} of {} SOL spent in the last 24h", amount_sol, spent, limit);
+        }
+        if let Some(limit) = safety.per_wallet_spend_limit_sol {
+            let spent: f64 = self.recent_spend.iter().filter(|spend| spend.wallet_index == wallet_index).map(|spend| spend.amount_sol).sum();
+            ensure!(spent + amount_sol <= limit,
+                    "Buying {} SOL would exceed wallet {}'s daily spend limit: {:.4} of {} SOL spent in the last 24h", amount_sol, wallet_index, spent, limit);
+        }
+
+        self.recent_spend.push(SpendRecord { timestamp: now, wallet_index, amount_sol });
+        Ok(())
+    }
+
+    // Start a token's snipe cooldown, refusing the buy if any wallet already sniped it within cooldown_secs.
+    // Keyed by mint alone, so rotating to another wallet doesn't buy the same token again, and stamped in the
+    // same call as the check so concurrent snipes of one mint can't all get through.
+    pub fn reserve_snipe(&mut self, now: u64, token_address: &str, cooldown_secs: u64) -> Result<()> {
+        self.last_snipe_by_token.retain(|_, sniped_at| now.saturating_sub(*sniped_at) < cooldown_secs);
+        if let Some(sniped_at) = self.last_snipe_by_token.get(token_address) {
+            return Err(anyhow!("{} was already sniped {}s ago (snipe_cooldown_secs is {})",
+                               token_address, now.saturating_sub(*sniped_at), cooldown_secs));
+        }
+        self.last_snipe_by_token.insert(token_address.to_string(), now);
+        Ok(())
+    }
+
+    // Undo a cooldown reservation for a snipe that didn't go through
+    pub fn release_snipe(&mut self, reserved_at: u64, token_address: &str) {
+        if self.last_snipe_by_token.get(token_address) == Some(&reserved_at) {
+            self.last_snipe_by_token.remove(token_address);
+        }
+    }
+
+    // Undo a reservation for a buy that didn't go through
+    pub fn release_spend(&mut self, timestamp: u64, wallet_index: usize, amount_sol: f64) {
+        if let Some(index) = self.recent_spend.iter()
+            .rposition(|spend| spend.timestamp == timestamp && spend.wallet_index == wallet_index && spend.amount_sol == amount_sol)
+        {
+            self.recent_spend.remove(index);
+        }
+    }
+
+    // Remember a closed trade's realized PnL for adaptive sizing
+    pub fn record_closed_trade(&mut self, pnl: f64) {
+        self.recent_pnl.push(pnl);
+        if self.recent_pnl.len() > RECENT_PNL_HISTORY {
+            self.recent_pnl.remove(0);
+        }
+    }
 }
 
 pub struct SolanaSniperBot {
     config: SniperConfig,
-    client: RpcClient,
+    rpc: Arc<RpcFailover>,
     wallets: Vec<Keypair>,
     state: Arc<Mutex<SniperState>>,
     dex_monitor: DexMonitor,
     websocket_manager: DexWebSocketManager,
+    telegram_sender: TelegramSender,
+    dump_detector: Mutex<DumpDetector>,
+    in_flight_snipes: Mutex<HashSet<(usize, String)>>,
+    // (wallet, mint) pairs with a sell in flight, so two exits can't sell the same tokens twice
+    in_flight_sells: Mutex<HashSet<(usize, String)>>,
+    evaluation_throttle: Option<std::sync::Mutex<throttle::EvaluationThrottle>>,
+    snipe_permits: Option<Semaphore>,
+    snipe_latency: Arc<std::sync::Mutex<metrics::LatencyHistogram>>,
+    // Mints with open positions, which the price feed subscribes to
+    price_watchlist: watch::Sender<HashSet<String>>,
+    trade_log: Mutex<TradeLog>,
+    shutdown_tx: Arc<watch::Sender<bool>>,
+    transaction_builder: Option<Box<dyn TransactionBuilder>>,
+    safety_checks: Vec<Box<dyn SafetyCheck>>,
+    compute_unit_price: u64,
+    blacklist: Mutex<Blacklist>,
+    approve_list: Option<Mutex<ApproveList>>,
+    next_wallet: AtomicUsize,
+    quote_decimals: u8,
+}
+
+// Asks a running bot to stop monitoring, cancel its monitor tasks and flush its state
+#[derive(Clone)]
+pub struct ShutdownHandle(Arc<watch::Sender<bool>>);
+
+impl ShutdownHandle {
+    pub fn shutdown(&self) {
+        self.0.send_replace(true);
+    }
+}
+
+// The monitor tasks start_monitoring started, each counted in active_monitors until it exits
+struct MonitorTasks {
+    state: Arc<Mutex<SniperState>>,
+    max_monitors: Option<usize>,
+    started: Vec<tokio::task::AbortHandle>,
+}
+
+impl MonitorTasks {
+    fn new(state: Arc<Mutex<SniperState>>, max_monitors: Option<usize>) -> Self {
+        MonitorTasks { state, max_monitors, started: Vec::new() }
+    }
+
+    fn len(&self) -> usize {
+        self.started.len()
+    }
+
+    // How many more monitors monitoring.max_monitors allows
+    fn free_slots(&self) -> usize {
+        self.max_monitors.map_or(usize::MAX, |max_monitors| max_monitors.saturating_sub(self.started.len()))
+    }
+
+    // Whether monitoring.max_monitors leaves room to start another monitor, logging the source when it doesn't
+    fn slot_free(&self, source: &str) -> bool {
+        if self.free_slots() == 0 {
+            warn!("Not starting {} monitoring: max_monitors ({}) already running", source, self.started.len());
+            return false;
+        }
+        true
+    }
+
+    async fn track(&mut self, task: tokio::task::JoinHandle<()>) {
+        self.started.push(task.abort_handle());
+        self.state.lock().await.active_monitors += 1;
+        let state = self.state.clone();
+        tokio::spawn(async move {
+            // Finished, panicked or aborted, the monitor is no longer running
+            let _ = task.await;
+            let mut state = state.lock().await;
+            state.active_monitors = state.active_monitors.saturating_sub(1);
+        });
+    }
+
+    fn abort_all(&self) {
+        for task in &self.started {
+            task.abort();
+        }
+    }
+}
+
+// Outcome of one snipe attempt that got past quoting: a paper trade, or a swap that was sent
+enum SnipeAttempt {
+    Shadow(String),
+    Sent { result: Result<String>, token_amount: u64, price_impact: Option<f64> },
 }
 
 impl SolanaSniperBot {
     pub fn new(config: SniperConfig) -> Result<Self> {
-        let client = RpcClient::new_with_commitment(
-            config.rpc_url.clone(),
-            CommitmentConfig::confirmed(),
-        );
+        config.validate().map_err(|e| anyhow!("Invalid config: {}", e))?;
+        let run_id = trace::init_run_id(config.run_id.clone());
+        info!("Run ID: {}", run_id);
+
+        let rpc = Arc::new(RpcFailover::new(
+            config.rpc_url.urls(),
+            config.commitment_config()?,
+            config.rpc_failover_threshold,
+        ));
 
         let mut wallets = Vec::new();
-        for wallet_path in &config.wallets {
-            let keypair = read_keypair_file(wallet_path)
-                .map_err(|e| anyhow!("Failed to read wallet {}: {}", wallet_path, e))?;
-            wallets.push(keypair);
-        }
-
-        let state = Arc::new(Mutex::new(SniperState {
-            is_running: false,
-            total_trades: 0,
-            successful_trades: 0,
-            failed_trades: 0,
-            total_profit: 0.0,
-            last_snipe_time: 0,
-            active_monitors: 0,
-        }));
+        for wallet in &config.wallets {
+            let wallet_path = wallet.path();
+            wallets.push(wallet_file::load_keypair(wallet_path)?);
+        }
 
-        // Convert our DexConfig to dex_monitor::DexConfig
-        let dex_config = dex_monitor::DexConfig {
-            raydium_api_url: config.dex_config.raydium_api_url.clone(),
-            orca_api_url: config.dex_config.orca_api_url.clone(),
-            jupiter_api_url: config.dex_config.jupiter_api_url.clone(),
-            birdeye_api_url: config.dex_config.birdeye_api_url.clone(),
-            solscan_api_url: config.dex_config.solscan_api_url.clone(),
+        let compute_unit_price = priority_fee::compute_unit_price(
+            &config.trading.gas_priority,
+            config.trading.priority_fee_micro_lamports,
+        )?;
+
+        let blacklist = Mutex::new(Blacklist::load(&config.safety.blacklist_file)?);
+        let approve_list = config.safety.approve_list_file.as_deref()
+            .map(|path| ApproveList::load(path).map(Mutex::new))
+            .transpose()?;
+        let quote_decimals = if config.trading.quote_mint == SOL_MINT {
+            token_accounts::SOL_DECIMALS
+        } else {
+            rpc.call(|client| token_accounts::mint_decimals(client, &config.trading.quote_mint))
+                .map_err(|e| anyhow!("Failed to read decimals of quote mint {}: {}", config.trading.quote_mint, e))?
         };
 
+        let state = Arc::new(Mutex::new(Self::load_state(&config.persistence)?.unwrap_or_default()));
+
+        // Convert our DexConfig to dex_monitor::DexConfig
+        let dex_config = dex_monitor::DexConfig::from(&config.dex_config);
+
         let telegram_sender = TelegramSender::new(config.telegram.clone());
-        let dex_monitor = DexMonitor::new(dex_config, config.rpc_url.clone(), telegram_sender);
+        let mut dex_monitor = DexMonitor::new(dex_config, rpc.clone(), telegram_sender.clone());
+        dex_monitor.set_notified_pools(persistence::NotifiedPools::load(
+            config.persistence.notified_pools_file.clone(),
+            config.persistence.notified_pools_ttl_secs,
+        ));
+        dex_monitor.set_broadcast_to_all_rpcs(config.broadcast_to_all_rpcs);
+        let dump_detector = Mutex::new(DumpDetector::new(config.dump_detection.clone()));
+        let trade_log = Mutex::new(TradeLog::new(
+            config.persistence.trade_log_file.clone(),
+            config.persistence.trade_log_buffer_size,
+        ));
+        let evaluation_throttle = config.monitoring.max_evaluations_per_second
+            .map(|max_per_second| std::sync::Mutex::new(throttle::EvaluationThrottle::new(max_per_second)));
+        let snipe_permits = config.monitoring.max_concurrent_snipes.map(Semaphore::new);
         let mut websocket_manager = DexWebSocketManager::new();
+        let reconnect_backoff = RetryPolicy {
+            max_retries: 0,
+            base_delay_ms: config.monitoring.websocket_reconnect_delay_ms,
+            max_delay_ms: config.monitoring.websocket_max_reconnect_delay_ms,
+        };
 
         // Add WebSocket monitors based on configuration
-        if config.monitoring.enable_raydium {
+        if let (true, Some(raydium_websocket_url)) = (config.monitoring.enable_raydium, &config.monitoring.raydium_websocket_url) {
             websocket_manager.add_monitor(
-                config.dex_config.raydium_api_url.clone(),
-                config.monitoring.websocket_reconnect_delay_ms,
+                raydium_websocket_url.clone(),
+                SubscriptionKind::Raydium,
+                reconnect_backoff.clone(),
+                config.monitoring.websocket_rate_limit_delay_ms,
             );
         }
+        if config.monitoring.birdeye_listing_feed {
+            if config.dex_config.birdeye_api_key.is_empty() {
+                warn!("birdeye_listing_feed is set but there is no Birdeye API key; not streaming Birdeye listings");
+            } else {
+                websocket_manager.add_monitor(
+                    config.monitoring.birdeye_websocket_url.clone(),
+                    SubscriptionKind::Birdeye { api_key: config.dex_config.birdeye_api_key.clone() },
+                    reconnect_backoff.clone(),
+                    config.monitoring.websocket_rate_limit_delay_ms,
+                );
+            }
+        }
+        let price_watchlist = watch::channel(HashSet::new()).0;
+        if config.monitoring.birdeye_price_feed {
+            if config.dex_config.birdeye_api_key.is_empty() {
+                warn!("birdeye_price_feed is set but there is no Birdeye API key; exits will poll prices instead");
+            } else {
+                websocket_manager.set_price_monitor(BirdeyePriceMonitor::new(
+                    config.monitoring.birdeye_websocket_url.clone(),
+                    config.dex_config.birdeye_api_key.clone(),
+                    price_watchlist.subscribe(),
+                    reconnect_backoff,
+                    config.monitoring.websocket_rate_limit_delay_ms,
+                ));
+            }
+        }
 
         Ok(SolanaSniperBot {
             config,
-            client,
+            rpc,
             wallets,
             state,
             dex_monitor,
             websocket_manager,
+            telegram_sender,
+            dump_detector,
+            in_flight_snipes: Mutex::new(HashSet::new()),
+            in_flight_sells: Mutex::new(HashSet::new()),
+            evaluation_throttle,
+            snipe_permits,
+            snipe_latency: Arc::new(std::sync::Mutex::new(metrics::LatencyHistogram::default())),
+            price_watchlist,
+            trade_log,
+            shutdown_tx: Arc::new(watch::channel(false).0),
+            transaction_builder: None,
+            safety_checks: safety_checks::default_checks(),
+            compute_unit_price,
+            blacklist,
+            approve_list,
+            next_wallet: AtomicUsize::new(0),
+            quote_decimals,
         })
     }
 
+    // Build snipe transactions with a custom builder instead of Jupiter swaps
+    pub fn with_transaction_builder(mut self, builder: Box<dyn TransactionBuilder>) -> Self {
+        self.transaction_builder = Some(builder);
+        self
+    }
+
+    // Run a custom safety check in analyze_token after the built-in ones
+    pub fn with_safety_check(mut self, check: Box<dyn SafetyCheck>) -> Self {
+        self.safety_checks.push(check);
+        self
+    }
+
     // Create new wallet
     pub fn create_wallet(&self, wallet_name: &str) -> Result<()> {
         let keypair = Keypair::new();
@@ -178,259 +1039,2914 @@ impl SolanaSniperBot {
         Ok(())
     }
 
-    // Check wallet balance
-    pub fn check_balance(&self, wallet_index: usize) -> Result<f64> {
+    // Derive a wallet from a BIP39 seed phrase at m/44'/501'/<account>'/0' and save it like create_wallet does
+    pub fn import_wallet_from_mnemonic(wallet_name: &str, mnemonic: &str, passphrase: &str, account_index: u32) -> Result<Pubkey> {
+        let keypair = wallet_file::keypair_from_mnemonic(mnemonic, passphrase, account_index)?;
+
+        let wallet_data = serde_json::to_string_pretty(&keypair.to_bytes().to_vec())?;
+        std::fs::write(format!("wallets/{}.json", wallet_name), wallet_data)?;
+        info!("Imported wallet: {} at {}", wallet_name, keypair.pubkey());
+
+        Ok(keypair.pubkey())
+    }
+
+    // Distinguish a bot with no wallets at all from a bad index
+    fn check_wallet_index(&self, wallet_index: usize) -> Result<()> {
+        if self.wallets.is_empty() {
+            return Err(anyhow!("No wallets are loaded; add wallet keypair files to wallets in the config"));
+        }
         if wallet_index >= self.wallets.len() {
-            return Err(anyhow!("Invalid wallet index"));
+            return Err(anyhow!("Wallet index {} is out of range: {} wallets are loaded (indices 0-{})",
+                               wallet_index, self.wallets.len(), self.wallets.len() - 1));
         }
+        Ok(())
+    }
+
+    // Check wallet balance
+    pub fn check_balance(&self, wallet_index: usize) -> Result<f64> {
+        self.check_wallet_index(wallet_index)?;
 
         let pubkey = self.wallets[wallet_index].pubkey();
-        let balance = self.client.get_balance(&pubkey)?;
+        let balance = self.rpc.call(|client| Ok(client.get_balance(&pubkey)?))?;
         
         Ok(balance as f64 / LAMPORTS_PER_SOL as f64)
     }
 
+    // Every loaded wallet's pubkey and SOL balance in index order, fetching a few balances at a time
+    pub async fn wallet_balances(&self) -> Vec<(Pubkey, Result<f64>)> {
+        futures_util::stream::iter(self.wallets.iter().map(|keypair| keypair.pubkey()))
+            .map(|pubkey| {
+                let rpc = self.rpc.clone();
+                async move {
+                    let balance = tokio::task::spawn_blocking(move || rpc.call(|client| Ok(client.get_balance(&pubkey)?))).await
+                        .map_err(anyhow::Error::from)
+                        .and_then(|balance| balance)
+                        .map(|lamports| lamports as f64 / LAMPORTS_PER_SOL as f64);
+                    (pubkey, balance)
+                }
+            })
+            .buffered(WALLET_BALANCE_CONCURRENCY)
+            .collect()
+            .await
+    }
+
+    // Balance a wallet can spend on buys, in whole units of the quote mint
+    fn quote_balance(&self, wallet_index: usize) -> Result<f64> {
+        if self.config.trading.quote_mint == SOL_MINT {
+            return self.check_balance(wallet_index);
+        }
+        let raw_balance = self.get_raw_token_balance(wallet_index, &self.config.trading.quote_mint)?;
+        Ok(token_accounts::from_raw_amount(raw_balance, self.quote_decimals))
+    }
+
+    // Combined SOL balance of every non-shadow wallet
+    pub fn total_balance(&self) -> Result<f64> {
+        let mut total = 0.0;
+        for wallet_index in 0..self.wallets.len() {
+            if !self.is_shadow_wallet(wallet_index) {
+                total += self.check_balance(wallet_index)?;
+            }
+        }
+        Ok(total)
+    }
+
+    // Raw token amount a wallet holds of a mint, summed over all its token accounts
+    pub fn get_raw_token_balance(&self, wallet_index: usize, token_address: &str) -> Result<u64> {
+        self.check_wallet_index(wallet_index)?;
+
+        let mint = Pubkey::from_str(token_address)?;
+        let accounts = self.rpc.call(|client| token_accounts::list_token_accounts(client, &self.wallets[wallet_index].pubkey(), TokenAccountsFilter::Mint(mint)))?;
+        Ok(accounts.iter().map(|account| account.amount).sum())
+    }
+
+    // Whole-unit balance of a mint in the wallet's associated token account, 0 when that account doesn't exist
+    pub fn get_token_balance(&self, wallet_index: usize, token_address: &str) -> Result<f64> {
+        self.check_wallet_index(wallet_index)?;
+
+        let mint = Pubkey::from_str(token_address)?;
+        let token_program = self.rpc.call(|client| token_accounts::mint_program_id(client, &mint))?;
+        let address = token_accounts::associated_token_address(&self.wallets[wallet_index].pubkey(), &mint, &token_program)?;
+        if self.rpc.call(|client| Ok(client.get_account_with_commitment(&address, client.commitment())?))?.value.is_none() {
+            return Ok(0.0);
+        }
+        let balance = self.rpc.call(|client| Ok(client.get_token_account_balance(&address)?))?;
+        Ok(token_accounts::from_raw_amount(balance.amount.parse()?, balance.decimals))
+    }
+
+    // Swap token balances worth less than threshold_sol back to SOL and close empty token accounts for their rent
+    pub async fn sweep_dust(&self, wallet_index: usize, threshold_sol: f64) -> Result<DustSweepReport> {
+        self.check_wallet_index(wallet_index)?;
+        self.check_maintenance_wallet(wallet_index)?;
+        if self.is_shadow_wallet(wallet_index) {
+            return Err(anyhow!("Wallet {} is a shadow wallet and never trades", wallet_index));
+        }
+
+        let keypair = &self.wallets[wallet_index];
+        let owner = keypair.pubkey();
+        let threshold_lamports = (threshold_sol * LAMPORTS_PER_SOL as f64) as u64;
+        let mut report = DustSweepReport::default();
+
+        // Tracked positions are managed by the exit logic, however small they get
+        let open_positions = self.state.lock().await.positions.clone();
+        for account in self.rpc.call(|client| token_accounts::list_all_token_accounts(client, &owner))? {
+            if account.mint == SOL_MINT || open_positions.contains_key(&Position::key(wallet_index, &account.mint)) {
+                continue;
+            }
+            if account.amount == 0 {
+                let close = token_accounts::close_account_instruction(&account, &owner);
+                let recent_blockhash = self.rpc.call(|client| Ok(client.get_latest_blockhash()?))?;
+                let transaction = Transaction::new_signed_with_payer(&[close], Some(&owner), &[keypair], recent_blockhash);
+                match self.rpc.call(|client| Ok(client.send_and_confirm_transaction(&transaction)?)) {
+                    Ok(_) => {
+                        report.closed_accounts += 1;
+                        report.reclaimed_sol += account.lamports as f64 / LAMPORTS_PER_SOL as f64;
+                    }
+                    Err(e) => warn!("Failed to close empty token account {}: {}", account.address, e),
+                }
+                continue;
+            }
+
+            let swap_info = match self.dex_monitor.get_jupiter_quote(&account.mint, SOL_MINT, account.amount, self.slippage_bps()).await {
+                Ok(swap_info) => swap_info,
+                Err(e) => {
+                    debug!("No route to sweep {} of {}: {}", account.amount, account.mint, e);
+                    report.skipped_no_route.push(account.mint);
+                    continue;
+                }
+            };
+            if swap_info.output_amount >= threshold_lamports || swap_info.output_amount == 0 {
+                continue;
+            }
+
+            match self.dex_monitor.execute_jupiter_swap(keypair, &swap_info, self.compute_unit_price, None).await {
+                Ok(signature) => {
+                    info!("Swept {} of {} to {} lamports: {}", account.amount, account.mint, swap_info.output_amount, signature);
+                    report.reclaimed_sol += swap_info.output_amount as f64 / LAMPORTS_PER_SOL as f64;
+                    report.swept_tokens.push(account.mint);
+                }
+                Err(e) => warn!("Failed to sweep {}: {}", account.mint, e),
+            }
+        }
+
+        info!("Dust sweep of wallet {}: {} tokens swapped, {} accounts closed, {:.6} SOL reclaimed",
+              wallet_index, report.swept_tokens.len(), report.closed_accounts, report.reclaimed_sol);
+        Ok(report)
+    }
+
+    pub fn dust_threshold_sol(&self) -> f64 {
+        self.config.trading.dust_threshold_sol
+    }
+
     // Feed wallet with SOL
     pub async fn feed_wallet(&self, from_index: usize, to_index: usize, amount: f64) -> Result<String> {
-        if from_index >= self.wallets.len() || to_index >= self.wallets.len() {
-            return Err(anyhow!("Invalid wallet index"));
-        }
+        self.check_wallet_index(from_index)?;
+        self.check_wallet_index(to_index)?;
+        self.check_maintenance_wallet(to_index)?;
 
         let from_keypair = &self.wallets[from_index];
         let to_pubkey = self.wallets[to_index].pubkey();
         let lamports = (amount * LAMPORTS_PER_SOL as f64) as u64;
 
-        let tx = system_instruction::transfer(
+        let mut instructions = priority_fee::compute_budget_instructions(
+            self.compute_unit_price,
+            priority_fee::TRANSFER_COMPUTE_UNIT_LIMIT,
+        );
+        instructions.push(system_instruction::transfer(
             &from_keypair.pubkey(),
             &to_pubkey,
             lamports,
-        );
+        ));
 
-        let recent_blockhash = self.client.get_latest_blockhash()?;
+        let recent_blockhash = self.rpc.call(|client| Ok(client.get_latest_blockhash()?))?;
         let transaction = Transaction::new_signed_with_payer(
-            &[tx],
+            &instructions,
             Some(&from_keypair.pubkey()),
             &[from_keypair],
             recent_blockhash,
         );
 
-        let signature = self.client.send_and_confirm_transaction(&transaction)?;
+        let signature = self.rpc.call(|client| Ok(client.send_and_confirm_transaction(&transaction)?))?;
         info!("Fed wallet {} with {} SOL: {}", to_index, amount, signature);
         
         Ok(signature.to_string())
     }
 
-    // Enhanced token analysis with real data
-    pub async fn analyze_token(&self, token_address: &str) -> Result<bool> {
-        info!("Analyzing token safety: {}", token_address);
-        
-        // Get token metadata from Birdeye
-        let metadata = self.dex_monitor.get_token_metadata(token_address).await?;
-        
-        // Check verification status
-        if !metadata.is_verified {
-            warn!("Token {} is not verified", token_address);
-            return Ok(false);
+    // Fund every maintenance wallet other than the source, batching the transfers into as few transactions as fit.
+    // Returns each recipient with the signature of the transaction that funded it.
+    pub async fn distribute_sol(&self, from_index: usize, amount: FundingAmount) -> Result<Vec<(usize, String)>> {
+        self.check_wallet_index(from_index)?;
+        let recipients: Vec<usize> = self.maintenance_wallet_indices().into_iter().filter(|wallet_index| *wallet_index != from_index).collect();
+        ensure!(!recipients.is_empty(), "No wallets to fund besides wallet {}", from_index);
+        let amount_per_wallet = match amount {
+            FundingAmount::PerWallet(amount) => amount,
+            FundingAmount::Total(total) => total / recipients.len() as f64,
+        };
+        let lamports = (amount_per_wallet * LAMPORTS_PER_SOL as f64) as u64;
+        ensure!(lamports > 0, "Amount per wallet must be greater than 0");
+
+        let from_keypair = &self.wallets[from_index];
+        let recent_blockhash = self.rpc.call(|client| Ok(client.get_latest_blockhash()?))?;
+        let batches: Vec<(&[usize], Transaction)> = recipients.chunks(TRANSFERS_PER_TRANSACTION).map(|batch| {
+            let mut instructions = priority_fee::compute_budget_instructions(
+                self.compute_unit_price,
+                priority_fee::TRANSFER_COMPUTE_UNIT_LIMIT * batch.len() as u32,
+            );
+            instructions.extend(batch.iter().map(|to_index| {
+                system_instruction::transfer(&from_keypair.pubkey(), &self.wallets[*to_index].pubkey(), lamports)
+            }));
+            let transaction = Transaction::new_signed_with_payer(&instructions, Some(&from_keypair.pubkey()), &[from_keypair], recent_blockhash);
+            (batch, transaction)
+        }).collect();
+
+        // The source has to cover every transfer and fee and stay rent-exempt, or a later batch would fail midway
+        let mut fees = 0;
+        for (_, transaction) in &batches {
+            fees += self.rpc.call(|client| Ok(client.get_fee_for_message(&transaction.message)?))?;
         }
-        
-        // Check market cap
-        if metadata.market_cap < self.config.safety.min_market_cap {
-            warn!("Token {} has low market cap: {}", token_address, metadata.market_cap);
-            return Ok(false);
+        let required = lamports * recipients.len() as u64 + fees + self.rpc.call(|client| Ok(client.get_minimum_balance_for_rent_exemption(0)?))?;
+        let balance = self.rpc.call(|client| Ok(client.get_balance(&from_keypair.pubkey())?))?;
+        ensure!(balance >= required,
+                "Wallet {} has {} SOL but funding {} wallets with {} SOL each needs {} SOL including fees and its rent-exempt reserve",
+                from_index, balance as f64 / LAMPORTS_PER_SOL as f64, recipients.len(), amount_per_wallet, required as f64 / LAMPORTS_PER_SOL as f64);
+
+        let mut funded = Vec::new();
+        for (batch, transaction) in batches {
+            let signature = self.rpc.call(|client| Ok(client.send_and_confirm_transaction(&transaction)?))?.to_string();
+            info!("Funded wallets {:?} with {} SOL each from wallet {}: {}", batch, amount_per_wallet, from_index, signature);
+            funded.extend(batch.iter().map(|to_index| (*to_index, signature.clone())));
         }
-        
-        // Check volume
-        if metadata.volume_24h < self.config.safety.min_volume_24h {
-            warn!("Token {} has low 24h volume: {}", token_address, metadata.volume_24h);
-            return Ok(false);
+        Ok(funded)
+    }
+
+    // Move every maintenance wallet's SOL above its rent-exempt reserve into one wallet, optionally selling
+    // each wallet's token balances first. A wallet that fails is logged and skipped so the rest still sweep.
+    pub async fn consolidate_sol(&self, to_index: usize, sell_tokens: bool) -> Result<ConsolidationReport> {
+        self.check_wallet_index(to_index)?;
+        let to_pubkey = self.wallets[to_index].pubkey();
+        let rent_exempt_reserve = self.rpc.call(|client| Ok(client.get_minimum_balance_for_rent_exemption(0)?))?;
+        let mut report = ConsolidationReport::default();
+
+        for from_index in self.maintenance_wallet_indices().into_iter().filter(|wallet_index| *wallet_index != to_index) {
+            let from_keypair = &self.wallets[from_index];
+            if sell_tokens {
+                for account in self.rpc.call(|client| token_accounts::list_all_token_accounts(client, &from_keypair.pubkey()))? {
+                    if account.amount == 0 || account.mint == SOL_MINT {
+                        continue;
+                    }
+                    match self.sell_token(from_index, &account.mint, account.amount as f64).await {
+                        Ok(_) => report.tokens_sold += 1,
+                        Err(e) => {
+                            warn!("Failed to sell {} of {} from wallet {}: {}", account.amount, account.mint, from_index, e);
+                            report.token_sell_failures += 1;
+                        }
+                    }
+                }
+            }
+
+            match self.sweep_sol(from_keypair, &to_pubkey, rent_exempt_reserve) {
+                Ok(Some((lamports, signature))) => {
+                    let sol = lamports as f64 / LAMPORTS_PER_SOL as f64;
+                    info!("Swept {} SOL from wallet {} to wallet {}: {}", sol, from_index, to_index, signature);
+                    report.total_sol += sol;
+                    report.transfers.push((from_index, sol, signature));
+                }
+                Ok(None) => debug!("Wallet {} has nothing above its rent-exempt reserve to sweep", from_index),
+                Err(e) => warn!("Failed to sweep wallet {}: {}", from_index, e),
+            }
+        }
+
+        info!("Consolidated {:.6} SOL from {} wallets into wallet {}", report.total_sol, report.transfers.len(), to_index);
+        Ok(report)
+    }
+
+    // Transfer everything above the reserve and the transfer's own fee, returning the lamports sent and the signature
+    fn sweep_sol(&self, from_keypair: &Keypair, to_pubkey: &Pubkey, reserve_lamports: u64) -> Result<Option<(u64, String)>> {
+        let balance = self.rpc.call(|client| Ok(client.get_balance(&from_keypair.pubkey())?))?;
+        let recent_blockhash = self.rpc.call(|client| Ok(client.get_latest_blockhash()?))?;
+        let build = |lamports: u64| {
+            let mut instructions = priority_fee::compute_budget_instructions(self.compute_unit_price, priority_fee::TRANSFER_COMPUTE_UNIT_LIMIT);
+            instructions.push(system_instruction::transfer(&from_keypair.pubkey(), to_pubkey, lamports));
+            Transaction::new_signed_with_payer(&instructions, Some(&from_keypair.pubkey()), &[from_keypair], recent_blockhash)
+        };
+
+        let fee = self.rpc.call(|client| Ok(client.get_fee_for_message(&build(0).message)?))?;
+        let lamports = balance.saturating_sub(reserve_lamports + fee);
+        if lamports == 0 {
+            return Ok(None);
+        }
+        let transaction = build(lamports);
+        let signature = self.rpc.call(|client| Ok(client.send_and_confirm_transaction(&transaction)?))?;
+        Ok(Some((lamports, signature.to_string())))
+    }
+
+    // The mint's age against min_token_age_secs and max_token_age_secs
+    async fn check_token_age(&self, token_address: &str) -> Result<CheckResult> {
+        let safety = &self.config.safety;
+        let Some(created_at) = self.dex_monitor.mint_created_at(token_address).await? else {
+            return Ok(CheckResult::Fail("no dated transactions to tell its age".to_string()));
+        };
+
+        let age_secs = (chrono::Utc::now().timestamp() as u64).saturating_sub(created_at);
+        if let Some(min_token_age_secs) = safety.min_token_age_secs.filter(|min_token_age_secs| age_secs < *min_token_age_secs) {
+            return Ok(CheckResult::Fail(format!("{}s old < min_token_age_secs ({}s)", age_secs, min_token_age_secs)));
+        }
+        if let Some(max_token_age_secs) = safety.max_token_age_secs.filter(|max_token_age_secs| age_secs > *max_token_age_secs) {
+            return Ok(CheckResult::Fail(format!("{}s old > max_token_age_secs ({}s)", age_secs, max_token_age_secs)));
         }
+        Ok(CheckResult::Pass(format!("{}s old", age_secs)))
+    }
+
+    // Whether a token passes the safety checks
+    pub async fn analyze_token(&self, token_address: &str) -> Result<bool> {
+        Ok(self.analyze_token_detailed(token_address).await?.passed())
+    }
+
+    // Run the safety checks in order, stopping at the first failure, and report what each measured
+    pub async fn analyze_token_detailed(&self, token_address: &str) -> Result<TokenAnalysis> {
+        info!("Analyzing token safety: {}", token_address);
+        let mut analysis = TokenAnalysis::new(token_address);
         
-        // Check holders
-        if metadata.holders < self.config.safety.min_holders {
-            warn!("Token {} has few holders: {}", token_address, metadata.holders);
-            return Ok(false);
+        // Check the mint against the blacklist before any API calls
+        if self.config.safety.blacklist_check {
+            let result = if self.is_blacklisted(token_address).await {
+                CheckResult::Fail("mint is blacklisted".to_string())
+            } else {
+                CheckResult::Pass("mint not blacklisted".to_string())
+            };
+            if !analysis.record("blacklist", result) {
+                return Ok(analysis);
+            }
+        }
+
+        if self.is_approved(token_address).await {
+            info!("Token {} is on the approve list; skipping the remaining safety checks", token_address);
+            analysis.record("approve_list", CheckResult::Pass("approved; remaining checks skipped".to_string()));
+            return Ok(analysis);
+        }
+
+        if (self.config.safety.min_token_age_secs.is_some() || self.config.safety.max_token_age_secs.is_some())
+            && !analysis.record("token_age", self.check_token_age(token_address).await?)
+        {
+            return Ok(analysis);
+        }
+
+        // Check the mint's authorities, which a rug can use to inflate supply or lock holders in
+        if self.config.safety.require_mint_authority_revoked || self.config.safety.require_freeze_authority_revoked {
+            let mint = Pubkey::from_str(token_address)?;
+            let authorities = self.rpc.call(|client| token_accounts::mint_authorities(client, &mint))?;
+            let authority_result = |authority: Option<Pubkey>| match authority {
+                Some(authority) => CheckResult::Fail(format!("still held by {}", authority)),
+                None => CheckResult::Pass("revoked".to_string()),
+            };
+            if self.config.safety.require_mint_authority_revoked && !analysis.record("mint_authority", authority_result(authorities.mint_authority)) {
+                return Ok(analysis);
+            }
+            if self.config.safety.require_freeze_authority_revoked && !analysis.record("freeze_authority", authority_result(authorities.freeze_authority)) {
+                return Ok(analysis);
+            }
         }
         
-        // Check liquidity
-        if let Ok(has_liquidity) = self.dex_monitor.check_token_liquidity(token_address).await {
-            if !has_liquidity {
-                warn!("Token {} has insufficient liquidity", token_address);
-                return Ok(false);
+        // Get token metadata from Birdeye
+        let metadata = self.dex_monitor.get_token_metadata(token_address).await?;
+        
+        // Check the creator against the blacklist
+        if self.config.safety.blacklist_check && !metadata.creator.is_empty() {
+            let result = if self.is_blacklisted(&metadata.creator).await {
+                CheckResult::Fail(format!("creator {} is blacklisted", metadata.creator))
+            } else {
+                CheckResult::Pass(format!("creator {} not blacklisted", metadata.creator))
+            };
+            if !analysis.record("creator_blacklist", result) {
+                return Ok(analysis);
             }
         }
         
-        // Check honeypot (using public method)
-        if self.config.safety.honeypot_check {
-            if let Ok(is_honeypot) = self.dex_monitor.analyze_token_safety(token_address).await {
-                if !is_honeypot {
-                    warn!("Token {} appears to be a honeypot", token_address);
-                    return Ok(false);
-                }
+        let ctx = CheckContext {
+            token_address,
+            safety: &self.config.safety,
+            rpc: &self.rpc,
+            dex_monitor: &self.dex_monitor,
+            slippage_bps: self.slippage_bps(),
+        };
+        for check in &self.safety_checks {
+            if !analysis.record(check.name(), check.evaluate(&metadata, &ctx).await?) {
+                return Ok(analysis);
             }
         }
         
         info!("Token {} passed all safety checks", token_address);
+        Ok(analysis)
+    }
+
+    pub async fn is_blacklisted(&self, address: &str) -> bool {
+        self.blacklist.lock().await.is_blacklisted(address)
+    }
+
+    pub async fn is_approved(&self, token_address: &str) -> bool {
+        match &self.approve_list {
+            Some(approve_list) => approve_list.lock().await.is_approved(token_address),
+            None => false,
+        }
+    }
+
+    // Blacklist a token mint or creator address, persisting it to the blacklist file
+    pub async fn add_to_blacklist(&self, address: &str) -> Result<bool> {
+        self.blacklist.lock().await.add(address)
+    }
+
+    // Pool-level checks that need the detected pool rather than just the mint
+    pub async fn analyze_pool(&self, pool: &PoolUpdate, token_address: &str) -> Result<bool> {
+        let approved = self.is_approved(token_address).await;
+        if !approved && pool.liquidity != UNKNOWN_LIQUIDITY && pool.liquidity < self.config.safety.min_liquidity_sol {
+            warn!("Pool {} opened with {} SOL of liquidity (need {})", pool.pool_address, pool.liquidity, self.config.safety.min_liquidity_sol);
+            return Ok(false);
+        }
+
+        if self.config.safety.verify_pool_account
+            && !self.dex_monitor.pool_account_is_live(&pool.pool_address, pool.program_id.as_deref())?
+        {
+            warn!("Pool account {} does not exist or is not owned by {}", pool.pool_address, pool.program_id.as_deref().unwrap_or("its DEX program"));
+            return Ok(false);
+        }
+
+        if approved {
+            info!("Token {} is on the approve list; skipping the remaining pool checks", token_address);
+            return Ok(true);
+        }
+
+        if let Some(top_traders) = self.config.safety.wash_trade_top_traders {
+            let trades_by_trader = self.dex_monitor.count_trades_by_trader(&pool.pool_address, token_address, self.config.safety.sell_scan_limit).await?;
+            let raw_volume = self.dex_monitor.get_token_metadata(token_address).await?.volume_24h;
+            let volume = dex_monitor::wash_discounted_volume(raw_volume, &trades_by_trader, top_traders);
+            if volume < self.config.safety.min_volume_24h {
+                warn!("Pool {} has {:.2} of 24h volume after discounting its top {} traders ({:.2} raw, need {})",
+                      pool.pool_address, volume, top_traders, raw_volume, self.config.safety.min_volume_24h);
+                return Ok(false);
+            }
+        }
+
+        if self.config.safety.require_lp_locked {
+            let details = pool.details.as_ref();
+            let (Some(lp_mint), Some(creator)) = (details.and_then(|details| details.lp_mint.as_deref()), details.and_then(|details| details.creator.as_deref())) else {
+                warn!("Pool {} has no known LP mint and creator, so its LP lock can't be verified", pool.pool_address);
+                return Ok(false);
+            };
+            let locked_percent = self.lp_locked_percent(lp_mint, creator)?;
+            if locked_percent < self.config.safety.min_lp_locked_percent {
+                warn!("Pool {} has {:.2}% of its LP burned or locked (need {}%)", pool.pool_address, locked_percent, self.config.safety.min_lp_locked_percent);
+                return Ok(false);
+            }
+            info!("Pool {} has {:.2}% of its LP burned or locked", pool.pool_address, locked_percent);
+        }
+
+        if self.config.safety.min_successful_sells > 0 {
+            let creator = pool.details.as_ref().and_then(|details| details.creator.as_deref());
+            let sells = self.dex_monitor.count_successful_sells(
+                &pool.pool_address,
+                token_address,
+                creator,
+                self.config.safety.sell_scan_limit,
+            ).await?;
+            if sells < self.config.safety.min_successful_sells {
+                warn!("Pool {} has only {} successful non-creator sells (need {})",
+                      pool.pool_address, sells, self.config.safety.min_successful_sells);
+                return Ok(false);
+            }
+        }
+
         Ok(true)
     }
 
-    // Real token sniping with Jupiter
+    // Share of a pool's LP supply not held by its creator. LP burned reduces the supply, so a fully burned
+    // supply counts as 100% locked.
+    fn lp_locked_percent(&self, lp_mint: &str, creator: &str) -> Result<f64> {
+        let lp_mint = Pubkey::from_str(lp_mint)?;
+        let supply: u64 = self.rpc.call(|client| Ok(client.get_token_supply(&lp_mint)?))?.amount.parse()?;
+        if supply == 0 {
+            return Ok(100.0);
+        }
+        let creator = Pubkey::from_str(creator)?;
+        let creator_balance: u64 = self.rpc.call(|client| token_accounts::list_token_accounts(client, &creator, TokenAccountsFilter::Mint(lp_mint)))?
+            .iter()
+            .map(|account| account.amount)
+            .sum();
+        Ok(100.0 - creator_balance.min(supply) as f64 / supply as f64 * 100.0)
+    }
+
+    // Real token sniping with Jupiter, at most one in flight per wallet and mint and max_concurrent_snipes overall
     pub async fn snipe_token(&self, wallet_index: usize, token_address: &str, amount_sol: f64) -> Result<String> {
-        if wallet_index >= self.wallets.len() {
-            return Err(anyhow!("Invalid wallet index"));
+        let _permit = match &self.snipe_permits {
+            Some(permits) => Some(match permits.try_acquire() {
+                Ok(permit) => permit,
+                Err(_) => {
+                    info!("{} snipes already in flight; snipe of {} from wallet {} is waiting for a slot",
+                          self.config.monitoring.max_concurrent_snipes.unwrap_or_default(), token_address, wallet_index);
+                    permits.acquire().await?
+                }
+            }),
+            None => None,
+        };
+
+        let in_flight_key = (wallet_index, token_address.to_string());
+        if !self.in_flight_snipes.lock().await.insert(in_flight_key.clone()) {
+            return Err(anyhow!("Snipe of {} already in flight for wallet {}", token_address, wallet_index));
         }
 
-        // Check balance
-        let balance = self.check_balance(wallet_index)?;
-        if balance < amount_sol {
-            return Err(anyhow!("Insufficient balance: {} SOL, need {} SOL", balance, amount_sol));
+        let trace_id = trace::new_trace_id();
+        info!("[trace={}] Sniping token {} from wallet {} with {} SOL", trace_id, token_address, wallet_index, amount_sol);
+        let started = std::time::Instant::now();
+        let result = self.execute_snipe(&trace_id, wallet_index, token_address, amount_sol).await;
+        match &result {
+            Ok(_) => self.snipe_latency.lock().unwrap().observe(started.elapsed()),
+            Err(e) => warn!(trace = trace_id.as_str(), token = token_address, wallet = wallet_index, error:% = e; "Snipe failed"),
+        }
+        self.in_flight_snipes.lock().await.remove(&in_flight_key);
+        result
+    }
+
+    // Buy one listing from every configured wallet concurrently, each with its own amount
+    pub async fn multi_wallet_snipe(&self, token_address: &str) -> Vec<(usize, Result<String>)> {
+        let snipes = self.config.trading.multi_wallet_snipe.iter().map(|allocation| async move {
+            let result = self.snipe_token(allocation.wallet_index, token_address, allocation.amount_sol).await;
+            (allocation.wallet_index, result)
+        });
+        let results = futures_util::future::join_all(snipes).await;
+
+        let succeeded = results.iter().filter(|(_, result)| result.is_ok()).count();
+        info!("Multi-wallet snipe of {}: {}/{} wallets succeeded", token_address, succeeded, results.len());
+        for (wallet_index, result) in &results {
+            if let Err(e) = result {
+                error!("Wallet {} failed to snipe token {}: {}", wallet_index, token_address, e);
+            }
+        }
+
+        results
+    }
+
+    async fn execute_snipe(&self, trace_id: &str, wallet_index: usize, token_address: &str, amount_sol: f64) -> Result<String> {
+        self.check_wallet_index(wallet_index)?;
+
+        // Check balance (shadow wallets never spend anything)
+        let shadow = self.is_shadow_wallet(wallet_index);
+        if !shadow {
+            let balance = self.quote_balance(wallet_index)?;
+            if balance < amount_sol {
+                return Err(SniperError::InsufficientBalance {
+                    balance,
+                    mint: self.config.trading.quote_mint.clone(),
+                    needed: amount_sol,
+                }.into());
+            }
+        }
+
+        // Hold the cooldown and the spend against the daily limits until the snipe settles, so concurrent snipes
+        // can't buy one mint twice or overshoot the limits
+        let cooldown_secs = self.config.trading.snipe_cooldown_secs;
+        let reserved_at = chrono::Utc::now().timestamp() as u64;
+        {
+            let mut state = self.state.lock().await;
+            if cooldown_secs > 0 {
+                state.reserve_snipe(reserved_at, token_address, cooldown_secs)?;
+            }
+            if !shadow {
+                if let Err(e) = state.reserve_spend(reserved_at, wallet_index, amount_sol, &self.config.safety) {
+                    state.release_snipe(reserved_at, token_address);
+                    return Err(e);
+                }
+            }
+        }
+        let result = self.analyze_and_snipe(trace_id, wallet_index, token_address, amount_sol).await;
+        if result.is_err() {
+            let mut state = self.state.lock().await;
+            state.release_snipe(reserved_at, token_address);
+            if !shadow {
+                state.release_spend(reserved_at, wallet_index, amount_sol);
+            }
         }
+        result
+    }
 
+    async fn analyze_and_snipe(&self, trace_id: &str, wallet_index: usize, token_address: &str, amount_sol: f64) -> Result<String> {
         // Enhanced token analysis
         if self.config.anti_rug_check {
-            let is_safe = self.analyze_token(token_address).await?;
-            if !is_safe {
-                return Err(anyhow!("Token failed safety check"));
+            let analysis = self.analyze_token_detailed(token_address).await?;
+            if let Some(failure) = analysis.failure() {
+                return Err(SniperError::SafetyCheckFailed(failure).into());
+            }
+        }
+
+        let policy = self.trade_retry_policy();
+        let mut attempt = 0;
+        loop {
+            let slippage_bps = self.escalated_slippage_bps(attempt, policy.max_retries);
+            if attempt > 0 {
+                info!("[trace={}] Snipe of {} attempt {}/{} at {} bps slippage",
+                      trace_id, token_address, attempt + 1, policy.max_retries + 1, slippage_bps);
+            }
+            let result = match &self.transaction_builder {
+                Some(builder) => self.attempt_built_snipe(builder.as_ref(), trace_id, wallet_index, token_address, amount_sol).await,
+                None => self.attempt_jupiter_snipe(trace_id, wallet_index, token_address, amount_sol, slippage_bps).await,
+            };
+
+            // Quote failures and failed sends alike are retried while transient, each time re-quoting and signing
+            // against a fresh blockhash; only the final attempt is counted and alerted. A Jupiter swap that ran out
+            // of slippage is retried too, as long as the next attempt quotes wider.
+            let error = match &result {
+                Err(e) | Ok(SnipeAttempt::Sent { result: Err(e), .. }) => Some(e),
+                _ => None,
+            };
+            if let Some(e) = error {
+                let wider_slippage = self.transaction_builder.is_none()
+                    && self.escalated_slippage_bps(attempt + 1, policy.max_retries) > slippage_bps;
+                let slippage_exceeded = wider_slippage && retry::is_slippage_error(e);
+                if attempt < policy.max_retries && (slippage_exceeded || retry::is_transient_trade_error(e)) {
+                    attempt += 1;
+                    let delay = policy.delay_for(attempt);
+                    if slippage_exceeded {
+                        warn!("[trace={}] Snipe of {} exceeded {} bps slippage ({}), retrying in {}ms at {} bps", trace_id, token_address,
+                              slippage_bps, e, delay.as_millis(), self.escalated_slippage_bps(attempt, policy.max_retries));
+                    } else {
+                        warn!("[trace={}] Snipe of {} failed transiently ({}), retrying in {}ms", trace_id, token_address, e, delay.as_millis());
+                    }
+                    tokio::time::sleep(delay).await;
+                    continue;
+                }
             }
+
+            return match result? {
+                SnipeAttempt::Shadow(signature) => Ok(signature),
+                SnipeAttempt::Sent { result, token_amount, price_impact } => {
+                    let result = self.finish_snipe(result, trace_id, wallet_index, token_address, amount_sol, token_amount).await;
+                    if attempt > 0 {
+                        info!("[trace={}] Snipe of {} {} after {} retries", trace_id, token_address,
+                              if result.is_ok() { "succeeded" } else { "failed" }, attempt);
+                    }
+                    self.notify_trade(TradeAlert {
+                        side: "buy",
+                        token_address,
+                        wallet_index,
+                        sol_amount: amount_sol,
+                        token_amount,
+                        price_impact,
+                    }, &result).await;
+                    result
+                }
+            };
+        }
+    }
+
+    // Backoff for failed snipes: trading.max_retries retries when retry_failed_trades is set, otherwise none
+    fn trade_retry_policy(&self) -> RetryPolicy {
+        let max_retries = if self.config.trading.retry_failed_trades { self.config.trading.max_retries } else { 0 };
+        RetryPolicy { max_retries, ..RetryPolicy::default() }
+    }
+
+    // Slippage for a snipe attempt (0-based): slippage_tolerance first, then raised linearly so the last retry
+    // quotes at trading.max_slippage. It never goes past max_slippage, so that bounds the worst fill.
+    fn escalated_slippage_bps(&self, attempt: u32, max_retries: u32) -> u16 {
+        let base = self.slippage_bps();
+        let max = ((self.config.trading.max_slippage * 10_000.0).round() as u16).max(base);
+        if max_retries == 0 {
+            return base;
         }
+        base + ((max - base) as u32 * attempt.min(max_retries) / max_retries) as u16
+    }
 
+    // One quote and swap through Jupiter
+    async fn attempt_jupiter_snipe(&self, trace_id: &str, wallet_index: usize, token_address: &str, amount_sol: f64, slippage_bps: u16) -> Result<SnipeAttempt> {
         let keypair = &self.wallets[wallet_index];
-        let sol_mint = "So11111111111111111111111111111111111111112";
-        let amount_lamports = (amount_sol * LAMPORTS_PER_SOL as f64) as u64;
+        let amount_raw = token_accounts::to_raw_amount(amount_sol, self.quote_decimals);
 
         // Get Jupiter quote
-        let swap_info = self.dex_monitor.get_jupiter_quote(sol_mint, token_address, amount_lamports).await?;
+        let swap_info = self.dex_monitor.get_jupiter_quote(&self.config.trading.quote_mint, token_address, amount_raw, slippage_bps).await?;
         
         // Check price impact
         if swap_info.price_impact > self.config.trading.max_price_impact {
             return Err(anyhow!("Price impact too high: {}%", swap_info.price_impact * 100.0));
         }
+        self.check_quote_output(&swap_info)?;
+        self.check_quote_consistency(&swap_info).await?;
 
-        // Execute swap
-        let signature = self.dex_monitor.execute_jupiter_swap(keypair, &swap_info).await?;
-        
-        // Update state
-        let mut state = self.state.lock().await;
-        state.total_trades += 1;
-        state.successful_trades += 1;
-        state.last_snipe_time = chrono::Utc::now().timestamp() as u64;
+        if self.is_shadow_wallet(wallet_index) {
+            let signature = self.record_shadow_buy(trace_id, wallet_index, token_address, amount_sol).await?;
+            self.open_position(wallet_index, token_address, amount_sol, swap_info.output_amount).await;
+            self.persist_state().await;
+            return Ok(SnipeAttempt::Shadow(signature));
+        }
+
+        // Execute swap; a failed or lost swap never opens a position
+        let result = self.dex_monitor.execute_jupiter_swap(keypair, &swap_info, self.compute_unit_price, self.snipe_confirmation_deadline()).await;
+        Ok(SnipeAttempt::Sent { result, token_amount: swap_info.output_amount, price_impact: Some(swap_info.price_impact) })
+    }
+
+    // One build with the user-supplied transaction builder, signing and sending its transaction ourselves
+    async fn attempt_built_snipe(&self, builder: &dyn TransactionBuilder, trace_id: &str, wallet_index: usize, token_address: &str, amount_sol: f64) -> Result<SnipeAttempt> {
+        let keypair = &self.wallets[wallet_index];
+        let request = SnipeRequest {
+            wallet_index,
+            payer: keypair.pubkey(),
+            token_address: token_address.to_string(),
+            amount_sol,
+            recent_blockhash: self.rpc.call(|client| Ok(client.get_latest_blockhash()?))?,
+        };
+        let built = builder.build(&request)?;
+
+        if self.is_shadow_wallet(wallet_index) {
+            let signature = self.record_shadow_buy(trace_id, wallet_index, token_address, amount_sol).await?;
+            self.open_position(wallet_index, token_address, amount_sol, built.expected_token_amount).await;
+            self.persist_state().await;
+            return Ok(SnipeAttempt::Shadow(signature));
+        }
+
+        let message = self.dex_monitor.prepare_swap_message(&keypair.pubkey(), token_address, built.message, None).await?;
+        let signed_tx = VersionedTransaction::try_new(message, &[keypair])?;
+        let result = self.dex_monitor.send_transaction(&signed_tx, self.snipe_confirmation_deadline()).await;
+        Ok(SnipeAttempt::Sent { result, token_amount: built.expected_token_amount, price_impact: None })
+    }
+
+    // Account for a submitted snipe: count it, and on success log it and open the position
+    async fn finish_snipe(&self, result: Result<String>, trace_id: &str, wallet_index: usize, token_address: &str, amount_sol: f64, token_amount: u64) -> Result<String> {
+        self.count_trade(result.is_ok()).await;
+        let signature = match result {
+            Ok(signature) => signature,
+            Err(e) => {
+                self.persist_state().await;
+                return Err(e);
+            }
+        };
+        self.state.lock().await.last_snipe_time = chrono::Utc::now().timestamp() as u64;
+
+        self.record_trade(trace_id, wallet_index, token_address, "buy", amount_sol, &signature).await;
+        self.open_position(wallet_index, token_address, amount_sol, token_amount).await;
+        self.persist_state().await;
         
-        info!("Successfully sniped token {} with {} SOL: {}", token_address, amount_sol, signature);
+        info!(trace = trace_id, token = token_address, wallet = wallet_index, amount_sol = amount_sol, signature = signature.as_str(); "Sniped token");
         
         Ok(signature)
     }
 
-    // Sell token
+    // Sell a fixed amount of a token (ExactIn)
     pub async fn sell_token(&self, wallet_index: usize, token_address: &str, amount: f64) -> Result<String> {
-        if wallet_index >= self.wallets.len() {
-            return Err(anyhow!("Invalid wallet index"));
+        self.check_wallet_index(wallet_index)?;
+        self.guarded_sell(wallet_index, token_address, amount, SwapMode::ExactIn).await
+    }
+
+    // Sell just enough of a token to receive `proceeds` of the quote mint
+    pub async fn sell_for_proceeds(&self, wallet_index: usize, token_address: &str, proceeds: f64) -> Result<String> {
+        self.check_wallet_index(wallet_index)?;
+        let proceeds_raw = token_accounts::to_raw_amount(proceeds, self.quote_decimals);
+        self.guarded_sell(wallet_index, token_address, proceeds_raw as f64, SwapMode::ExactOut).await
+    }
+
+    // Sell enough of a position to get its cost basis back, keeping the rest
+    pub async fn recover_initial(&self, wallet_index: usize, token_address: &str) -> Result<String> {
+        let position = self.state.lock().await.positions.get(&Position::key(wallet_index, token_address)).cloned()
+            .ok_or_else(|| anyhow!("Wallet {} has no open position in {}", wallet_index, token_address))?;
+        if position.entry_amount_sol <= 0.0 {
+            return Err(anyhow!("Position in {} from wallet {} has no cost basis to recover", token_address, wallet_index));
+        }
+        self.sell_for_proceeds(wallet_index, token_address, position.entry_amount_sol).await
+    }
+
+    // At most one sell in flight per wallet and mint
+    async fn guarded_sell(&self, wallet_index: usize, token_address: &str, amount: f64, swap_mode: SwapMode) -> Result<String> {
+        let in_flight_key = (wallet_index, token_address.to_string());
+        if !self.in_flight_sells.lock().await.insert(in_flight_key.clone()) {
+            return Err(anyhow!("Sell of {} already in flight for wallet {}", token_address, wallet_index));
         }
+        let result = self.execute_sell(wallet_index, token_address, amount, swap_mode).await;
+        self.in_flight_sells.lock().await.remove(&in_flight_key);
+        result
+    }
+
+    // Sell every open position, up to trading.sell_all_concurrency at a time, returning each position's result
+    pub async fn sell_all(&self) -> Vec<(Position, Result<String>)> {
+        let positions: Vec<Position> = self.state.lock().await.positions.values().cloned().collect();
+        info!("Selling {} open positions, {} at a time", positions.len(), self.config.trading.sell_all_concurrency);
+        futures_util::stream::iter(positions)
+            .map(|position| async move {
+                let result = self.sell_token(position.wallet_index, &position.token_address, position.token_amount as f64).await;
+                (position, result)
+            })
+            .buffer_unordered(self.config.trading.sell_all_concurrency)
+            .collect()
+            .await
+    }
+
+    // With ExactIn `amount` is the tokens to sell; with ExactOut it is the raw quote amount to receive
+    async fn execute_sell(&self, wallet_index: usize, token_address: &str, amount: f64, swap_mode: SwapMode) -> Result<String> {
 
         let keypair = &self.wallets[wallet_index];
-        let sol_mint = "So11111111111111111111111111111111111111112";
+        let trace_id = trace::new_trace_id();
+        info!("[trace={}] Selling token {} from wallet {} ({} {})", trace_id, token_address, wallet_index, swap_mode.as_str(), amount);
         
         // Get sell quote
-        let swap_info = self.dex_monitor.get_jupiter_quote(token_address, sol_mint, amount as u64).await?;
+        let swap_info = self.dex_monitor.get_jupiter_quote_with_mode(token_address, &self.config.trading.quote_mint, amount as u64, self.slippage_bps(), swap_mode).await?;
+        if swap_info.output_amount == 0 {
+            return Err(SniperError::TokenNotSellable(token_address.to_string()).into());
+        }
+        self.check_quote_output(&swap_info)?;
+        let amount = swap_info.input_amount as f64;
+
+        let proceeds_sol = token_accounts::from_raw_amount(swap_info.output_amount, self.quote_decimals);
+        if self.is_shadow_wallet(wallet_index) {
+            let signature = self.record_shadow_sell(&trace_id, wallet_index, token_address, amount, proceeds_sol).await?;
+            self.refresh_last_price(wallet_index, token_address).await;
+            self.close_position(wallet_index, token_address, amount as u64, proceeds_sol, 0.0, &signature).await;
+            self.persist_state().await;
+            return Ok(signature);
+        }
         
         // Execute sell
-        let signature = self.dex_monitor.execute_jupiter_swap(keypair, &swap_info).await?;
+        let result = self.dex_monitor.execute_jupiter_swap(keypair, &swap_info, self.compute_unit_price, None).await;
+        self.notify_trade(TradeAlert {
+            side: "sell",
+            token_address,
+            wallet_index,
+            sol_amount: proceeds_sol,
+            token_amount: amount as u64,
+            price_impact: Some(swap_info.price_impact),
+        }, &result).await;
+        self.count_trade(result.is_ok()).await;
+        let signature = match result {
+            Ok(signature) => signature,
+            Err(e) => {
+                self.persist_state().await;
+                return Err(e);
+            }
+        };
+        self.record_trade(&trace_id, wallet_index, token_address, "sell", amount, &signature).await;
+        self.refresh_last_price(wallet_index, token_address).await;
+        let fees_sol = match self.dex_monitor.transaction_fee(&signature).await {
+            Ok(fee) => token_accounts::from_raw_amount(fee, token_accounts::SOL_DECIMALS),
+            Err(e) => {
+                warn!("[trace={}] Could not get the fee of sell {}, leaving it out of PnL: {}", trace_id, signature, e);
+                0.0
+            }
+        };
+        if let Some(closed) = self.close_position(wallet_index, token_address, amount as u64, proceeds_sol, fees_sol, &signature).await {
+            let mut state = self.state.lock().await;
+            state.total_profit += closed.realized_pnl;
+            state.record_closed_trade(closed.realized_pnl);
+            if let Some(strategy) = &closed.strategy {
+                *state.strategy_profit.entry(strategy.clone()).or_default() += closed.realized_pnl;
+            }
+            drop(state);
+            self.record_closed_position(&closed);
+        }
+        self.persist_state().await;
         
-        info!("Successfully sold token {}: {}", token_address, signature);
+        info!(trace = trace_id.as_str(), token = token_address, wallet = wallet_index, amount = amount, signature = signature.as_str(); "Sold token");
         Ok(signature)
     }
 
-    // Start real-time monitoring
-    pub async fn start_monitoring(&self) -> Result<()> {
-        info!("Starting real-time DEX monitoring...");
-        
+    // Count a real swap once it has settled. Sends only succeed once the transaction is confirmed without an
+    // on-chain error, so reverted swaps land in failed_trades rather than successful_trades.
+    async fn count_trade(&self, succeeded: bool) {
         let mut state = self.state.lock().await;
-        state.is_running = true;
-        state.active_monitors = 1; // Placeholder
-        drop(state);
+        state.total_trades += 1;
+        if succeeded {
+            state.successful_trades += 1;
+        } else {
+            state.failed_trades += 1;
+        }
+    }
 
-        // Start on-chain monitoring for Raydium
-        if self.config.monitoring.enable_raydium {
-            let dex_config = dex_monitor::DexConfig {
-                raydium_api_url: self.config.dex_config.raydium_api_url.clone(),
-                orca_api_url: self.config.dex_config.orca_api_url.clone(),
-                jupiter_api_url: self.config.dex_config.jupiter_api_url.clone(),
-                birdeye_api_url: self.config.dex_config.birdeye_api_url.clone(),
-                solscan_api_url: self.config.dex_config.solscan_api_url.clone(),
-            };
-            let rpc_url = self.config.rpc_url.clone();
-            let message_tx = self.websocket_manager.get_message_sender();
-            
-            let telegram_config = TelegramConfig {
-                bot_token: self.config.telegram.bot_token.clone(),
-                chat_id: self.config.telegram.chat_id.clone() 
-            };
-            let telegram_sender = TelegramSender::new(telegram_config);
-            tokio::spawn(async move {
-                let dex_monitor = crate::dex_monitor::DexMonitor::new(dex_config, rpc_url, telegram_sender);
-                if let Err(e) = dex_monitor.monitor_raydium_onchain(message_tx).await {
-                    error!("Raydium on-chain monitoring failed: {}", e);
+    // Tell the operator about a real swap, and about a failed one if they asked for that
+    async fn notify_trade(&self, trade: TradeAlert<'_>, result: &Result<String>) {
+        let sent = match result {
+            Ok(signature) => self.telegram_sender.send_trade_telegram_message(&trade, signature).await,
+            Err(e) => self.telegram_sender.send_failed_trade_telegram_message(&trade, &e.to_string()).await,
+        };
+        if let Err(e) = sent {
+            error!("{}", e);
+        }
+    }
+
+    // Track a filled buy so the exit monitor can sell it from the same wallet
+    async fn open_position(&self, wallet_index: usize, token_address: &str, amount_sol: f64, token_amount: u64) {
+        // An unknown entry price is filled in by the first exit-monitor poll instead
+        let entry_price = match self.dex_monitor.get_token_metadata(token_address).await {
+            Ok(metadata) => metadata.price,
+            Err(e) => {
+                warn!("Could not get entry price for {}: {}", token_address, e);
+                0.0
+            }
+        };
+
+        let mut state = self.state.lock().await;
+        state.positions.entry(Position::key(wallet_index, token_address))
+            .or_insert_with(|| Position {
+                wallet_index,
+                token_address: token_address.to_string(),
+                entry_amount_sol: 0.0,
+                token_amount: 0,
+                entry_price: 0.0,
+                opened_at: chrono::Utc::now().timestamp() as u64,
+                pool_address: None,
+                pool_creator: None,
+                last_pool_signature: None,
+                strategy: self.strategy_for_wallet(wallet_index).map(|strategy| strategy.name.clone()),
+                last_price: 0.0,
+            })
+            .add(amount_sol, token_amount, entry_price);
+        self.sync_price_watchlist(&state);
+    }
+
+    // Point the price feed at the mints currently held; unchanged watchlists don't wake it
+    fn sync_price_watchlist(&self, state: &SniperState) {
+        let mints: HashSet<String> = state.positions.values().map(|position| position.token_address.clone()).collect();
+        self.price_watchlist.send_if_modified(|watchlist| {
+            let changed = *watchlist != mints;
+            *watchlist = mints;
+            changed
+        });
+    }
+
+    // Reduce (or close) a position after a sell, returning the sold lot with its realized PnL in SOL net of fees
+    async fn close_position(&self, wallet_index: usize, token_address: &str, token_amount: u64, proceeds_sol: f64, fees_sol: f64, signature: &str) -> Option<ClosedPosition> {
+        let key = Position::key(wallet_index, token_address);
+        let mut state = self.state.lock().await;
+        let position = state.positions.get_mut(&key)?;
+        let closed = position.close(token_amount, proceeds_sol, fees_sol, chrono::Utc::now().timestamp() as u64, signature);
+        if position.token_amount == 0 {
+            state.positions.remove(&key);
+            self.sync_price_watchlist(&state);
+        }
+        Some(closed)
+    }
+
+    // Record the market price at a sell as the position's exit price; a failed lookup keeps the last polled price
+    async fn refresh_last_price(&self, wallet_index: usize, token_address: &str) {
+        match self.dex_monitor.get_token_metadata(token_address).await {
+            Ok(metadata) => {
+                if let Some(position) = self.state.lock().await.positions.get_mut(&Position::key(wallet_index, token_address)) {
+                    position.last_price = metadata.price;
                 }
-            });
+            }
+            Err(e) => warn!("Could not get exit price for {}: {}", token_address, e),
+        }
+    }
+
+    fn record_closed_position(&self, closed: &ClosedPosition) {
+        if let Some(closed_positions_file) = &self.config.persistence.closed_positions_file {
+            if let Err(e) = persistence::append_json_line(closed_positions_file, closed) {
+                error!("Failed to record closed position in {}: {}", closed_positions_file, e);
+            }
+        }
+    }
+
+    // Compare every RPC's slot with the furthest-ahead one, alerting when an endpoint starts or stops lagging
+    async fn monitor_rpc_lag(&self) {
+        let Some(max_slot_lag) = self.config.max_rpc_slot_lag else {
+            return std::future::pending().await;
+        };
+        let clients = self.rpc.clients();
+        if clients.len() < 2 {
+            info!("Only one RPC configured; slot lag monitoring is disabled");
+            return std::future::pending().await;
         }
 
-        // Process messages from all monitors
+        let interval = std::time::Duration::from_millis(self.config.rpc_lag_check_interval_ms);
+        let mut lagging = HashSet::new();
         loop {
-            let message = self.websocket_manager.receive_message().await;
-            
-            if let Some(message) = message {
-                match message {
-                    WebSocketMessage::TokenListing(listing) => {
-                        info!("New token listing: {} ({})", listing.symbol, listing.token_address);
-                        
-                        // Auto snipe if enabled
-                        if self.config.auto_sell {
-                            for (i, _) in self.wallets.iter().enumerate() {
-                                if let Err(e) = self.snipe_token(i, &listing.token_address, self.config.max_sol_per_trade).await {
-                                    error!("Failed to snipe token {}: {}", listing.token_address, e);
-                                }
-                            }
-                        }
-                    }
-                    WebSocketMessage::PoolUpdate(pool) => {
-                        info!("Pool update: {} - {} <-> {}", pool.pool_address, pool.token_a, pool.token_b);
-                    }
-                    WebSocketMessage::PriceUpdate(price) => {
-                        info!("Price update: {} = ${}", price.token_address, price.price);
+            // RpcClient blocks, so every endpoint is asked at once off the async workers
+            let slot_requests = clients.iter().map(|client| {
+                let client = client.clone();
+                tokio::task::spawn_blocking(move || (client.url(), client.get_slot()))
+            });
+            let slots: Vec<(String, u64)> = futures_util::future::join_all(slot_requests).await.into_iter()
+                .filter_map(|result| match result {
+                    Ok((rpc_url, Ok(slot))) => Some((rpc_url, slot)),
+                    Ok((rpc_url, Err(e))) => {
+                        warn!("Failed to get slot from {}: {}", rpc_lag::endpoint_label(&rpc_url), e);
+                        None
                     }
-                    WebSocketMessage::Error(error_msg) => {
-                        error!("WebSocket error: {}", error_msg);
+                    Err(e) => {
+                        warn!("Slot request task failed: {}", e);
+                        None
                     }
+                })
+                .collect();
+            let lags = rpc_lag::slot_lags(&slots);
+
+            for (rpc_url, lag) in &lags {
+                let is_lagging = *lag > max_slot_lag;
+                if is_lagging == lagging.contains(rpc_url) {
+                    continue;
+                }
+                let label = rpc_lag::endpoint_label(rpc_url);
+                if is_lagging {
+                    warn!("RPC {} is {} slots behind (limit {}), no longer broadcasting to it", label, lag, max_slot_lag);
+                    lagging.insert(rpc_url.clone());
+                } else {
+                    info!("RPC {} caught up ({} slots behind)", label, lag);
+                    lagging.remove(rpc_url);
+                }
+                if let Err(e) = self.telegram_sender.send_rpc_lag_telegram_message(&label, *lag, max_slot_lag, is_lagging).await {
+                    error!("{}", e);
                 }
-            } else {
-                // Channel closed, break the loop
-                break;
             }
-        }
+            self.dex_monitor.set_lagging_rpc_urls(lagging.clone());
 
-        Ok(())
+            self.state.lock().await.rpc_slot_lag = lags.iter()
+                .map(|(rpc_url, lag)| (rpc_lag::endpoint_label(rpc_url), *lag))
+                .collect();
+            self.persist_state().await;
+            tokio::time::sleep(interval).await;
+        }
     }
 
-    // Get current status
-    pub async fn get_status(&self) -> SniperState {
-        self.state.lock().await.clone()
+    // Act on Buy/Ignore presses of new-pool alert buttons until shut down
+    async fn handle_telegram_buttons(&self) {
+        if !self.config.telegram.interactive_buttons || !self.telegram_sender.is_enabled() {
+            return std::future::pending().await;
+        }
+
+        let mut offset = 0;
+        loop {
+            let decisions = match self.telegram_sender.get_pool_decisions(&mut offset, TELEGRAM_POLL_TIMEOUT_SECS).await {
+                Ok(decisions) => decisions,
+                Err(e) => {
+                    warn!("Failed to poll Telegram for button presses: {}", e);
+                    tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+                    continue;
+                }
+            };
+            for (callback_query_id, decision) in decisions {
+                let reply = match decision {
+                    Some(PoolDecision::Buy { token_address, amount_sol }) => self.buy_from_button(&token_address, amount_sol).await,
+                    Some(PoolDecision::Ignore { token_address }) => {
+                        info!("Operator ignored {} from Telegram", token_address);
+                        "Ignored".to_string()
+                    }
+                    None => "Unknown button".to_string(),
+                };
+                if let Err(e) = self.telegram_sender.answer_callback_query(&callback_query_id, &reply).await {
+                    warn!("Failed to answer Telegram button press: {}", e);
+                }
+            }
+        }
     }
 
-    // Start the bot
-    pub async fn start(&self) -> Result<()> {
-        info!("Starting Solana Sniper Bot...");
-        
-        // Start monitoring
-        self.start_monitoring().await?;
-        
-        Ok(())
+    // Snipe a token an operator approved from Telegram, returning the reply shown to them
+    async fn buy_from_button(&self, token_address: &str, amount_sol: f64) -> String {
+        if amount_sol <= 0.0 || amount_sol > self.config.max_sol_per_trade {
+            return format!("Amount {} SOL is outside (0, {}]", amount_sol, self.config.max_sol_per_trade);
+        }
+        let Some(wallet_index) = self.select_wallet().await else {
+            return "No wallet available to buy from".to_string();
+        };
+        info!("Operator approved buying {} with {} SOL from Telegram", token_address, amount_sol);
+        match self.snipe_token(wallet_index, token_address, amount_sol).await {
+            Ok(signature) => format!("Bought: {}", signature),
+            Err(e) => format!("Buy failed: {}", e),
+        }
     }
 
-    // Stop the bot
-    pub async fn stop(&self) -> Result<()> {
-        let mut state = self.state.lock().await;
-        state.is_running = false;
-        state.active_monitors = 0;
+    // Poll prices of open positions and sell any that crossed their strategy's take-profit or stop-loss
+    async fn monitor_positions(&self) {
+        let any_threshold = self.config.trading.take_profit_percent.is_some()
+            || self.config.trading.stop_loss_percent.is_some()
+            || self.config.strategies.iter().any(|strategy| strategy.take_profit_percent.is_some() || strategy.stop_loss_percent.is_some());
+        let any_exit_enabled = any_threshold
+            || self.config.dump_detection.enabled
+            || self.config.trading.exit_on_creator_liquidity_removal;
+        // Dump alerts go out even without auto_sell; every other check here only exists to sell
+        if !self.config.dump_detection.enabled && (!self.config.auto_sell || !any_exit_enabled) {
+            return std::future::pending().await;
+        }
+
+        let interval = std::time::Duration::from_millis(self.config.trading.position_check_interval_ms);
+        loop {
+            tokio::time::sleep(interval).await;
+            let positions: Vec<Position> = self.state.lock().await.positions.values().cloned().collect();
+            for position in positions {
+                let (take_profit_percent, stop_loss_percent) = self.exit_thresholds(&position);
+                if let Err(e) = self.check_position(&position, take_profit_percent, stop_loss_percent).await {
+                    error!("Failed to check position in {} for wallet {}: {}", position.token_address, position.wallet_index, e);
+                }
+            }
+        }
+    }
+
+    // Take-profit and stop-loss percentages for a position, from its wallet's strategy or the trading defaults
+    fn exit_thresholds(&self, position: &Position) -> (Option<f64>, Option<f64>) {
+        let strategy = self.strategy_for_wallet(position.wallet_index);
+        let take_profit_percent = strategy.and_then(|strategy| strategy.take_profit_percent).or(self.config.trading.take_profit_percent);
+        let stop_loss_percent = strategy.and_then(|strategy| strategy.stop_loss_percent).or(self.config.trading.stop_loss_percent);
+        (take_profit_percent, stop_loss_percent)
+    }
+
+    async fn check_position(&self, position: &Position, take_profit_percent: Option<f64>, stop_loss_percent: Option<f64>) -> Result<()> {
+        if self.config.auto_sell && self.config.trading.exit_on_creator_liquidity_removal {
+            if let Some(signature) = self.check_creator_liquidity_removal(position).await? {
+                return self.exit_position(position, ExitReason::CreatorLiquidityRemoval { signature }).await;
+            }
+        }
+
+        let metadata = self.dex_monitor.get_token_metadata(&position.token_address).await?;
+        self.record_price(position, metadata.price).await;
+        if self.config.auto_sell
+            && position.entry_price > 0.0
+            && self.exit_on_price(position, metadata.price, take_profit_percent, stop_loss_percent).await? {
+            return Ok(());
+        }
+
+        self.check_for_dump(position.wallet_index, &position.token_address, position.token_amount as f64).await?;
+        Ok(())
+    }
+
+    // Record a fresh market price on an open position, taking it as the entry price if none was known yet
+    async fn record_price(&self, position: &Position, price: f64) {
+        if let Some(open) = self.state.lock().await.positions.get_mut(&Position::key(position.wallet_index, &position.token_address)) {
+            open.last_price = price;
+            if open.entry_price <= 0.0 {
+                open.entry_price = price;
+            }
+        }
+    }
+
+    // Sell the position if the price crossed its take-profit or stop-loss, returning whether it was sold
+    async fn exit_on_price(&self, position: &Position, price: f64, take_profit_percent: Option<f64>, stop_loss_percent: Option<f64>) -> Result<bool> {
+        let Some(reason) = positions::exit_signal(position, price, take_profit_percent, stop_loss_percent) else {
+            return Ok(false);
+        };
+        if let (ExitReason::TakeProfit { .. }, Some(take_profit_percent)) = (&reason, take_profit_percent) {
+            if self.config.trading.requote_before_take_profit && !self.confirm_take_profit(position, take_profit_percent).await? {
+                return Ok(false);
+            }
+        }
+        self.exit_position(position, reason).await?;
+        Ok(true)
+    }
+
+    // Apply a streamed price to every open position in the token, exiting any that crossed a threshold
+    async fn apply_price_update(&self, update: &PriceUpdate) {
+        let positions: Vec<Position> = self.state.lock().await.positions.values()
+            .filter(|position| position.token_address == update.token_address)
+            .cloned()
+            .collect();
+        for position in positions {
+            self.record_price(&position, update.price).await;
+            if !self.config.auto_sell || position.entry_price <= 0.0 {
+                continue;
+            }
+            let (take_profit_percent, stop_loss_percent) = self.exit_thresholds(&position);
+            if let Err(e) = self.exit_on_price(&position, update.price, take_profit_percent, stop_loss_percent).await {
+                error!("Failed to exit position in {} for wallet {}: {}", position.token_address, position.wallet_index, e);
+            }
+        }
+    }
+
+    // Quote the actual sell so a take-profit fired by a stale or spiking price feed doesn't sell at a loss
+    async fn confirm_take_profit(&self, position: &Position, take_profit_percent: f64) -> Result<bool> {
+        let swap_info = self.dex_monitor.get_jupiter_quote(&position.token_address, &self.config.trading.quote_mint, position.token_amount, self.slippage_bps()).await?;
+        let proceeds = token_accounts::from_raw_amount(swap_info.output_amount, self.quote_decimals);
+        let Some(return_percent) = position.return_percent(proceeds) else {
+            return Ok(true);
+        };
+        if return_percent < take_profit_percent {
+            info!("Deferring take-profit on {} from wallet {}: sell quotes {:.6} ({:+.2}%), below the {}% target",
+                  position.token_address, position.wallet_index, proceeds, return_percent, take_profit_percent);
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    async fn exit_position(&self, position: &Position, reason: ExitReason) -> Result<()> {
+        info!("Exiting {} from wallet {}: {}", position.token_address, position.wallet_index, reason);
+        let signature = self.sell_token(position.wallet_index, &position.token_address, position.token_amount as f64).await?;
+        if let Err(e) = self.telegram_sender.send_position_exit_telegram_message(&position.token_address, &reason.to_string(), &signature).await {
+            error!("{}", e);
+        }
+        Ok(())
+    }
+
+    // Look through the position's pool activity since the last check for the creator pulling liquidity
+    async fn check_creator_liquidity_removal(&self, position: &Position) -> Result<Option<String>> {
+        let (Some(pool_address), Some(creator)) = (&position.pool_address, &position.pool_creator) else {
+            return Ok(None);
+        };
+        let (removal, newest_signature) = self.dex_monitor.find_creator_liquidity_removal(
+            pool_address,
+            creator,
+            position.last_pool_signature.as_deref(),
+            self.config.safety.sell_scan_limit,
+        ).await?;
+
+        if let Some(newest_signature) = newest_signature {
+            let mut state = self.state.lock().await;
+            if let Some(open) = state.positions.get_mut(&Position::key(position.wallet_index, &position.token_address)) {
+                open.last_pool_signature = Some(newest_signature);
+            }
+        }
+        if let Some(signature) = &removal {
+            warn!("Creator {} removed liquidity from pool {} of held token {}: {}", creator, pool_address, position.token_address, signature);
+        }
+        Ok(removal)
+    }
+
+    // Remember which pool (and creator) the positions in a freshly sniped token came from
+    async fn set_position_pool(&self, token_address: &str, pool: &PoolUpdate) {
+        let creator = pool.details.as_ref().and_then(|details| details.creator.clone());
+        let mut state = self.state.lock().await;
+        for position in state.positions.values_mut().filter(|position| position.token_address == token_address && position.pool_address.is_none()) {
+            position.pool_address = Some(pool.pool_address.clone());
+            position.pool_creator = creator.clone();
+        }
+    }
+
+    // Poll metadata for a held token and alert on a holder drop or sell-volume spike, also selling out of it
+    // when exit_on_dump and auto_sell are both set
+    pub async fn check_for_dump(&self, wallet_index: usize, token_address: &str, amount: f64) -> Result<Option<DumpSignal>> {
+        if !self.config.dump_detection.enabled {
+            return Ok(None);
+        }
+
+        let metadata = self.dex_monitor.get_token_metadata(token_address).await?;
+        let signal = self.dump_detector.lock().await.record(
+            token_address,
+            metadata.holders,
+            metadata.sell_volume_24h,
+            chrono::Utc::now().timestamp() as u64,
+        );
+
+        if let Some(signal) = &signal {
+            warn!("Dump detected for token {}: {}", token_address, signal);
+
+            let mut exited = false;
+            if self.config.dump_detection.exit_on_dump && self.config.auto_sell {
+                match self.sell_token(wallet_index, token_address, amount).await {
+                    Ok(_) => {
+                        exited = true;
+                        self.dump_detector.lock().await.clear(token_address);
+                    }
+                    Err(e) => error!("Failed to exit dumping token {}: {}", token_address, e),
+                }
+            }
+
+            if let Err(e) = self.telegram_sender.send_dump_alert_telegram_message(token_address, &signal.to_string(), exited).await {
+                error!("{}", e);
+            }
+        }
+
+        Ok(signal)
+    }
+
+    // Start real-time monitoring
+    pub async fn start_monitoring(&self) -> Result<()> {
+        info!("Starting real-time DEX monitoring...");
+        
+        {
+            let mut state = self.state.lock().await;
+            state.is_running = true;
+            // Positions restored from disk need prices too
+            self.sync_price_watchlist(&state);
+        }
+
+        // Start on-chain monitoring for Raydium and Orca, Jupiter listing polling and the WebSocket feeds, up to max_monitors of them
+        let message_tx = self.websocket_manager.get_message_sender();
+        let monitoring = &self.config.monitoring;
+        let mut monitors = MonitorTasks::new(self.state.clone(), monitoring.max_monitors);
+        if monitoring.enable_raydium && monitors.slot_free("Raydium") {
+            match self.dex_monitor.monitor_raydium_onchain(message_tx.clone(), monitoring.clone()).await {
+                Ok(task) => monitors.track(task).await,
+                Err(e) => error!("Raydium on-chain monitoring failed: {}", e),
+            }
+        }
+        if monitoring.enable_orca && monitors.slot_free("Orca") {
+            match self.dex_monitor.monitor_orca(message_tx.clone(), monitoring.clone()).await {
+                Ok(task) => monitors.track(task).await,
+                Err(e) => error!("Orca on-chain monitoring failed: {}", e),
+            }
+        }
+        if monitoring.enable_jupiter && monitors.slot_free("Jupiter") {
+            match self.dex_monitor.monitor_jupiter(message_tx, monitoring.clone()).await {
+                Ok(task) => monitors.track(task).await,
+                Err(e) => error!("Jupiter listing monitoring failed: {}", e),
+            }
+        }
+        // WebSocket feeds send on the same channel, so their messages reach the same receive loop
+        let websocket_monitors = self.websocket_manager.monitor_count();
+        if websocket_monitors > 0 && monitors.slot_free("WebSocket") {
+            let tasks = self.websocket_manager.spawn_monitors(monitors.free_slots());
+            if tasks.len() < websocket_monitors {
+                warn!("Starting {} of {} WebSocket monitors: max_monitors reached", tasks.len(), websocket_monitors);
+            } else {
+                info!("Starting {} WebSocket monitors", tasks.len());
+            }
+            for task in tasks {
+                monitors.track(task).await;
+            }
+        }
+        info!("Started {} monitors", monitors.len());
+        self.persist_state().await;
+
+        // Process messages from all monitors while watching open positions for exits, until shut down
+        let mut shutdown_rx = self.shutdown_tx.subscribe();
+        tokio::select! {
+            _ = self.process_messages() => {}
+            _ = self.monitor_positions() => {}
+            _ = self.handle_telegram_buttons() => {}
+            _ = self.monitor_rpc_lag() => {}
+            _ = self.watch_approve_list() => {}
+            _ = self.serve_metrics() => {}
+            _ = shutdown_rx.wait_for(|shutdown| *shutdown) => info!("Shutdown requested"),
+        }
+
+        monitors.abort_all();
+        self.stop().await
+    }
+
+    // Serve Prometheus metrics on metrics_port; a server that fails to start is logged and the bot carries on
+    async fn serve_metrics(&self) {
+        let Some(metrics_port) = self.config.metrics_port else {
+            return std::future::pending().await;
+        };
+        if let Err(e) = metrics::serve(metrics_port, self.state.clone(), self.snipe_latency.clone()).await {
+            error!("Metrics server on port {} failed: {}", metrics_port, e);
+        }
+        std::future::pending().await
+    }
+
+    // Pick up mints added to the approve list, sniping them right away if snipe_approved_on_add is set
+    async fn watch_approve_list(&self) {
+        let Some(approve_list) = &self.approve_list else {
+            return std::future::pending().await;
+        };
+
+        let interval = std::time::Duration::from_millis(self.config.safety.approve_list_poll_interval_ms);
+        loop {
+            tokio::time::sleep(interval).await;
+            let added = match approve_list.lock().await.reload_if_changed() {
+                Ok(added) => added,
+                Err(e) => {
+                    error!("{}", e);
+                    continue;
+                }
+            };
+            for token_address in added {
+                info!("Token {} added to the approve list", token_address);
+                if self.config.auto_buy && self.config.safety.snipe_approved_on_add {
+                    self.auto_snipe(&token_address).await;
+                }
+            }
+        }
+    }
+
+    async fn process_messages(&self) {
+        loop {
+            let message = self.websocket_manager.receive_message().await;
+            
+            if let Some(message) = message {
+                match message {
+                    WebSocketMessage::TokenListing(listing) => {
+                        info!(token = listing.token_address.as_str(), symbol = listing.symbol.as_str(); "New token listing");
+                        
+                        // Auto snipe if enabled
+                        if self.config.auto_buy && self.admit_evaluation(&listing.token_address) {
+                            self.auto_snipe(&listing.token_address).await;
+                        }
+                    }
+                    WebSocketMessage::PoolUpdate(pool) => {
+                        info!(pool = pool.pool_address.as_str(), token_a = pool.token_a.as_str(), token_b = pool.token_b.as_str(); "Pool update");
+
+                        if self.config.auto_buy {
+                            if let Some(token_address) = new_token_mint(&pool).filter(|token_address| self.admit_evaluation(token_address)) {
+                                match self.analyze_pool(&pool, token_address).await {
+                                    Ok(true) => {
+                                        self.auto_snipe(token_address).await;
+                                        self.set_position_pool(token_address, &pool).await;
+                                    }
+                                    Ok(false) => info!("Skipping pool {}: failed pool checks", pool.pool_address),
+                                    Err(e) => error!("Failed to analyze pool {}: {}", pool.pool_address, e),
+                                }
+                            }
+                        }
+                    }
+                    WebSocketMessage::PriceUpdate(price) => {
+                        debug!(token = price.token_address.as_str(), price = price.price; "Price update");
+                        self.apply_price_update(&price).await;
+                    }
+                    WebSocketMessage::Error(error_msg) => {
+                        error!("WebSocket error: {}", error_msg);
+                    }
+                }
+            } else {
+                // Channel closed, break the loop
+                break;
+            }
+        }
+    }
+
+    // Whether monitoring.max_evaluations_per_second leaves room to evaluate this token now
+    fn admit_evaluation(&self, token_address: &str) -> bool {
+        let Some(throttle) = &self.evaluation_throttle else {
+            return true;
+        };
+        let admitted = throttle.lock().unwrap().try_admit(std::time::Instant::now());
+        if !admitted {
+            warn!("Skipping {}: over max_evaluations_per_second", token_address);
+        }
+        admitted
+    }
+
+    async fn auto_snipe(&self, token_address: &str) {
+        if self.below_capital_floor().await {
+            info!("Not buying {}: auto-buy is paused below the capital floor", token_address);
+            return;
+        }
+
+        if !self.config.strategies.is_empty() {
+            let runs = self.config.strategies.iter().map(|strategy| self.run_strategy(strategy, token_address));
+            futures_util::future::join_all(runs).await;
+            return;
+        }
+
+        if !self.config.trading.multi_wallet_snipe.is_empty() {
+            self.multi_wallet_snipe(token_address).await;
+            return;
+        }
+
+        let Some(wallet_index) = self.select_wallet().await else {
+            warn!("Not buying {}: no wallet available", token_address);
+            return;
+        };
+        let amount_sol = match self.trade_size(wallet_index).await {
+            Ok(amount_sol) => amount_sol,
+            Err(e) => {
+                error!("Failed to size snipe of {} for wallet {}: {}", token_address, wallet_index, e);
+                return;
+            }
+        };
+        if let Err(e) = self.snipe_token(wallet_index, token_address, amount_sol).await {
+            error!("Failed to snipe token {}: {}", token_address, e);
+        }
+    }
+
+    // Pick the one wallet to buy a listing with, per monitoring.wallet_selection
+    async fn select_wallet(&self) -> Option<usize> {
+        if self.wallets.is_empty() {
+            return None;
+        }
+        match self.config.monitoring.wallet_selection {
+            WalletSelection::Single => Some(self.config.monitoring.designated_wallet),
+            WalletSelection::RoundRobin => Some(self.next_wallet.fetch_add(1, Ordering::Relaxed) % self.wallets.len()),
+            WalletSelection::LowestBalanceFirst => {
+                let needed = self.config.max_sol_per_trade + self.config.min_sol_balance;
+                self.wallet_balances().await.into_iter().enumerate()
+                    .filter_map(|(wallet_index, (_, balance))| match balance {
+                        Ok(balance) => Some((wallet_index, balance)),
+                        Err(e) => {
+                            warn!("Failed to check balance of wallet {}: {}", wallet_index, e);
+                            None
+                        }
+                    })
+                    .filter(|(_, balance)| *balance >= needed)
+                    .min_by(|a, b| a.1.total_cmp(&b.1))
+                    .map(|(wallet_index, _)| wallet_index)
+            }
+        }
+    }
+
+    // Check the combined wallet balance against the capital floor, alerting when buying pauses or resumes
+    async fn below_capital_floor(&self) -> bool {
+        let Some(capital_floor_sol) = self.config.trading.capital_floor_sol else {
+            return false;
+        };
+        let total_balance = match self.total_balance() {
+            Ok(total_balance) => total_balance,
+            Err(e) => {
+                error!("Failed to check total wallet balance: {}", e);
+                return self.state.lock().await.buying_paused;
+            }
+        };
+
+        let paused = total_balance < capital_floor_sol;
+        let mut state = self.state.lock().await;
+        if state.buying_paused == paused {
+            return paused;
+        }
+        state.buying_paused = paused;
+        drop(state);
+        self.persist_state().await;
+
+        if paused {
+            warn!("Total wallet balance {:.4} SOL is below the capital floor of {} SOL; pausing auto-buy", total_balance, capital_floor_sol);
+        } else {
+            info!("Total wallet balance {:.4} SOL is back above the capital floor of {} SOL; resuming auto-buy", total_balance, capital_floor_sol);
+        }
+        if let Err(e) = self.telegram_sender.send_capital_floor_telegram_message(total_balance, capital_floor_sol, paused).await {
+            error!("{}", e);
+        }
+        paused
+    }
+
+    // Act on a listing for one strategy, buying from each of its wallets
+    async fn run_strategy(&self, strategy: &StrategyConfig, token_address: &str) {
+        for &wallet_index in &strategy.wallets {
+            let amount_sol = match strategy.amount_sol {
+                Some(amount_sol) => amount_sol,
+                None => match self.trade_size(wallet_index).await {
+                    Ok(amount_sol) => amount_sol,
+                    Err(e) => {
+                        error!("Strategy {} failed to size snipe of {} for wallet {}: {}", strategy.name, token_address, wallet_index, e);
+                        continue;
+                    }
+                },
+            };
+            if let Err(e) = self.snipe_token(wallet_index, token_address, amount_sol).await {
+                error!("Strategy {} failed to snipe token {}: {}", strategy.name, token_address, e);
+            }
+        }
+    }
+
+    fn strategy_for_wallet(&self, wallet_index: usize) -> Option<&StrategyConfig> {
+        self.config.strategies.iter().find(|strategy| strategy.wallets.contains(&wallet_index))
+    }
+
+    // Size of the next auto-buy for a wallet, randomized and/or adapted to recent results per the sizing config
+    pub async fn trade_size(&self, wallet_index: usize) -> Result<f64> {
+        let base_amount = sizing::base_amount(self.config.max_sol_per_trade, &self.config.sizing);
+        // Random draws still go through the reserve and portfolio caps below
+        if !self.config.sizing.enabled && !self.config.sizing.randomize {
+            return Ok(base_amount);
+        }
+
+        let recent_pnl = self.state.lock().await.recent_pnl.clone();
+        if self.is_shadow_wallet(wallet_index) {
+            return Ok(base_amount * sizing::size_multiplier(&recent_pnl, &self.config.sizing));
+        }
+
+        let balance = self.check_balance(wallet_index)?;
+        Ok(sizing::adjusted_amount(base_amount, &recent_pnl, balance, self.config.min_sol_balance, &self.config.sizing))
+    }
+
+    // A quote with no (or dust) output would just waste a transaction
+    fn check_quote_output(&self, swap_info: &SwapInfo) -> Result<()> {
+        if swap_info.output_amount == 0 || swap_info.output_amount < self.config.trading.min_output_amount {
+            return Err(anyhow!(
+                "Quote output too low: {} {} (minimum {})",
+                swap_info.output_amount, swap_info.output_token, self.config.trading.min_output_amount.max(1)
+            ));
+        }
+        Ok(())
+    }
+
+    // Thin or fast-moving liquidity shows up as very different prices for the full and half-size buy
+    async fn check_quote_consistency(&self, swap_info: &SwapInfo) -> Result<()> {
+        let Some(max_quote_divergence) = self.config.trading.max_quote_divergence else {
+            return Ok(());
+        };
+        let half_quote = self.dex_monitor.get_jupiter_quote(
+            &swap_info.input_token,
+            &swap_info.output_token,
+            (swap_info.input_amount / 2).max(1),
+            self.slippage_bps(),
+        ).await?;
+        let divergence = dex_monitor::quote_price_divergence(swap_info, &half_quote);
+        if divergence > max_quote_divergence {
+            return Err(anyhow!(
+                "Quotes for {} disagree by {:.2}% (max {:.2}%)",
+                swap_info.output_token, divergence * 100.0, max_quote_divergence * 100.0
+            ));
+        }
+        debug!("Quotes for {} agree within {:.2}%", swap_info.output_token, divergence * 100.0);
+        Ok(())
+    }
+
+    fn snipe_confirmation_deadline(&self) -> Option<std::time::Duration> {
+        match self.config.trading.snipe_confirmation_deadline_ms {
+            0 => None,
+            deadline_ms => Some(std::time::Duration::from_millis(deadline_ms)),
+        }
+    }
+
+    fn slippage_bps(&self) -> u16 {
+        (self.config.slippage_tolerance * 10_000.0).round() as u16
+    }
+
+    // Non-shadow wallets that maintenance operations may act on, in index order
+    pub fn maintenance_wallet_indices(&self) -> Vec<usize> {
+        (0..self.wallets.len())
+            .filter(|wallet_index| !self.is_shadow_wallet(*wallet_index) && self.check_maintenance_wallet(*wallet_index).is_ok())
+            .collect()
+    }
+
+    fn check_maintenance_wallet(&self, wallet_index: usize) -> Result<()> {
+        match self.config.wallets.get(wallet_index) {
+            Some(wallet) if !self.config.maintenance_wallets.allows(wallet_index, wallet) => {
+                Err(anyhow!("Wallet {} is excluded from maintenance operations by maintenance_wallets", wallet_index))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn is_shadow_wallet(&self, wallet_index: usize) -> bool {
+        self.config.wallets.get(wallet_index).map(|wallet| wallet.is_shadow()).unwrap_or(false)
+    }
+
+    // Paper-trade a buy for a shadow wallet, tracking its cost basis separately from real trades
+    async fn record_shadow_buy(&self, trace_id: &str, wallet_index: usize, token_address: &str, amount_sol: f64) -> Result<String> {
+        let signature = format!("shadow-{}-{}", wallet_index, chrono::Utc::now().timestamp_millis());
+        let mut state = self.state.lock().await;
+        state.shadow_trades += 1;
+        *state.shadow_cost_basis.entry(format!("{}:{}", wallet_index, token_address)).or_insert(0.0) += amount_sol;
+        drop(state);
+
+        self.record_trade(trace_id, wallet_index, token_address, "shadow_buy", amount_sol, &signature).await;
+        info!("[trace={}] Shadow wallet {} simulated buy of {} with {} SOL", trace_id, wallet_index, token_address, amount_sol);
+        Ok(signature)
+    }
+
+    // Paper-trade a sell for a shadow wallet and realize its simulated PnL
+    async fn record_shadow_sell(&self, trace_id: &str, wallet_index: usize, token_address: &str, amount: f64, proceeds_sol: f64) -> Result<String> {
+        let signature = format!("shadow-{}-{}", wallet_index, chrono::Utc::now().timestamp_millis());
+        let mut state = self.state.lock().await;
+        let cost_sol = state.shadow_cost_basis.remove(&format!("{}:{}", wallet_index, token_address)).unwrap_or(0.0);
+        state.shadow_trades += 1;
+        state.shadow_profit += proceeds_sol - cost_sol;
+        drop(state);
+
+        self.record_trade(trace_id, wallet_index, token_address, "shadow_sell", amount, &signature).await;
+        info!("[trace={}] Shadow wallet {} simulated sell of {} for {} SOL (PnL {} SOL)",
+              trace_id, wallet_index, token_address, proceeds_sol, proceeds_sol - cost_sol);
+        Ok(signature)
+    }
+
+    async fn record_trade(&self, trace_id: &str, wallet_index: usize, token_address: &str, side: &str, amount: f64, signature: &str) {
+        let record = TradeRecord {
+            run_id: trace::current_run_id().unwrap_or_default().to_string(),
+            trace_id: trace_id.to_string(),
+            timestamp: chrono::Utc::now().timestamp() as u64,
+            wallet_index,
+            token_address: token_address.to_string(),
+            side: side.to_string(),
+            amount,
+            signature: signature.to_string(),
+        };
+        if let Err(e) = self.trade_log.lock().await.record(record) {
+            error!("Failed to write trade log: {}", e);
+        }
+    }
+
+    // Restore counters and open positions from the state file left by a previous run
+    pub fn load_state(persistence: &PersistenceConfig) -> Result<Option<SniperState>> {
+        let Some(state_file) = &persistence.state_file else {
+            return Ok(None);
+        };
+        let state: Option<SniperState> = persistence::read_json_file(state_file)
+            .map_err(|e| anyhow!("Failed to load state from {}: {}", state_file, e))?;
+        Ok(state.map(|mut state| {
+            info!("Loaded state from {}: {} trades, {} open positions", state_file, state.total_trades, state.positions.len());
+            // Liveness fields describe the previous process, not this one
+            state.is_running = false;
+            state.active_monitors = 0;
+            state
+        }))
+    }
+
+    // Save after a trade so a crash loses at most the trade in flight
+    async fn persist_state(&self) {
+        if let Err(e) = self.save_state().await {
+            error!("Failed to save state: {}", e);
+        }
+    }
+
+    // Save a snapshot of the current state to the configured state file
+    pub async fn save_state(&self) -> Result<()> {
+        if let Some(state_file) = &self.config.persistence.state_file {
+            let state = {
+                let mut state = self.state.lock().await;
+                state.rpc_health = self.rpc.health();
+                state.clone()
+            };
+            persistence::write_json_file(state_file, &state)?;
+        }
+        Ok(())
+    }
+
+    // Flush buffered trade records and persist the final state
+    pub async fn flush(&self) -> Result<()> {
+        self.trade_log.lock().await.flush()?;
+        self.save_state().await?;
+        Ok(())
+    }
+
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        ShutdownHandle(self.shutdown_tx.clone())
+    }
+
+    // Get current status, with open positions marked to market (falling back to their last known price)
+    pub async fn get_status(&self) -> SniperState {
+        let positions: Vec<Position> = self.state.lock().await.positions.values().cloned().collect();
+        let mut prices = HashMap::new();
+        for position in &positions {
+            match self.dex_monitor.get_token_metadata(&position.token_address).await {
+                Ok(metadata) => {
+                    prices.insert(Position::key(position.wallet_index, &position.token_address), metadata.price);
+                }
+                Err(e) => warn!("Could not price {} for unrealized PnL: {}", position.token_address, e),
+            }
+        }
+
+        let mut state = self.state.lock().await;
+        for (key, price) in prices {
+            if let Some(position) = state.positions.get_mut(&key) {
+                position.last_price = price;
+            }
+        }
+        state.unrealized_pnl = state.positions.values()
+            .filter_map(|position| position.unrealized_pnl(position.last_price))
+            .sum();
+        state.clone()
+    }
+
+    // Per-endpoint request and error counts, and which endpoint is active
+    pub fn rpc_health(&self) -> Vec<EndpointHealth> {
+        self.rpc.health()
+    }
+
+    // Make sure the RPC serves the configured cluster before doing anything with it
+    pub fn verify_cluster(&self) -> Result<()> {
+        if let Some(expected_cluster) = &self.config.expected_cluster {
+            let genesis_hash = self.rpc.call(|client| Ok(client.get_genesis_hash()?))?;
+            cluster::verify_genesis_hash(expected_cluster, &genesis_hash.to_string())?;
+            info!("RPC cluster verified: {}", expected_cluster);
+        }
+        Ok(())
+    }
+
+    // Start the bot
+    pub async fn start(&self) -> Result<()> {
+        info!("Starting Solana Sniper Bot...");
+        self.verify_cluster()?;
+        self.dex_monitor.check_jupiter_reachable().await?;
+        
+        // Start monitoring
+        self.start_monitoring().await?;
+        
+        Ok(())
+    }
+
+    // Stop the bot
+    pub async fn stop(&self) -> Result<()> {
+        let mut state = self.state.lock().await;
+        state.is_running = false;
+        state.active_monitors = 0;
+        drop(state);
         
         info!("Stopping Solana Sniper Bot...");
+
+        // Best-effort flush so a slow disk can't hang shutdown forever
+        let flush_timeout = std::time::Duration::from_millis(self.config.persistence.shutdown_flush_timeout_ms);
+        match tokio::time::timeout(flush_timeout, self.flush()).await {
+            Ok(result) => result?,
+            Err(_) => warn!("Shutdown flush timed out after {}ms", self.config.persistence.shutdown_flush_timeout_ms),
+        }
         Ok(())
     }
 }
+
+// The side of a new pool that isn't SOL, i.e. the token being launched
+pub(crate) fn new_token_mint(pool: &PoolUpdate) -> Option<&str> {
+    if pool.token_a == SOL_MINT {
+        Some(pool.token_b.as_str())
+    } else if pool.token_b == SOL_MINT {
+        Some(pool.token_a.as_str())
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mock_server::MockServer;
+
+    fn config() -> SniperConfig {
+        serde_json::from_value(serde_json::json!({
+            "rpc_url": "https://api.mainnet-beta.solana.com",
+            "wallets": [],
+            "min_sol_balance": 0.1,
+            "max_sol_per_trade": 1.0,
+            "slippage_tolerance": 0.05,
+            "auto_sell": true,
+            "anti_rug_check": true,
+            "dex_config": {
+                "raydium_api_url": "https://api.raydium.io",
+                "orca_api_url": "https://api.orca.so",
+                "jupiter_api_url": "https://quote-api.jup.ag",
+                "birdeye_api_url": "https://public-api.birdeye.so",
+                "solscan_api_url": "https://api.solscan.io",
+            },
+            "monitoring": {
+                "enable_raydium": true,
+                "enable_orca": false,
+                "enable_jupiter": false,
+                "check_interval_ms": 1_000,
+                "websocket_reconnect_delay_ms": 1_000,
+            },
+            "safety": {
+                "min_liquidity_sol": 5.0,
+                "max_creator_holdings_percent": 20.0,
+                "blacklist_check": true,
+                "honeypot_check": true,
+                "min_market_cap": 0.0,
+                "min_holders": 0,
+                "min_volume_24h": 0.0,
+            },
+            "trading": {
+                "max_price_impact": 0.1,
+                "min_slippage": 0.01,
+                "max_slippage": 0.1,
+                "gas_priority": "medium",
+                "retry_failed_trades": false,
+                "max_retries": 3,
+            },
+            "telegram": {
+                "bot_token": "",
+                "chat_id": "",
+            },
+        })).unwrap()
+    }
+
+    // A bot whose RPC and APIs all point at a closed local port, so anything reaching the network fails
+    fn offline_bot(blacklist_file: &str, approve_list_file: Option<&str>) -> SolanaSniperBot {
+        let unreachable = "http://127.0.0.1:1".to_string();
+        let mut config = config();
+        config.rpc_url = RpcUrls::One(unreachable.clone());
+        config.dex_config.jupiter_api_url = unreachable.clone();
+        config.dex_config.birdeye_api_url = unreachable.clone();
+        config.dex_config.metadata_retry.max_retries = 0;
+        config.safety.blacklist_file = blacklist_file.to_string();
+        config.safety.approve_list_file = approve_list_file.map(|path| path.to_string());
+        SolanaSniperBot::new(config).unwrap()
+    }
+
+    // A bot with `wallet_count` fresh wallets whose RPC, Jupiter, Birdeye and Raydium are all served from url. The
+    // blocking RPC client needs tests using it to run on a multi-threaded runtime.
+    fn mock_bot(url: &str, wallet_count: usize, configure: impl FnOnce(&mut SniperConfig)) -> SolanaSniperBot {
+        let mut config = config();
+        config.rpc_url = RpcUrls::One(url.to_string());
+        config.dex_config.jupiter_api_url = url.to_string();
+        config.dex_config.birdeye_api_url = url.to_string();
+        config.dex_config.raydium_api_url = url.to_string();
+        config.dex_config.birdeye_api_key = SecretString::from("test-key");
+        config.dex_config.metadata_retry.max_retries = 0;
+        config.anti_rug_check = false;
+        config.safety.blacklist_file = scratch_path(&format!("mock-bot-blacklist-{}", Pubkey::new_unique()));
+        config.wallets = (0..wallet_count).map(|_| {
+            let keypair = Keypair::new();
+            let path = scratch_path(&format!("mock-bot-wallet-{}", keypair.pubkey()));
+            solana_sdk::signature::write_keypair_file(&keypair, &path).unwrap();
+            WalletEntry::Path(path)
+        }).collect();
+        configure(&mut config);
+        SolanaSniperBot::new(config).unwrap()
+    }
+
+    fn scratch_path(name: &str) -> String {
+        std::env::temp_dir().join(format!("{}-{}.txt", name, std::process::id())).to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn base_config_is_valid() {
+        config().validate().unwrap();
+    }
+
+    fn validation_error(config: SniperConfig) -> String {
+        config.validate().unwrap_err().to_string()
+    }
+
+    #[test]
+    fn invalid_trade_limits_are_rejected() {
+        let mut zero_trade = config();
+        zero_trade.max_sol_per_trade = 0.0;
+        assert!(validation_error(zero_trade).contains("max_sol_per_trade"));
+
+        let mut slippage = config();
+        slippage.slippage_tolerance = 1.5;
+        assert!(validation_error(slippage).contains("slippage_tolerance"));
+
+        let mut inverted_slippage = config();
+        inverted_slippage.trading.min_slippage = 0.2;
+        assert!(validation_error(inverted_slippage).contains("must not exceed trading.max_slippage"));
+
+        let mut stop_loss = config();
+        stop_loss.trading.stop_loss_percent = Some(150.0);
+        assert!(validation_error(stop_loss).contains("trading.stop_loss_percent"));
+    }
+
+    #[test]
+    fn invalid_rpc_settings_are_rejected() {
+        let mut no_endpoints = config();
+        no_endpoints.rpc_url = RpcUrls::Many(Vec::new());
+        assert!(validation_error(no_endpoints).contains("rpc_url needs at least one endpoint"));
+
+        let mut commitment = config();
+        commitment.commitment = "eventually".to_string();
+        assert!(validation_error(commitment).contains("commitment must be processed, confirmed or finalized"));
+
+        let mut threshold = config();
+        threshold.rpc_failover_threshold = 0;
+        assert!(validation_error(threshold).contains("rpc_failover_threshold"));
+    }
+
+    #[test]
+    fn rpc_urls_must_also_be_in_rpc_url() {
+        let mut listed = config();
+        listed.rpc_url = RpcUrls::Many(vec!["https://a.example".to_string(), "https://b.example".to_string()]);
+        listed.rpc_urls = vec!["https://b.example".to_string()];
+        assert!(listed.validate().is_ok());
+
+        let mut stray = config();
+        stray.rpc_urls = vec!["https://b.example".to_string()];
+        assert!(validation_error(stray).contains("rpc_urls entry https://b.example is not in rpc_url"));
+    }
+
+    #[test]
+    fn invalid_wallet_references_are_rejected() {
+        let mut auto_buy = config();
+        auto_buy.auto_buy = true;
+        assert!(validation_error(auto_buy).contains("auto_buy needs at least one wallet"));
+
+        let mut multi_wallet = config();
+        multi_wallet.trading.multi_wallet_snipe = vec![WalletAllocation { wallet_index: 0, amount_sol: 0.1 }];
+        assert!(validation_error(multi_wallet).contains("trading.multi_wallet_snipe uses wallet 0"));
+    }
+
+    #[test]
+    fn invalid_safety_and_sizing_are_rejected() {
+        let mut holdings = config();
+        holdings.safety.max_creator_holdings_percent = 120.0;
+        assert!(validation_error(holdings).contains("safety.max_creator_holdings_percent"));
+
+        let mut token_age = config();
+        token_age.safety.min_token_age_secs = Some(600);
+        token_age.safety.max_token_age_secs = Some(60);
+        assert!(validation_error(token_age).contains("safety.min_token_age_secs"));
+
+        let mut spend_limit = config();
+        spend_limit.safety.daily_spend_limit_sol = Some(0.0);
+        assert!(validation_error(spend_limit).contains("safety.daily_spend_limit_sol"));
+
+        let mut random_sizing = config();
+        random_sizing.sizing.randomize = true;
+        random_sizing.sizing.buy_amount_min = 0.5;
+        random_sizing.sizing.buy_amount_max = 2.0;
+        assert!(validation_error(random_sizing).contains("must not exceed max_sol_per_trade"));
+    }
+
+    #[test]
+    fn state_round_trips_through_the_state_file() {
+        let mut state = SniperState {
+            is_running: true,
+            total_trades: 3,
+            successful_trades: 2,
+            failed_trades: 1,
+            total_profit: 0.25,
+            active_monitors: 2,
+            ..SniperState::default()
+        };
+        state.positions.insert(Position::key(1, "mint"), Position {
+            wallet_index: 1,
+            token_address: "mint".to_string(),
+            entry_amount_sol: 0.5,
+            token_amount: 1_000_000,
+            entry_price: 0.0005,
+            opened_at: 1_700_000_000,
+            pool_address: Some("pool".to_string()),
+            pool_creator: None,
+            last_pool_signature: None,
+            strategy: Some("fast".to_string()),
+            last_price: 0.0006,
+        });
+        state.reserve_snipe(1_700_000_000, "mint", 60).unwrap();
+        state.recent_spend.push(SpendRecord { timestamp: 1_700_000_000, wallet_index: 1, amount_sol: 0.5 });
+
+        let path = std::env::temp_dir().join(format!("sniper-state-{}.json", std::process::id()));
+        let state_file = path.to_str().unwrap().to_string();
+        persistence::write_json_file(&state_file, &state).unwrap();
+        let persistence = PersistenceConfig { state_file: Some(state_file), ..PersistenceConfig::default() };
+        let loaded = SolanaSniperBot::load_state(&persistence).unwrap().unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!((loaded.total_trades, loaded.successful_trades, loaded.failed_trades), (3, 2, 1));
+        assert_eq!(loaded.total_profit, 0.25);
+        let position = &loaded.positions[&Position::key(1, "mint")];
+        assert_eq!(position.token_amount, 1_000_000);
+        assert_eq!(position.entry_amount_sol, 0.5);
+        assert_eq!(position.pool_address.as_deref(), Some("pool"));
+        assert_eq!(position.strategy.as_deref(), Some("fast"));
+        assert_eq!(loaded.last_snipe_by_token["mint"], 1_700_000_000);
+        assert_eq!(loaded.recent_spend.len(), 1);
+        // Liveness belongs to the process that wrote the file
+        assert!(!loaded.is_running);
+        assert_eq!(loaded.active_monitors, 0);
+    }
+
+    #[test]
+    fn missing_state_file_loads_nothing() {
+        let path = std::env::temp_dir().join(format!("sniper-state-missing-{}.json", std::process::id()));
+        let persistence = PersistenceConfig { state_file: Some(path.to_str().unwrap().to_string()), ..PersistenceConfig::default() };
+        assert!(SolanaSniperBot::load_state(&persistence).unwrap().is_none());
+    }
+
+    #[test]
+    fn daily_spend_limit_caps_all_wallets_together() {
+        let mut safety = config().safety;
+        safety.daily_spend_limit_sol = Some(1.0);
+        let mut state = SniperState::default();
+        state.reserve_spend(1_000, 0, 0.6, &safety).unwrap();
+        state.reserve_spend(1_010, 1, 0.4, &safety).unwrap();
+
+        assert!(state.reserve_spend(1_020, 2, 0.1, &safety).unwrap_err().to_string().contains("daily spend limit"));
+        assert!(state.reserve_spend(1_000 + SPEND_WINDOW_SECS, 2, 0.6, &safety).is_ok());
+    }
+
+    #[test]
+    fn per_wallet_spend_limit_caps_each_wallet() {
+        let mut safety = config().safety;
+        safety.per_wallet_spend_limit_sol = Some(0.5);
+        let mut state = SniperState::default();
+        state.reserve_spend(1_000, 0, 0.5, &safety).unwrap();
+
+        assert!(state.reserve_spend(1_010, 0, 0.1, &safety).unwrap_err().to_string().contains("wallet 0's daily spend limit"));
+        assert!(state.reserve_spend(1_010, 1, 0.5, &safety).is_ok());
+    }
+
+    #[test]
+    fn released_spend_no_longer_counts() {
+        let mut safety = config().safety;
+        safety.daily_spend_limit_sol = Some(1.0);
+        let mut state = SniperState::default();
+        state.reserve_spend(1_000, 0, 0.8, &safety).unwrap();
+        assert!(state.reserve_spend(1_010, 0, 0.8, &safety).is_err());
+
+        // The first buy failed, so its reservation is given back
+        state.release_spend(1_000, 0, 0.8);
+        assert!(state.recent_spend.is_empty());
+        assert!(state.reserve_spend(1_010, 0, 0.8, &safety).is_ok());
+    }
+
+    #[tokio::test]
+    async fn blacklisted_mint_fails_before_any_api_call() {
+        let blacklisted = Pubkey::new_unique().to_string();
+        let path = scratch_path("bot-blacklist");
+        let bot = offline_bot(&path, None);
+        assert!(bot.add_to_blacklist(&blacklisted).await.unwrap());
+
+        let analysis = bot.analyze_token_detailed(&blacklisted).await.unwrap();
+        assert_eq!(analysis.failure().as_deref(), Some("blacklist: mint is blacklisted"));
+        assert_eq!(analysis.checks.len(), 1);
+
+        // Anything not listed goes on to the metadata lookup, which can't be reached
+        assert!(bot.analyze_token_detailed(&Pubkey::new_unique().to_string()).await.is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn approved_mint_skips_the_remaining_checks_but_not_the_blacklist() {
+        let (approved, approved_but_blacklisted) = (Pubkey::new_unique().to_string(), Pubkey::new_unique().to_string());
+        let (blacklist_file, approve_list_file) = (scratch_path("bot-approve-blacklist"), scratch_path("bot-approve-list"));
+        std::fs::write(&approve_list_file, format!("{}\n{}\n", approved, approved_but_blacklisted)).unwrap();
+        let bot = offline_bot(&blacklist_file, Some(&approve_list_file));
+        bot.add_to_blacklist(&approved_but_blacklisted).await.unwrap();
+
+        // Passing without the network shows the metadata lookup and later checks were skipped
+        let analysis = bot.analyze_token_detailed(&approved).await.unwrap();
+        assert!(analysis.passed());
+        assert_eq!(analysis.checks.last().unwrap().name, "approve_list");
+        assert!(!bot.analyze_token_detailed(&approved_but_blacklisted).await.unwrap().passed());
+
+        std::fs::remove_file(&blacklist_file).unwrap();
+        std::fs::remove_file(&approve_list_file).unwrap();
+    }
+
+    #[test]
+    fn snipe_cooldown_rejects_the_same_mint_from_any_wallet() {
+        let mut state = SniperState::default();
+        assert!(state.reserve_snipe(1_000, "mint", 60).is_ok());
+
+        assert!(state.reserve_snipe(1_030, "mint", 60).is_err());
+        assert!(state.reserve_snipe(1_030, "other-mint", 60).is_ok());
+    }
+
+    #[test]
+    fn snipe_cooldown_expires() {
+        let mut state = SniperState::default();
+        state.reserve_snipe(1_000, "mint", 60).unwrap();
+        assert!(state.reserve_snipe(1_060, "mint", 60).is_ok());
+        assert_eq!(state.last_snipe_by_token.len(), 1);
+    }
+
+    #[test]
+    fn released_snipe_no_longer_cools_down() {
+        let mut state = SniperState::default();
+        state.reserve_snipe(1_000, "mint", 60).unwrap();
+        state.release_snipe(1_000, "mint");
+        assert!(state.reserve_snipe(1_010, "mint", 60).is_ok());
+
+        // Releasing an older reservation leaves a newer one in place
+        state.release_snipe(1_000, "mint");
+        assert!(state.reserve_snipe(1_020, "mint", 60).is_err());
+    }
+
+    #[tokio::test]
+    async fn concurrent_snipes_of_one_mint_reserve_the_cooldown_once() {
+        let state = Arc::new(Mutex::new(SniperState::default()));
+        let reservations = (0..2).map(|_| {
+            let state = state.clone();
+            tokio::spawn(async move { state.lock().await.reserve_snipe(1_000, "mint", 60) })
+        });
+        let results = futures_util::future::join_all(reservations).await;
+        assert_eq!(results.into_iter().filter(|result| result.as_ref().unwrap().is_ok()).count(), 1);
+    }
+
+    #[tokio::test]
+    async fn active_monitors_counts_running_monitor_tasks_up_to_the_cap() {
+        let state = Arc::new(Mutex::new(SniperState::default()));
+        let mut monitors = MonitorTasks::new(state.clone(), Some(2));
+        let (exit_tx, exit_rx) = tokio::sync::oneshot::channel::<()>();
+
+        assert!(monitors.slot_free("Raydium"));
+        monitors.track(tokio::spawn(async { let _ = exit_rx.await; })).await;
+        assert!(monitors.slot_free("Orca"));
+        monitors.track(tokio::spawn(std::future::pending())).await;
+        assert_eq!(state.lock().await.active_monitors, 2);
+        assert!(!monitors.slot_free("Jupiter"));
+        assert_eq!(monitors.free_slots(), 0);
+
+        exit_tx.send(()).unwrap();
+        tokio::time::timeout(std::time::Duration::from_secs(5), async {
+            while state.lock().await.active_monitors != 1 {
+                tokio::task::yield_now().await;
+            }
+        }).await.expect("the exited monitor is no longer counted");
+
+        monitors.abort_all();
+        tokio::time::timeout(std::time::Duration::from_secs(5), async {
+            while state.lock().await.active_monitors != 0 {
+                tokio::task::yield_now().await;
+            }
+        }).await.expect("aborted monitors are no longer counted");
+    }
+
+    #[tokio::test]
+    async fn each_websocket_monitor_is_its_own_task_up_to_the_cap() {
+        let mut websocket_manager = DexWebSocketManager::new();
+        for _ in 0..3 {
+            websocket_manager.add_monitor("ws://127.0.0.1:1".to_string(), SubscriptionKind::Raydium, retry::RetryPolicy::default(), 1_000);
+        }
+        let state = Arc::new(Mutex::new(SniperState::default()));
+        let mut monitors = MonitorTasks::new(state.clone(), Some(2));
+
+        for task in websocket_manager.spawn_monitors(monitors.free_slots()) {
+            monitors.track(task).await;
+        }
+
+        assert_eq!(monitors.len(), 2);
+        assert_eq!(state.lock().await.active_monitors, 2);
+        monitors.abort_all();
+    }
+
+    // Enough workers for the mock server to hold every wallet's quote at once
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn multi_wallet_snipe_buys_from_every_wallet_at_once() {
+        let quoting = Arc::new((std::sync::Mutex::new(0), std::sync::Condvar::new()));
+        let cluster = MockServer::start(move |request| {
+            if request.path.starts_with("/quote") {
+                // Hold every quote until all three wallets are quoting, which only happens if they snipe at once
+                let (quotes, all_quoting) = &*quoting;
+                let mut quotes = quotes.lock().unwrap();
+                *quotes += 1;
+                all_quoting.notify_all();
+                let (quotes, wait) = all_quoting.wait_timeout_while(quotes, std::time::Duration::from_secs(5), |quotes| *quotes < 3).unwrap();
+                drop(quotes);
+                if wait.timed_out() {
+                    return (503, serde_json::json!({ "error": "wallets quoted one at a time" }));
+                }
+            }
+            mock_server::healthy_cluster(request)
+        }).await;
+        let bot = mock_bot(cluster.url(), 3, |config| {
+            config.trading.multi_wallet_snipe = (0..3).map(|wallet_index| WalletAllocation { wallet_index, amount_sol: 0.1 * (wallet_index + 1) as f64 }).collect();
+        });
+        let mint = Pubkey::new_unique().to_string();
+
+        let results = bot.multi_wallet_snipe(&mint).await;
+
+        assert_eq!(results.iter().map(|(wallet_index, _)| *wallet_index).collect::<Vec<_>>(), vec![0, 1, 2]);
+        assert!(results.iter().all(|(_, result)| result.is_ok()), "{:?}", results);
+        let payers: HashSet<Pubkey> = cluster.requests().iter()
+            .filter(|request| mock_server::rpc_method(request) == Some("sendTransaction"))
+            .map(|request| *mock_server::sent_transaction(request).message.static_account_keys().first().unwrap())
+            .collect();
+        assert_eq!(payers, bot.wallets.iter().map(|wallet| wallet.pubkey()).collect());
+        let state = bot.state.lock().await;
+        assert_eq!(state.successful_trades, 3);
+        for wallet_index in 0..3 {
+            let position = &state.positions[&Position::key(wallet_index, &mint)];
+            assert!((position.entry_amount_sol - 0.1 * (wallet_index + 1) as f64).abs() < 1e-9);
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn stop_flushes_buffered_trades_and_the_latest_state() {
+        let cluster = MockServer::start(mock_server::healthy_cluster).await;
+        let state_file = scratch_path(&format!("mock-bot-state-{}", Pubkey::new_unique()));
+        let trade_log_file = scratch_path(&format!("mock-bot-trades-{}", Pubkey::new_unique()));
+        let bot = mock_bot(cluster.url(), 1, |config| {
+            config.persistence.state_file = Some(state_file.clone());
+            config.persistence.trade_log_file = Some(trade_log_file.clone());
+            config.persistence.trade_log_buffer_size = 20;
+        });
+        let mint = Pubkey::new_unique().to_string();
+
+        let signature = bot.snipe_token(0, &mint, 0.1).await.unwrap();
+        // The buy is still buffered in memory
+        assert!(persistence::read_json_lines::<TradeRecord>(&trade_log_file).unwrap().is_empty());
+        bot.stop().await.unwrap();
+
+        let trades: Vec<TradeRecord> = persistence::read_json_lines(&trade_log_file).unwrap();
+        assert_eq!(trades.len(), 1);
+        assert_eq!((trades[0].side.as_str(), trades[0].token_address.as_str(), trades[0].signature.as_str()), ("buy", mint.as_str(), signature.as_str()));
+        let state: SniperState = persistence::read_json_file(&state_file).unwrap().unwrap();
+        assert!(!state.is_running);
+        assert_eq!(state.successful_trades, 1);
+        assert!(state.positions.contains_key(&Position::key(0, &mint)));
+        std::fs::remove_file(&state_file).unwrap();
+        std::fs::remove_file(&trade_log_file).unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn shadow_wallet_paper_trades_while_a_real_wallet_executes() {
+        let cluster = MockServer::start(mock_server::healthy_cluster).await;
+        let bot = mock_bot(cluster.url(), 2, |config| {
+            config.wallets[1] = WalletEntry::Detailed { path: config.wallets[1].path().to_string(), shadow: true, tags: Vec::new() };
+            config.trading.multi_wallet_snipe = (0..2).map(|wallet_index| WalletAllocation { wallet_index, amount_sol: 0.1 }).collect();
+        });
+        let mint = Pubkey::new_unique().to_string();
+
+        let results = bot.multi_wallet_snipe(&mint).await;
+
+        let signatures: Vec<&String> = results.iter().map(|(_, result)| result.as_ref().unwrap()).collect();
+        assert!(!signatures[0].starts_with("shadow-"));
+        assert!(signatures[1].starts_with("shadow-"));
+        let payers: Vec<Pubkey> = cluster.requests().iter()
+            .filter(|request| mock_server::rpc_method(request) == Some("sendTransaction"))
+            .map(|request| *mock_server::sent_transaction(request).message.static_account_keys().first().unwrap())
+            .collect();
+        assert_eq!(payers, vec![bot.wallets[0].pubkey()]);
+        let state = bot.state.lock().await;
+        assert_eq!(state.shadow_trades, 1);
+        assert!((state.shadow_cost_basis[&format!("1:{}", mint)] - 0.1).abs() < 1e-9);
+        assert!(state.positions.contains_key(&Position::key(0, &mint)));
+        assert!(state.positions.contains_key(&Position::key(1, &mint)));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn zero_output_quote_is_rejected_before_anything_is_sent() {
+        let cluster = MockServer::start(|request| {
+            if request.path.starts_with("/quote") {
+                let (status, mut quote) = mock_server::healthy_cluster(request);
+                quote["outAmount"] = serde_json::json!("0");
+                return (status, quote);
+            }
+            mock_server::healthy_cluster(request)
+        }).await;
+        let bot = mock_bot(cluster.url(), 1, |_| {});
+        let mint = Pubkey::new_unique().to_string();
+
+        let error = bot.snipe_token(0, &mint, 0.1).await.unwrap_err();
+
+        assert!(error.to_string().contains("Quote output too low"), "{}", error);
+        assert!(!cluster.requests().iter().any(|request| {
+            request.path.starts_with("/swap") || mock_server::rpc_method(request) == Some("sendTransaction")
+        }));
+        assert!(bot.state.lock().await.positions.is_empty());
+    }
+
+    // A Token-2022 mint whose TransferFeeConfig extension charges fee_bps on every transfer
+    fn token_2022_mint_with_transfer_fee(fee_bps: u16) -> serde_json::Value {
+        let mut data = vec![0; 166];
+        data[44] = 6;
+        data[45] = 1;
+        // Account type (mint), then the TransferFeeConfig extension header and its 108-byte value
+        data[165] = 1;
+        data.extend_from_slice(&1u16.to_le_bytes());
+        data.extend_from_slice(&108u16.to_le_bytes());
+        let mut transfer_fee_config = vec![0; 108];
+        transfer_fee_config[106..].copy_from_slice(&fee_bps.to_le_bytes());
+        data.extend_from_slice(&transfer_fee_config);
+        mock_server::account(token_accounts::TOKEN_2022_PROGRAM_ID, &data)
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn high_tax_tokens_are_rejected_and_low_tax_tokens_pass() {
+        let taxed_on_sell = Pubkey::new_unique().to_string();
+        let transfer_fee = Pubkey::new_unique().to_string();
+        let untaxed = Pubkey::new_unique().to_string();
+        let (taxed_mint, fee_mint) = (taxed_on_sell.clone(), transfer_fee.clone());
+        let cluster = MockServer::start(move |request| {
+            // Selling the taxed token returns only 80% of what was paid for it
+            if request.path.starts_with("/quote") && mock_server::query_param(request, "inputMint").as_deref() == Some(taxed_mint.as_str()) {
+                let (status, mut quote) = mock_server::healthy_cluster(request);
+                let amount: u64 = quote["inAmount"].as_str().unwrap().parse().unwrap();
+                quote["outAmount"] = serde_json::json!((amount * 8 / 10).to_string());
+                return (status, quote);
+            }
+            if mock_server::rpc_method(request) == Some("getAccountInfo") && request.body["params"][0] == fee_mint.as_str() {
+                return mock_server::rpc_result(request, serde_json::json!({ "context": { "slot": 1 }, "value": token_2022_mint_with_transfer_fee(500) }));
+            }
+            mock_server::healthy_cluster(request)
+        }).await;
+        let bot = mock_bot(cluster.url(), 1, |config| config.safety.max_token_tax_percent = Some(5.0));
+        let token_tax = |analysis: &TokenAnalysis| analysis.checks.iter().find(|check| check.name == "token_tax").cloned().unwrap_or_else(|| panic!("{}", analysis));
+
+        let taxed = bot.analyze_token_detailed(&taxed_on_sell).await.unwrap();
+        assert!(!token_tax(&taxed).passed, "{}", taxed);
+        assert_eq!(taxed.failure().unwrap(), "token_tax: estimated 20.00% > 5%");
+
+        // A 5% transfer fee is paid on both the buy and the sell
+        let fee = bot.analyze_token_detailed(&transfer_fee).await.unwrap();
+        assert_eq!(fee.failure().unwrap(), "token_tax: estimated 10.00% > 5%");
+
+        let clean = bot.analyze_token_detailed(&untaxed).await.unwrap();
+        assert!(token_tax(&clean).passed, "{}", clean);
+        assert_eq!(token_tax(&clean).detail, "estimated 0.00% <= 5%");
+    }
+
+    // A log line captured by the test logger, with the structured fields trade events carry
+    struct CapturedLog {
+        message: String,
+        trace: Option<String>,
+        token: Option<String>,
+    }
+
+    static CAPTURED_LOGS: std::sync::Mutex<Vec<CapturedLog>> = std::sync::Mutex::new(Vec::new());
+
+    struct CaptureLogger;
+
+    impl log::Log for CaptureLogger {
+        fn enabled(&self, metadata: &log::Metadata) -> bool {
+            metadata.level() <= log::Level::Info
+        }
+
+        fn log(&self, record: &log::Record) {
+            let field = |key: &str| record.key_values().get(log::kv::Key::from(key)).map(|value| value.to_string());
+            CAPTURED_LOGS.lock().unwrap().push(CapturedLog { message: record.args().to_string(), trace: field("trace"), token: field("token") });
+        }
+
+        fn flush(&self) {}
+    }
+
+    // Capture info-level logs from every test from here on; the logger is global, so callers filter what they read
+    fn capture_logs() {
+        static INSTALL: std::sync::Once = std::sync::Once::new();
+        INSTALL.call_once(|| {
+            log::set_logger(&CaptureLogger).unwrap();
+            log::set_max_level(log::LevelFilter::Info);
+        });
+    }
+
+    // The trace ID of every captured log line about token, from its "[trace=...]" prefix or its trace field
+    fn logged_trace_ids(token: &str) -> Vec<String> {
+        CAPTURED_LOGS.lock().unwrap().iter()
+            .filter(|log| log.message.contains(token) || log.token.as_deref() == Some(token))
+            .filter_map(|log| log.trace.clone().or_else(|| {
+                log.message.strip_prefix("[trace=").and_then(|rest| rest.split(']').next()).map(str::to_string)
+            }))
+            .collect()
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn every_event_of_a_trade_carries_its_trace_id() {
+        capture_logs();
+        let cluster = MockServer::start(mock_server::healthy_cluster).await;
+        let trade_log_file = scratch_path(&format!("mock-bot-trades-{}", Pubkey::new_unique()));
+        let bot = mock_bot(cluster.url(), 1, |config| {
+            config.persistence.trade_log_file = Some(trade_log_file.clone());
+            config.persistence.trade_log_buffer_size = 1;
+        });
+        let (first, second) = (Pubkey::new_unique().to_string(), Pubkey::new_unique().to_string());
+
+        bot.snipe_token(0, &first, 0.1).await.unwrap();
+        bot.snipe_token(0, &second, 0.1).await.unwrap();
+
+        let trades: Vec<TradeRecord> = persistence::read_json_lines(&trade_log_file).unwrap();
+        let trace_ids: Vec<&str> = trades.iter().map(|trade| trade.trace_id.as_str()).collect();
+        assert_eq!(trades.iter().map(|trade| trade.token_address.as_str()).collect::<Vec<_>>(), vec![first.as_str(), second.as_str()]);
+        assert_ne!(trace_ids[0], trace_ids[1]);
+        for (token, trace_id) in [(&first, trace_ids[0]), (&second, trace_ids[1])] {
+            let logged = logged_trace_ids(token);
+            assert!(logged.len() >= 2, "{:?}", logged);
+            assert!(logged.iter().all(|logged| logged == trace_id), "{} vs {:?}", trace_id, logged);
+        }
+        std::fs::remove_file(&trade_log_file).unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn snipe_missing_its_confirmation_deadline_opens_no_position() {
+        // The transaction is accepted but never confirms
+        let cluster = MockServer::start(|request| match mock_server::rpc_method(request) {
+            Some("getSignatureStatuses") => mock_server::rpc_result(request, serde_json::json!({ "context": { "slot": 1 }, "value": [null] })),
+            _ => mock_server::healthy_cluster(request),
+        }).await;
+        let bot = mock_bot(cluster.url(), 1, |config| config.trading.snipe_confirmation_deadline_ms = 300);
+        let mint = Pubkey::new_unique().to_string();
+
+        let error = bot.snipe_token(0, &mint, 0.1).await.unwrap_err();
+
+        assert!(error.to_string().contains("not confirmed within 300ms"), "{}", error);
+        let state = bot.state.lock().await;
+        assert!(state.positions.is_empty());
+        assert_eq!((state.total_trades, state.successful_trades, state.failed_trades), (1, 0, 1));
+        assert_eq!(state.last_snipe_time, 0);
+    }
+
+    // Builds a plain SOL transfer to a fixed recipient in place of a swap
+    struct TransferBuilder {
+        recipient: Pubkey,
+    }
+
+    impl TransactionBuilder for TransferBuilder {
+        fn build(&self, request: &SnipeRequest) -> Result<BuiltTransaction> {
+            let lamports = (request.amount_sol * LAMPORTS_PER_SOL as f64) as u64;
+            let transfer = system_instruction::transfer(&request.payer, &self.recipient, lamports);
+            let message = solana_sdk::message::Message::new_with_blockhash(&[transfer], Some(&request.payer), &request.recent_blockhash);
+            Ok(BuiltTransaction { message: solana_sdk::message::VersionedMessage::Legacy(message), expected_token_amount: 42_000 })
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn custom_builder_transaction_is_signed_and_submitted() {
+        let cluster = MockServer::start(mock_server::healthy_cluster).await;
+        let recipient = Pubkey::new_unique();
+        let bot = mock_bot(cluster.url(), 1, |_| {}).with_transaction_builder(Box::new(TransferBuilder { recipient }));
+        let mint = Pubkey::new_unique().to_string();
+
+        let signature = bot.snipe_token(0, &mint, 0.1).await.unwrap();
+
+        let requests = cluster.requests();
+        assert!(!requests.iter().any(|request| request.path.starts_with("/quote") || request.path.starts_with("/swap")));
+        let sent: Vec<VersionedTransaction> = requests.iter()
+            .filter(|request| mock_server::rpc_method(request) == Some("sendTransaction"))
+            .map(mock_server::sent_transaction)
+            .collect();
+        assert_eq!(sent.len(), 1);
+        let transaction = &sent[0];
+        assert_eq!(transaction.signatures[0].to_string(), signature);
+        assert!(transaction.verify_with_results().iter().all(|verified| *verified));
+        let keys = transaction.message.static_account_keys();
+        assert_eq!(keys[0], bot.wallets[0].pubkey());
+        let transfer = system_instruction::transfer(&bot.wallets[0].pubkey(), &recipient, LAMPORTS_PER_SOL / 10);
+        assert!(transaction.message.instructions().iter().any(|instruction| {
+            keys[instruction.program_id_index as usize] == transfer.program_id && instruction.data == transfer.data
+        }));
+        assert_eq!(bot.state.lock().await.positions[&Position::key(0, &mint)].token_amount, 42_000);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn dust_sweep_swaps_dust_and_leaves_larger_balances_alone() {
+        let owner = Keypair::new();
+        let (dust, holding, emptied) = (Pubkey::new_unique().to_string(), Pubkey::new_unique().to_string(), Pubkey::new_unique().to_string());
+        // At par, 1_000 raw units quote to 1_000 lamports and 50_000_000 to 0.05 SOL
+        let accounts = serde_json::json!([
+            mock_server::token_account(&owner.pubkey(), &dust, 1_000),
+            mock_server::token_account(&owner.pubkey(), &holding, 50_000_000),
+            mock_server::token_account(&owner.pubkey(), &emptied, 0),
+        ]);
+        let cluster = MockServer::start(move |request| {
+            if mock_server::rpc_method(request) == Some("getTokenAccountsByOwner") && request.body["params"][1]["programId"] == token_accounts::TOKEN_PROGRAM_ID {
+                return mock_server::rpc_result(request, serde_json::json!({ "context": { "slot": 1 }, "value": accounts }));
+            }
+            mock_server::healthy_cluster(request)
+        }).await;
+        let keypair_path = scratch_path(&format!("mock-bot-wallet-{}", owner.pubkey()));
+        solana_sdk::signature::write_keypair_file(&owner, &keypair_path).unwrap();
+        let bot = mock_bot(cluster.url(), 0, |config| config.wallets = vec![WalletEntry::Path(keypair_path)]);
+
+        let report = bot.sweep_dust(0, 0.01).await.unwrap();
+
+        assert_eq!(report.swept_tokens, vec![dust.clone()]);
+        assert!(report.skipped_no_route.is_empty());
+        assert_eq!(report.closed_accounts, 1);
+        let swapped: Vec<String> = cluster.requests().iter()
+            .filter(|request| request.path.starts_with("/swap"))
+            .map(|request| request.body["quoteResponse"]["inputMint"].as_str().unwrap_or_default().to_string())
+            .collect();
+        assert_eq!(swapped, vec![dust]);
+        assert!((report.reclaimed_sol - (1_000.0 + 2_039_280.0) / LAMPORTS_PER_SOL as f64).abs() < 1e-12);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn strategies_act_independently_on_one_listing() {
+        let cluster = MockServer::start(mock_server::healthy_cluster).await;
+        let bot = mock_bot(cluster.url(), 2, |config| {
+            config.strategies = vec![
+                StrategyConfig { name: "fast".to_string(), wallets: vec![0], amount_sol: Some(0.1), take_profit_percent: Some(50.0), stop_loss_percent: None },
+                StrategyConfig { name: "slow".to_string(), wallets: vec![1], amount_sol: Some(0.2), take_profit_percent: Some(200.0), stop_loss_percent: Some(30.0) },
+            ];
+        });
+        let mint = Pubkey::new_unique().to_string();
+
+        bot.auto_snipe(&mint).await;
+
+        let state = bot.state.lock().await;
+        let fast = &state.positions[&Position::key(0, &mint)];
+        let slow = &state.positions[&Position::key(1, &mint)];
+        assert_eq!((fast.strategy.as_deref(), fast.entry_amount_sol), (Some("fast"), 0.1));
+        assert_eq!((slow.strategy.as_deref(), slow.entry_amount_sol), (Some("slow"), 0.2));
+        assert_eq!(bot.exit_thresholds(fast), (Some(50.0), None));
+        assert_eq!(bot.exit_thresholds(slow), (Some(200.0), Some(30.0)));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn auto_buy_pauses_below_the_capital_floor_and_resumes_above_it() {
+        let balance = Arc::new(std::sync::atomic::AtomicU64::new(2 * LAMPORTS_PER_SOL));
+        let wallet_balance = balance.clone();
+        let cluster = MockServer::start(move |request| match mock_server::rpc_method(request) {
+            Some("getBalance") => mock_server::rpc_result(request, serde_json::json!({ "context": { "slot": 1 }, "value": wallet_balance.load(Ordering::SeqCst) })),
+            _ => mock_server::healthy_cluster(request),
+        }).await;
+        let bot = mock_bot(cluster.url(), 1, |config| config.trading.capital_floor_sol = Some(5.0));
+        let (first, second) = (Pubkey::new_unique().to_string(), Pubkey::new_unique().to_string());
+        let quotes = || cluster.requests().iter().filter(|request| request.path.starts_with("/quote")).count();
+
+        bot.auto_snipe(&first).await;
+        assert!(bot.state.lock().await.buying_paused);
+        assert_eq!(quotes(), 0);
+
+        balance.store(10 * LAMPORTS_PER_SOL, Ordering::SeqCst);
+        bot.auto_snipe(&second).await;
+        let state = bot.state.lock().await;
+        assert!(!state.buying_paused);
+        assert!(!state.positions.contains_key(&Position::key(0, &first)));
+        assert!(state.positions.contains_key(&Position::key(0, &second)));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn take_profit_waits_for_a_sell_quote_that_reaches_the_target() {
+        // Sell quotes pay out `multiplier` lamports per raw token unit
+        let multiplier = Arc::new(std::sync::atomic::AtomicU64::new(1));
+        let quote_multiplier = multiplier.clone();
+        let cluster = MockServer::start(move |request| {
+            let (status, mut response) = mock_server::healthy_cluster(request);
+            if request.path.starts_with("/quote") {
+                let amount: u64 = response["inAmount"].as_str().unwrap().parse().unwrap();
+                response["outAmount"] = serde_json::json!((amount * quote_multiplier.load(Ordering::SeqCst)).to_string());
+            }
+            (status, response)
+        }).await;
+        let bot = mock_bot(cluster.url(), 1, |config| {
+            config.trading.take_profit_percent = Some(50.0);
+            config.trading.requote_before_take_profit = true;
+        });
+        let position = Position {
+            wallet_index: 0,
+            token_address: Pubkey::new_unique().to_string(),
+            entry_amount_sol: 0.1,
+            token_amount: 100_000_000,
+            entry_price: 0.001,
+            opened_at: 1_700_000_000,
+            pool_address: None,
+            pool_creator: None,
+            last_pool_signature: None,
+            strategy: None,
+            last_price: 0.001,
+        };
+
+        // The feed says +100%, but selling would only return the 0.1 SOL paid
+        assert!(!bot.confirm_take_profit(&position, 50.0).await.unwrap());
+        assert!(!bot.exit_on_price(&position, 0.002, Some(50.0), None).await.unwrap());
+        assert!(!cluster.requests().iter().any(|request| request.path.starts_with("/swap")));
+
+        multiplier.store(2, Ordering::SeqCst);
+        assert!(bot.confirm_take_profit(&position, 50.0).await.unwrap());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn thin_liquidity_for_the_market_cap_is_rejected() {
+        let thin = Pubkey::new_unique().to_string();
+        let healthy = Pubkey::new_unique().to_string();
+        let thin_mint = thin.clone();
+        // Both tokens have 100 SOL of liquidity at $150, i.e. $15,000
+        let cluster = MockServer::start(move |request| {
+            let token = mock_server::query_param(request, "address").or_else(|| mock_server::query_param(request, "mint1"));
+            if request.path.starts_with("/pools/info/mint") {
+                return (200, serde_json::json!({ "data": { "data": [{
+                    "mintA": { "address": token, "decimals": 6 },
+                    "mintB": { "address": SOL_MINT, "decimals": 9 },
+                    "mintAmountA": 1_000_000.0,
+                    "mintAmountB": 100.0,
+                }] } }));
+            }
+            let (status, mut response) = mock_server::healthy_cluster(request);
+            if request.path.starts_with("/defi/token_overview") {
+                let (price, market_cap) = match token.as_deref() {
+                    Some(SOL_MINT) => (150.0, 0.0),
+                    Some(token) if token == thin_mint => (0.001, 1_000_000.0),
+                    _ => (0.001, 100_000.0),
+                };
+                response["data"]["price"] = serde_json::json!(price);
+                response["data"]["mc"] = serde_json::json!(market_cap);
+            }
+            (status, response)
+        }).await;
+        let bot = mock_bot(cluster.url(), 1, |config| config.safety.min_liquidity_to_market_cap_ratio = Some(0.05));
+
+        let rejected = bot.analyze_token_detailed(&thin).await.unwrap();
+        assert_eq!(rejected.failure().unwrap(), "liquidity: 0.0150 of market cap < 0.05");
+
+        let passed = bot.analyze_token_detailed(&healthy).await.unwrap();
+        let liquidity = passed.checks.iter().find(|check| check.name == "liquidity").unwrap_or_else(|| panic!("{}", passed));
+        assert!(liquidity.passed);
+        assert_eq!(liquidity.detail, "100 SOL >= 5 SOL, 0.1500 of market cap >= 0.05");
+    }
+
+    #[tokio::test]
+    async fn wallet_errors_say_whether_wallets_are_missing_or_the_index_is_wrong() {
+        let mut auto_buy = config();
+        auto_buy.auto_buy = true;
+        let startup = SolanaSniperBot::new(auto_buy).err().unwrap().to_string();
+        assert_eq!(startup, "Invalid config: auto_buy needs at least one wallet in wallets");
+
+        let unreachable = "http://127.0.0.1:1";
+        let monitor_only = mock_bot(unreachable, 0, |_| {});
+        let no_wallets = "No wallets are loaded; add wallet keypair files to wallets in the config";
+        assert_eq!(monitor_only.check_balance(0).unwrap_err().to_string(), no_wallets);
+        assert_eq!(monitor_only.snipe_token(0, &Pubkey::new_unique().to_string(), 0.1).await.unwrap_err().to_string(), no_wallets);
+        assert_eq!(monitor_only.sell_token(0, &Pubkey::new_unique().to_string(), 1.0).await.unwrap_err().to_string(), no_wallets);
+
+        let two_wallets = mock_bot(unreachable, 2, |_| {});
+        assert_eq!(two_wallets.check_balance(5).unwrap_err().to_string(), "Wallet index 5 is out of range: 2 wallets are loaded (indices 0-1)");
+        assert_eq!(two_wallets.sweep_dust(2, 0.01).await.unwrap_err().to_string(), "Wallet index 2 is out of range: 2 wallets are loaded (indices 0-1)");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn usdc_quoted_trades_scale_amounts_and_pnl_by_six_decimals() {
+        let owner = Keypair::new();
+        let usdc = Pubkey::new_unique().to_string();
+        let mint = Pubkey::new_unique().to_string();
+        let (wallet, quote_mint, token) = (owner.pubkey(), usdc.clone(), mint.clone());
+        let cluster = MockServer::start(move |request| {
+            let (status, mut response) = mock_server::healthy_cluster(request);
+            // Selling the token returns 1.5 times what it cost
+            if request.path.starts_with("/quote") && mock_server::query_param(request, "inputMint").as_deref() == Some(token.as_str()) {
+                let amount: u64 = response["inAmount"].as_str().unwrap().parse().unwrap();
+                response["outAmount"] = serde_json::json!((amount * 3 / 2).to_string());
+            }
+            let context = serde_json::json!({ "slot": 1 });
+            match mock_server::rpc_method(request) {
+                Some("getTokenSupply") => mock_server::rpc_result(request, serde_json::json!({
+                    "context": context,
+                    "value": { "amount": "1000000000000", "decimals": 6, "uiAmount": 1_000_000.0, "uiAmountString": "1000000" },
+                })),
+                // 50 USDC to spend
+                Some("getTokenAccountsByOwner") if request.body["params"][1]["mint"] == quote_mint.as_str() => mock_server::rpc_result(request, serde_json::json!({
+                    "context": context,
+                    "value": [mock_server::token_account(&wallet, &quote_mint, 50_000_000)],
+                })),
+                _ => (status, response),
+            }
+        }).await;
+        let keypair_path = scratch_path(&format!("mock-bot-wallet-{}", owner.pubkey()));
+        solana_sdk::signature::write_keypair_file(&owner, &keypair_path).unwrap();
+        let bot = mock_bot(cluster.url(), 0, |config| {
+            config.wallets = vec![WalletEntry::Path(keypair_path)];
+            config.trading.quote_mint = usdc.clone();
+            config.max_sol_per_trade = 20.0;
+        });
+
+        bot.snipe_token(0, &mint, 10.0).await.unwrap();
+        let buy_amounts: Vec<String> = cluster.requests().iter()
+            .filter(|request| request.path.starts_with("/quote"))
+            .filter_map(|request| mock_server::query_param(request, "amount"))
+            .collect();
+        assert_eq!(buy_amounts, vec!["10000000"]);
+        let position = bot.state.lock().await.positions[&Position::key(0, &mint)].clone();
+        assert_eq!((position.entry_amount_sol, position.token_amount), (10.0, 10_000_000));
+
+        bot.sell_token(0, &mint, position.token_amount as f64).await.unwrap();
+        let state = bot.state.lock().await;
+        assert!(state.positions.is_empty());
+        assert!((state.total_profit - 5.0).abs() < 1e-9, "{}", state.total_profit);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn pool_checks_fail_when_the_pool_account_is_absent() {
+        let program_id = Pubkey::new_unique().to_string();
+        let (absent, live, foreign) = (Pubkey::new_unique().to_string(), Pubkey::new_unique().to_string(), Pubkey::new_unique().to_string());
+        let (absent_pool, live_pool, foreign_pool, owner) = (absent.clone(), live.clone(), foreign.clone(), program_id.clone());
+        let cluster = MockServer::start(move |request| {
+            let context = serde_json::json!({ "slot": 1 });
+            let address = &request.body["params"][0];
+            match mock_server::rpc_method(request) {
+                Some("getAccountInfo") if address == absent_pool.as_str() => mock_server::rpc_result(request, serde_json::json!({ "context": context, "value": null })),
+                Some("getAccountInfo") if address == live_pool.as_str() => mock_server::rpc_result(request, serde_json::json!({ "context": context, "value": mock_server::account(&owner, &[0; 752]) })),
+                Some("getAccountInfo") if address == foreign_pool.as_str() => mock_server::rpc_result(request, serde_json::json!({ "context": context, "value": mock_server::account(SOL_MINT, &[0; 752]) })),
+                _ => mock_server::healthy_cluster(request),
+            }
+        }).await;
+        let bot = mock_bot(cluster.url(), 1, |config| config.safety.verify_pool_account = true);
+        let token = Pubkey::new_unique().to_string();
+        let pool = |pool_address: &str| PoolUpdate {
+            pool_address: pool_address.to_string(),
+            token_a: SOL_MINT.to_string(),
+            token_b: token.clone(),
+            liquidity: 100.0,
+            volume_24h: 0.0,
+            timestamp: 0,
+            details: None,
+            program_id: Some(program_id.clone()),
+        };
+
+        assert!(!bot.analyze_pool(&pool(&absent), &token).await.unwrap());
+        assert!(!bot.analyze_pool(&pool(&foreign), &token).await.unwrap());
+        assert!(bot.analyze_pool(&pool(&live), &token).await.unwrap());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+    async fn sell_all_sells_concurrently_up_to_the_limit_and_reports_every_position() {
+        let mints: Vec<String> = (0..5).map(|_| Pubkey::new_unique().to_string()).collect();
+        let unsellable = mints[4].clone();
+        // Sell quotes are slow, so overlapping sells show up as quotes in flight at once
+        let in_flight = Arc::new((AtomicUsize::new(0), AtomicUsize::new(0)));
+        let quotes = in_flight.clone();
+        let cluster = MockServer::start(move |request| {
+            if !request.path.starts_with("/quote") {
+                return mock_server::healthy_cluster(request);
+            }
+            let (current, peak) = &*quotes;
+            peak.fetch_max(current.fetch_add(1, Ordering::SeqCst) + 1, Ordering::SeqCst);
+            std::thread::sleep(std::time::Duration::from_millis(200));
+            current.fetch_sub(1, Ordering::SeqCst);
+            if mock_server::query_param(request, "inputMint").as_deref() == Some(unsellable.as_str()) {
+                return (400, serde_json::json!({ "error": "No routes found" }));
+            }
+            mock_server::healthy_cluster(request)
+        }).await;
+        let bot = mock_bot(cluster.url(), 1, |config| config.trading.sell_all_concurrency = 2);
+        for mint in &mints {
+            bot.open_position(0, mint, 0.1, 1_000_000).await;
+        }
+
+        let results = bot.sell_all().await;
+
+        assert_eq!(in_flight.1.load(Ordering::SeqCst), 2);
+        let mut sold: Vec<&str> = results.iter().map(|(position, _)| position.token_address.as_str()).collect();
+        sold.sort();
+        let mut expected: Vec<&str> = mints.iter().map(String::as_str).collect();
+        expected.sort();
+        assert_eq!(sold, expected);
+        for (position, result) in &results {
+            assert_eq!(result.is_err(), position.token_address == mints[4], "{}: {:?}", position.token_address, result);
+        }
+        let state = bot.state.lock().await;
+        assert_eq!(state.positions.keys().collect::<Vec<_>>(), vec![&Position::key(0, &mints[4])]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn buys_spend_exact_input_and_recovering_the_initial_asks_for_exact_output() {
+        let mint = Pubkey::new_unique().to_string();
+        let token = mint.clone();
+        let cluster = MockServer::start(move |request| {
+            let (status, mut response) = mock_server::healthy_cluster(request);
+            // The token has doubled, so receiving an exact output takes half as many tokens
+            if mock_server::query_param(request, "swapMode").as_deref() == Some("ExactOut")
+                && mock_server::query_param(request, "inputMint").as_deref() == Some(token.as_str())
+            {
+                let amount: u64 = response["outAmount"].as_str().unwrap().parse().unwrap();
+                response["inAmount"] = serde_json::json!((amount / 2).to_string());
+            }
+            (status, response)
+        }).await;
+        let bot = mock_bot(cluster.url(), 1, |_| {});
+
+        bot.snipe_token(0, &mint, 0.1).await.unwrap();
+        bot.recover_initial(0, &mint).await.unwrap();
+
+        let quotes: Vec<(Option<String>, Option<String>, Option<String>)> = cluster.requests().iter()
+            .filter(|request| request.path.starts_with("/quote"))
+            .map(|request| (
+                mock_server::query_param(request, "inputMint"),
+                mock_server::query_param(request, "swapMode"),
+                mock_server::query_param(request, "amount"),
+            ))
+            .collect();
+        let quote = |input_mint: &str, swap_mode: &str| (Some(input_mint.to_string()), Some(swap_mode.to_string()), Some("100000000".to_string()));
+        assert_eq!(quotes, vec![quote(SOL_MINT, "ExactIn"), quote(&mint, "ExactOut")]);
+        // Half the tokens paid back the 0.1 SOL; the other half stays open
+        assert_eq!(bot.state.lock().await.positions[&Position::key(0, &mint)].token_amount, 50_000_000);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn excluded_wallets_are_not_fed() {
+        let cluster = MockServer::start(|request| match mock_server::rpc_method(request) {
+            Some("getFeeForMessage") => mock_server::rpc_result(request, serde_json::json!({ "context": { "slot": 1 }, "value": 5_000 })),
+            Some("getMinimumBalanceForRentExemption") => mock_server::rpc_result(request, serde_json::json!(890_880)),
+            _ => mock_server::healthy_cluster(request),
+        }).await;
+        let bot = mock_bot(cluster.url(), 4, |config| {
+            config.wallets[2] = WalletEntry::Detailed { path: config.wallets[2].path().to_string(), shadow: false, tags: vec!["cold".to_string()] };
+            config.maintenance_wallets.exclude_tags = vec!["cold".to_string()];
+        });
+
+        let funded = bot.distribute_sol(0, FundingAmount::PerWallet(0.5)).await.unwrap();
+
+        assert_eq!(funded.iter().map(|(wallet_index, _)| *wallet_index).collect::<Vec<_>>(), vec![1, 3]);
+        let recipients: Vec<Pubkey> = cluster.requests().iter()
+            .filter(|request| mock_server::rpc_method(request) == Some("sendTransaction"))
+            .flat_map(|request| {
+                let transaction = mock_server::sent_transaction(request);
+                let keys = transaction.message.static_account_keys().to_vec();
+                transaction.message.instructions().iter()
+                    .filter(|instruction| keys[instruction.program_id_index as usize] == solana_sdk::system_program::id())
+                    .map(|instruction| keys[instruction.accounts[1] as usize])
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        assert_eq!(recipients, vec![bot.wallets[1].pubkey(), bot.wallets[3].pubkey()]);
+        assert_eq!(bot.feed_wallet(0, 2, 0.5).await.unwrap_err().to_string(), "Wallet 2 is excluded from maintenance operations by maintenance_wallets");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn trades_count_as_failed_when_they_revert_on_chain() {
+        let reverting = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let revert = reverting.clone();
+        let cluster = MockServer::start(move |request| {
+            if mock_server::rpc_method(request) == Some("getSignatureStatuses") && revert.load(Ordering::SeqCst) {
+                let reverted = serde_json::json!({
+                    "slot": 1, "confirmations": null, "confirmationStatus": "finalized",
+                    "err": { "InstructionError": [2, { "Custom": 6001 }] },
+                    "status": { "Err": { "InstructionError": [2, { "Custom": 6001 }] } },
+                });
+                return mock_server::rpc_result(request, serde_json::json!({ "context": { "slot": 1 }, "value": [reverted] }));
+            }
+            mock_server::healthy_cluster(request)
+        }).await;
+        let bot = mock_bot(cluster.url(), 1, |_| {});
+        let (bought, reverted) = (Pubkey::new_unique().to_string(), Pubkey::new_unique().to_string());
+        let counts = || async {
+            let state = bot.state.lock().await;
+            (state.total_trades, state.successful_trades, state.failed_trades)
+        };
+
+        bot.snipe_token(0, &bought, 0.1).await.unwrap();
+        assert_eq!(counts().await, (1, 1, 0));
+
+        reverting.store(true, Ordering::SeqCst);
+        bot.sell_token(0, &bought, 100_000_000.0).await.unwrap_err();
+        assert_eq!(counts().await, (2, 1, 1));
+        bot.snipe_token(0, &reverted, 0.1).await.unwrap_err();
+        assert_eq!(counts().await, (3, 1, 2));
+
+        let state = bot.state.lock().await;
+        assert!(state.positions.contains_key(&Position::key(0, &bought)));
+        assert!(!state.positions.contains_key(&Position::key(0, &reverted)));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn tokens_with_live_mint_or_freeze_authorities_are_rejected() {
+        let (minter, freezer) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (mintable, freezable, revoked) = (Pubkey::new_unique().to_string(), Pubkey::new_unique().to_string(), Pubkey::new_unique().to_string());
+        let (mintable_mint, freezable_mint) = (mintable.clone(), freezable.clone());
+        let cluster = MockServer::start(move |request| {
+            let address = &request.body["params"][0];
+            let authorities = if address == mintable_mint.as_str() {
+                (Some(minter), None)
+            } else if address == freezable_mint.as_str() {
+                (None, Some(freezer))
+            } else {
+                return mock_server::healthy_cluster(request);
+            };
+            match mock_server::rpc_method(request) {
+                Some("getAccountInfo") => mock_server::rpc_result(request, serde_json::json!({
+                    "context": { "slot": 1 },
+                    "value": mock_server::mint_account_with_authorities(6, authorities.0, authorities.1),
+                })),
+                _ => mock_server::healthy_cluster(request),
+            }
+        }).await;
+        let bot = mock_bot(cluster.url(), 1, |config| {
+            config.safety.require_mint_authority_revoked = true;
+            config.safety.require_freeze_authority_revoked = true;
+        });
+
+        let analysis = bot.analyze_token_detailed(&mintable).await.unwrap();
+        assert_eq!(analysis.failure().unwrap(), format!("mint_authority: still held by {}", minter));
+
+        let analysis = bot.analyze_token_detailed(&freezable).await.unwrap();
+        assert_eq!(analysis.failure().unwrap(), format!("freeze_authority: still held by {}", freezer));
+
+        let analysis = bot.analyze_token_detailed(&revoked).await.unwrap();
+        for name in ["mint_authority", "freeze_authority"] {
+            let check = analysis.checks.iter().find(|check| check.name == name).unwrap_or_else(|| panic!("{}", analysis));
+            assert!(check.passed);
+            assert_eq!(check.detail, "revoked");
+        }
+    }
+}
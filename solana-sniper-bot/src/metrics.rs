@@ -0,0 +1,90 @@
+use anyhow::Result;
+use log::info;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server, StatusCode};
+use std::convert::Infallible;
+use std::fmt::Write;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+use crate::SniperState;
+
+// Upper bounds of the snipe latency histogram buckets, in seconds
+const LATENCY_BUCKETS_SECS: &[f64] = &[0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+
+// Prometheus-style cumulative histogram of how long successful snipes took, from start to confirmation
+#[derive(Debug, Default)]
+pub struct LatencyHistogram {
+    bucket_counts: [u64; LATENCY_BUCKETS_SECS.len()],
+    count: u64,
+    sum_secs: f64,
+}
+
+impl LatencyHistogram {
+    pub fn observe(&mut self, latency: Duration) {
+        let secs = latency.as_secs_f64();
+        for (bucket_count, upper_bound) in self.bucket_counts.iter_mut().zip(LATENCY_BUCKETS_SECS) {
+            if secs <= *upper_bound {
+                *bucket_count += 1;
+            }
+        }
+        self.count += 1;
+        self.sum_secs += secs;
+    }
+}
+
+// Render the bot's counters, gauges and snipe latency in the Prometheus text exposition format
+pub fn render(state: &SniperState, latency: &LatencyHistogram) -> String {
+    let mut out = String::new();
+    let mut metric = |name: &str, kind: &str, help: &str, value: f64| {
+        let _ = writeln!(out, "# HELP {} {}\n# TYPE {} {}\n{} {}", name, help, name, kind, name, value);
+    };
+    metric("sniper_total_trades", "counter", "Real trades attempted", state.total_trades as f64);
+    metric("sniper_successful_trades", "counter", "Real trades confirmed without error", state.successful_trades as f64);
+    metric("sniper_failed_trades", "counter", "Real trades that failed or went unconfirmed", state.failed_trades as f64);
+    metric("sniper_total_profit_sol", "gauge", "Realized profit of real sells in SOL, net of fees", state.total_profit);
+    metric("sniper_active_monitors", "gauge", "DEX monitors currently running", state.active_monitors as f64);
+    metric("sniper_open_positions", "gauge", "Open positions", state.positions.len() as f64);
+
+    let name = "sniper_snipe_latency_seconds";
+    let _ = writeln!(out, "# HELP {} Time from starting a snipe to its confirmation\n# TYPE {} histogram", name, name);
+    for (bucket_count, upper_bound) in latency.bucket_counts.iter().zip(LATENCY_BUCKETS_SECS) {
+        let _ = writeln!(out, "{}_bucket{{le=\"{}\"}} {}", name, upper_bound, bucket_count);
+    }
+    let _ = writeln!(out, "{}_bucket{{le=\"+Inf\"}} {}", name, latency.count);
+    let _ = writeln!(out, "{}_sum {}", name, latency.sum_secs);
+    let _ = writeln!(out, "{}_count {}", name, latency.count);
+    out
+}
+
+// Serve GET /metrics on the given port until the returned future is dropped
+pub async fn serve(port: u16, state: Arc<Mutex<SniperState>>, latency: Arc<std::sync::Mutex<LatencyHistogram>>) -> Result<()> {
+    let make_service = make_service_fn(move |_| {
+        let (state, latency) = (state.clone(), latency.clone());
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request: Request<Body>| {
+                let (state, latency) = (state.clone(), latency.clone());
+                async move {
+                    if request.uri().path() != "/metrics" {
+                        let mut response = Response::new(Body::from("Not found\n"));
+                        *response.status_mut() = StatusCode::NOT_FOUND;
+                        return Ok::<_, Infallible>(response);
+                    }
+                    let state = state.lock().await.clone();
+                    let body = render(&state, &latency.lock().unwrap());
+                    let mut response = Response::new(Body::from(body));
+                    response.headers_mut().insert("Content-Type", "text/plain; version=0.0.4".parse().unwrap());
+                    Ok(response)
+                }
+            }))
+        }
+    });
+
+    let addr = SocketAddr::from(([0, 0, 0, 0], port));
+    let server = Server::try_bind(&addr)?.serve(make_service);
+    info!("Serving Prometheus metrics on http://{}/metrics", addr);
+    server.await?;
+    Ok(())
+}
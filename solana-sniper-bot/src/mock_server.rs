@@ -0,0 +1,239 @@
+use base64::Engine;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server};
+use serde_json::json;
+use solana_sdk::{
+    compute_budget::ComputeBudgetInstruction,
+    hash::Hash,
+    message::{Message, VersionedMessage},
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::Signature,
+    system_instruction,
+    transaction::VersionedTransaction,
+};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use crate::token_accounts::TOKEN_PROGRAM_ID;
+
+// SPL Token mint layout: mint authority and supply, decimals and the initialized flag, then the freeze authority
+const MINT_LEN: usize = 82;
+const MINT_AUTHORITY_OFFSET: usize = 0;
+const MINT_DECIMALS_OFFSET: usize = 44;
+const FREEZE_AUTHORITY_OFFSET: usize = 46;
+
+// A request received by a MockServer
+#[derive(Debug, Clone)]
+pub struct MockRequest {
+    pub method: String,
+    // Path and query, e.g. "/quote?inputMint=..."
+    pub path: String,
+    // The JSON body, or Null when there was none
+    pub body: serde_json::Value,
+}
+
+type Handler = dyn Fn(&MockRequest) -> (u16, serde_json::Value) + Send + Sync;
+
+// A local HTTP server standing in for Telegram, Jupiter, Birdeye or an RPC node in tests. Every request is
+// answered with the handler's status and JSON body, and recorded for assertions.
+pub struct MockServer {
+    url: String,
+    requests: Arc<Mutex<Vec<MockRequest>>>,
+}
+
+impl MockServer {
+    pub async fn start(handler: impl Fn(&MockRequest) -> (u16, serde_json::Value) + Send + Sync + 'static) -> Self {
+        let handler: Arc<Handler> = Arc::new(handler);
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = requests.clone();
+        let make_service = make_service_fn(move |_| {
+            let handler = handler.clone();
+            let recorded = recorded.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request: Request<Body>| {
+                    let handler = handler.clone();
+                    let recorded = recorded.clone();
+                    async move {
+                        let (parts, body) = request.into_parts();
+                        let body = hyper::body::to_bytes(body).await.unwrap_or_default();
+                        let request = MockRequest {
+                            method: parts.method.to_string(),
+                            path: parts.uri.path_and_query().map(|path| path.to_string()).unwrap_or_default(),
+                            body: serde_json::from_slice(&body).unwrap_or(serde_json::Value::Null),
+                        };
+                        let (status, response) = handler(&request);
+                        recorded.lock().unwrap().push(request);
+                        Ok::<_, Infallible>(Response::builder()
+                            .status(status)
+                            .header("Content-Type", "application/json")
+                            .body(Body::from(response.to_string()))
+                            .unwrap())
+                    }
+                }))
+            }
+        });
+        let server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(make_service);
+        let url = format!("http://{}", server.local_addr());
+        tokio::spawn(server);
+        MockServer { url, requests }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+// A JSON-RPC success response to request
+pub fn rpc_result(request: &MockRequest, result: serde_json::Value) -> (u16, serde_json::Value) {
+    (200, json!({ "jsonrpc": "2.0", "id": request.body["id"], "result": result }))
+}
+
+// The JSON-RPC method called by request, if it is an RPC call
+pub fn rpc_method(request: &MockRequest) -> Option<&str> {
+    request.body["method"].as_str()
+}
+
+// The transaction submitted by a sendTransaction call
+pub fn sent_transaction(request: &MockRequest) -> VersionedTransaction {
+    let encoded = request.body["params"][0].as_str().expect("sendTransaction carries a transaction");
+    bincode::deserialize(&base64::engine::general_purpose::STANDARD.decode(encoded).unwrap()).unwrap()
+}
+
+// A query parameter of request
+pub fn query_param(request: &MockRequest, name: &str) -> Option<String> {
+    let url = url::Url::parse(&format!("http://mock{}", request.path)).ok()?;
+    url.query_pairs().find(|(key, _)| key == name).map(|(_, value)| value.into_owned())
+}
+
+// How a healthy cluster, Jupiter and Birdeye answer, for tests that only need to change part of it:
+// - every wallet holds 10 SOL and no token accounts, and every other account is a 6-decimal mint
+// - every sent transaction confirms at once
+// - Jupiter quotes at par with no price impact, and its swaps set a compute budget but no token account
+// - Birdeye knows every token, priced at 0.001 and on Jupiter's strict list
+pub fn healthy_cluster(request: &MockRequest) -> (u16, serde_json::Value) {
+    if request.path.starts_with("/quote") {
+        let amount = query_param(request, "amount").unwrap_or_default();
+        return (200, json!({
+            "inputMint": query_param(request, "inputMint"),
+            "outputMint": query_param(request, "outputMint"),
+            "inAmount": amount,
+            "outAmount": amount,
+            "priceImpactPct": "0",
+            "routePlan": [{ "swapInfo": { "label": "Raydium", "feeAmount": "0" } }],
+        }));
+    }
+    if request.path.starts_with("/swap") {
+        let payer = Pubkey::from_str(request.body["userPublicKey"].as_str().unwrap()).unwrap();
+        let instructions = [
+            ComputeBudgetInstruction::set_compute_unit_limit(150_000),
+            system_instruction::transfer(&payer, &payer, 1),
+        ];
+        let transaction = VersionedTransaction {
+            signatures: vec![Signature::default()],
+            message: VersionedMessage::Legacy(Message::new_with_blockhash(&instructions, Some(&payer), &Hash::new_unique())),
+        };
+        let encoded = base64::engine::general_purpose::STANDARD.encode(bincode::serialize(&transaction).unwrap());
+        return (200, json!({ "swapTransaction": encoded }));
+    }
+    if request.path.starts_with("/defi/token_overview") {
+        return (200, json!({ "success": true, "data": { "symbol": "MOCK", "name": "Mock Token", "decimals": 6, "price": 0.001, "holder": 100 } }));
+    }
+    if request.path.starts_with("/defi/token_security") {
+        return (200, json!({ "success": true, "data": { "jupStrictList": true } }));
+    }
+    if request.path.starts_with("/defi/") {
+        return (200, json!({ "success": true, "data": {} }));
+    }
+
+    let context = json!({ "slot": 1 });
+    let result = match rpc_method(request) {
+        Some("getVersion") => json!({ "solana-core": "1.18.26", "feature-set": 0 }),
+        Some("getBalance") => json!({ "context": context, "value": 10 * LAMPORTS_PER_SOL }),
+        Some("getAccountInfo") => json!({ "context": context, "value": mint_account(6) }),
+        Some("getTokenAccountsByOwner") => json!({ "context": context, "value": [] }),
+        Some("getLatestBlockhash") => json!({ "context": context, "value": { "blockhash": Hash::new_unique().to_string(), "lastValidBlockHeight": 1_000 } }),
+        Some("isBlockhashValid") => json!({ "context": context, "value": true }),
+        Some("sendTransaction") => json!(sent_transaction(request).signatures[0].to_string()),
+        Some("getSignatureStatuses") => {
+            let confirmed = json!({ "slot": 1, "confirmations": null, "err": null, "status": { "Ok": null }, "confirmationStatus": "finalized" });
+            let statuses = request.body["params"][0].as_array().map_or(0, |signatures| signatures.len());
+            json!({ "context": context, "value": vec![confirmed; statuses] })
+        }
+        method => return (200, json!({
+            "jsonrpc": "2.0",
+            "id": request.body["id"],
+            "error": { "code": -32601, "message": format!("Method not found: {:?}", method) },
+        })),
+    };
+    rpc_result(request, result)
+}
+
+// An initialized SPL Token mint with no mint or freeze authority, as getAccountInfo returns it
+pub fn mint_account(decimals: u8) -> serde_json::Value {
+    mint_account_with_authorities(decimals, None, None)
+}
+
+// An initialized SPL Token mint whose mint and freeze authorities are still held by the given keys
+pub fn mint_account_with_authorities(decimals: u8, mint_authority: Option<Pubkey>, freeze_authority: Option<Pubkey>) -> serde_json::Value {
+    account(TOKEN_PROGRAM_ID, &mint_data(decimals, mint_authority, freeze_authority))
+}
+
+// Raw SPL Token mint data; each authority is a COption<Pubkey>, a 4-byte tag followed by the key
+pub fn mint_data(decimals: u8, mint_authority: Option<Pubkey>, freeze_authority: Option<Pubkey>) -> Vec<u8> {
+    let mut data = vec![0; MINT_LEN];
+    for (offset, authority) in [(MINT_AUTHORITY_OFFSET, mint_authority), (FREEZE_AUTHORITY_OFFSET, freeze_authority)] {
+        if let Some(authority) = authority {
+            data[offset] = 1;
+            data[offset + 4..offset + 36].copy_from_slice(authority.as_ref());
+        }
+    }
+    data[MINT_DECIMALS_OFFSET] = decimals;
+    data[MINT_DECIMALS_OFFSET + 1] = 1;
+    data
+}
+
+pub fn account(owner: &str, data: &[u8]) -> serde_json::Value {
+    json!({
+        "data": [base64::engine::general_purpose::STANDARD.encode(data), "base64"],
+        "executable": false,
+        "lamports": 1_461_600,
+        "owner": owner,
+        "rentEpoch": 0,
+        "space": data.len(),
+    })
+}
+
+// A jsonParsed SPL Token account holding amount (raw, 6 decimals) of mint, as getTokenAccountsByOwner returns it
+pub fn token_account(owner: &Pubkey, mint: &str, amount: u64) -> serde_json::Value {
+    json!({
+        "pubkey": Pubkey::new_unique().to_string(),
+        "account": {
+            "data": {
+                "program": "spl-token",
+                "parsed": {
+                    "type": "account",
+                    "info": {
+                        "mint": mint,
+                        "owner": owner.to_string(),
+                        "state": "initialized",
+                        "isNative": false,
+                        "tokenAmount": { "amount": amount.to_string(), "decimals": 6, "uiAmount": amount as f64 / 1e6, "uiAmountString": (amount as f64 / 1e6).to_string() },
+                    },
+                },
+                "space": 165,
+            },
+            "executable": false,
+            "lamports": 2_039_280,
+            "owner": TOKEN_PROGRAM_ID,
+            "rentEpoch": 0,
+            "space": 165,
+        },
+    })
+}
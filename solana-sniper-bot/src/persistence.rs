@@ -0,0 +1,213 @@
+use anyhow::Result;
+use log::{debug, warn};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PersistenceConfig {
+    pub state_file: Option<String>,
+    pub trade_log_file: Option<String>,
+    pub trade_log_buffer_size: usize,
+    pub shutdown_flush_timeout_ms: u64,
+    #[serde(default)]
+    pub pid_file: Option<String>,
+    #[serde(default)]
+    pub closed_positions_file: Option<String>,
+    // Pools already announced on Telegram, kept so a restart doesn't announce them again
+    #[serde(default)]
+    pub notified_pools_file: Option<String>,
+    #[serde(default = "default_notified_pools_ttl_secs")]
+    pub notified_pools_ttl_secs: u64,
+}
+
+fn default_notified_pools_ttl_secs() -> u64 {
+    3600
+}
+
+impl Default for PersistenceConfig {
+    fn default() -> Self {
+        PersistenceConfig {
+            state_file: None,
+            trade_log_file: None,
+            trade_log_buffer_size: 20,
+            shutdown_flush_timeout_ms: 5000,
+            pid_file: None,
+            closed_positions_file: None,
+            notified_pools_file: None,
+            notified_pools_ttl_secs: default_notified_pools_ttl_secs(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TradeRecord {
+    #[serde(default)]
+    pub run_id: String,
+    #[serde(default)]
+    pub trace_id: String,
+    pub timestamp: u64,
+    pub wallet_index: usize,
+    pub token_address: String,
+    pub side: String,
+    pub amount: f64,
+    pub signature: String,
+}
+
+// Buffers trade records in memory and appends them to a JSON-lines file
+pub struct TradeLog {
+    path: Option<String>,
+    buffer_size: usize,
+    pending: Vec<TradeRecord>,
+}
+
+impl TradeLog {
+    pub fn new(path: Option<String>, buffer_size: usize) -> Self {
+        TradeLog {
+            path,
+            buffer_size,
+            pending: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, record: TradeRecord) -> Result<()> {
+        if self.path.is_none() {
+            return Ok(());
+        }
+        self.pending.push(record);
+        if self.pending.len() >= self.buffer_size {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        let path = match &self.path {
+            Some(path) if !self.pending.is_empty() => path,
+            _ => return Ok(()),
+        };
+
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        for record in &self.pending {
+            writeln!(file, "{}", serde_json::to_string(record)?)?;
+        }
+        file.flush()?;
+        debug!("Flushed {} trade records to {}", self.pending.len(), path);
+        self.pending.clear();
+        Ok(())
+    }
+}
+
+// When each recently announced pool was announced, optionally saved to a file after every announcement
+pub struct NotifiedPools {
+    path: Option<String>,
+    ttl_secs: u64,
+    notified_at: HashMap<String, u64>,
+}
+
+impl NotifiedPools {
+    // Reload announcements still within the TTL; an unreadable file only costs possible repeats
+    pub fn load(path: Option<String>, ttl_secs: u64) -> Self {
+        let mut notified_at: HashMap<String, u64> = match &path {
+            Some(path) => read_json_file(path).unwrap_or_else(|e| {
+                warn!("Failed to read notified pools from {}: {}", path, e);
+                None
+            }).unwrap_or_default(),
+            None => HashMap::new(),
+        };
+        let now = chrono::Utc::now().timestamp() as u64;
+        notified_at.retain(|_, at| now.saturating_sub(*at) < ttl_secs);
+        NotifiedPools { path, ttl_secs, notified_at }
+    }
+
+    // Record an announcement, returning false if the pool was already announced within the TTL
+    pub fn insert(&mut self, pool_address: &str) -> Result<bool> {
+        let now = chrono::Utc::now().timestamp() as u64;
+        if self.notified_at.get(pool_address).is_some_and(|at| now.saturating_sub(*at) < self.ttl_secs) {
+            return Ok(false);
+        }
+        self.notified_at.retain(|_, at| now.saturating_sub(*at) < self.ttl_secs);
+        self.notified_at.insert(pool_address.to_string(), now);
+        if let Some(path) = &self.path {
+            write_json_file(path, &self.notified_at)?;
+        }
+        Ok(true)
+    }
+}
+
+// Write a snapshot atomically so a crash mid-write never leaves a truncated file
+pub fn write_json_file<T: Serialize>(path: &str, value: &T) -> Result<()> {
+    let tmp_path = format!("{}.tmp", path);
+    std::fs::write(&tmp_path, serde_json::to_string_pretty(value)?)?;
+    std::fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+// Read a snapshot written by write_json_file, or None if it doesn't exist yet
+pub fn read_json_file<T: DeserializeOwned>(path: &str) -> Result<Option<T>> {
+    match std::fs::read_to_string(path) {
+        Ok(data) => Ok(Some(serde_json::from_str(&data)?)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+// Append one record to a JSON-lines file, creating it if needed
+pub fn append_json_line<T: Serialize>(path: &str, value: &T) -> Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(value)?)?;
+    Ok(())
+}
+
+// All records of a JSON-lines file, or none if it doesn't exist yet
+pub fn read_json_lines<T: DeserializeOwned>(path: &str) -> Result<Vec<T>> {
+    let data = match std::fs::read_to_string(path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    data.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| Ok(serde_json::from_str(line)?))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_path(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("sniper-persistence-{}-{}.json", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn pools_announced_before_a_reload_are_not_announced_again_within_the_ttl() {
+        let path = scratch_path("notified-reload");
+        assert!(NotifiedPools::load(Some(path.clone()), 3_600).insert("Pool111").unwrap());
+
+        let mut reloaded = NotifiedPools::load(Some(path.clone()), 3_600);
+
+        assert!(!reloaded.insert("Pool111").unwrap());
+        assert!(reloaded.insert("Pool222").unwrap());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn pools_are_announced_again_once_the_ttl_has_passed() {
+        let path = scratch_path("notified-expired");
+        let now = chrono::Utc::now().timestamp() as u64;
+        write_json_file(&path, &HashMap::from([
+            ("Expired111".to_string(), now - 3_601),
+            ("Recent111".to_string(), now - 60),
+        ])).unwrap();
+
+        let mut reloaded = NotifiedPools::load(Some(path.clone()), 3_600);
+
+        assert!(reloaded.insert("Expired111").unwrap());
+        assert!(!reloaded.insert("Recent111").unwrap());
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,261 @@
+use serde::{Deserialize, Serialize};
+
+// An open holding from a snipe, enough to size and attribute the exit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Position {
+    pub wallet_index: usize,
+    pub token_address: String,
+    pub entry_amount_sol: f64,
+    pub token_amount: u64,
+    // Price at entry as reported by the metadata feed; 0.0 until the first price is known
+    pub entry_price: f64,
+    pub opened_at: u64,
+    // Pool the token was sniped from, when it came from a detected pool
+    #[serde(default)]
+    pub pool_address: Option<String>,
+    #[serde(default)]
+    pub pool_creator: Option<String>,
+    // Newest pool transaction already checked for liquidity removal
+    #[serde(default)]
+    pub last_pool_signature: Option<String>,
+    // Strategy that owns the wallet holding this position, if strategies are configured
+    #[serde(default)]
+    pub strategy: Option<String>,
+    // Latest price seen by the exit monitor or a status check; 0.0 until one has run
+    #[serde(default)]
+    pub last_price: f64,
+}
+
+impl Position {
+    pub fn key(wallet_index: usize, token_address: &str) -> String {
+        format!("{}:{}", wallet_index, token_address)
+    }
+
+    // Percentage change from entry, or None while the entry price is unknown
+    pub fn change_percent(&self, current_price: f64) -> Option<f64> {
+        if self.entry_price <= 0.0 {
+            return None;
+        }
+        Some((current_price - self.entry_price) / self.entry_price * 100.0)
+    }
+
+    // Percentage return if the whole position sold for proceeds_sol, or None without a cost basis
+    pub fn return_percent(&self, proceeds_sol: f64) -> Option<f64> {
+        if self.entry_amount_sol <= 0.0 {
+            return None;
+        }
+        Some((proceeds_sol - self.entry_amount_sol) / self.entry_amount_sol * 100.0)
+    }
+
+    // Unrealized PnL in SOL at the given price, scaling the cost basis by the move since entry
+    pub fn unrealized_pnl(&self, current_price: f64) -> Option<f64> {
+        Some(self.entry_amount_sol * self.change_percent(current_price)? / 100.0)
+    }
+
+    // Take a sold lot out of the position, with the proportional share of the cost basis, and return it closed
+    pub fn close(&mut self, token_amount: u64, proceeds_sol: f64, fees_sol: f64, closed_at: u64, signature: &str) -> ClosedPosition {
+        let sold = token_amount.min(self.token_amount);
+        let cost_sol = if self.token_amount > 0 {
+            self.entry_amount_sol * sold as f64 / self.token_amount as f64
+        } else {
+            self.entry_amount_sol
+        };
+        self.entry_amount_sol -= cost_sol;
+        self.token_amount -= sold;
+        ClosedPosition {
+            wallet_index: self.wallet_index,
+            token_address: self.token_address.clone(),
+            opened_at: self.opened_at,
+            closed_at,
+            token_amount: sold,
+            sol_in: cost_sol,
+            sol_out: proceeds_sol,
+            fees_sol,
+            realized_pnl: proceeds_sol - cost_sol - fees_sol,
+            signature: signature.to_string(),
+            strategy: self.strategy.clone(),
+            entry_price: self.entry_price,
+            exit_price: self.last_price,
+        }
+    }
+
+    // Fold another buy of the same token into this position at a token-weighted average entry price
+    pub fn add(&mut self, amount_sol: f64, token_amount: u64, entry_price: f64) {
+        let total_tokens = self.token_amount + token_amount;
+        if total_tokens > 0 && self.entry_price > 0.0 && entry_price > 0.0 {
+            self.entry_price = (self.entry_price * self.token_amount as f64 + entry_price * token_amount as f64)
+                / total_tokens as f64;
+        } else if self.entry_price <= 0.0 {
+            self.entry_price = entry_price;
+        }
+        self.entry_amount_sol += amount_sol;
+        self.token_amount = total_tokens;
+    }
+}
+
+// A sold lot of a position with its cost basis and proceeds, for PnL reporting
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClosedPosition {
+    pub wallet_index: usize,
+    pub token_address: String,
+    pub opened_at: u64,
+    pub closed_at: u64,
+    pub token_amount: u64,
+    pub sol_in: f64,
+    pub sol_out: f64,
+    pub fees_sol: f64,
+    pub realized_pnl: f64,
+    pub signature: String,
+    #[serde(default)]
+    pub strategy: Option<String>,
+    // Market prices at entry and at the sell, or 0.0 where the metadata feed had none
+    #[serde(default)]
+    pub entry_price: f64,
+    #[serde(default)]
+    pub exit_price: f64,
+}
+
+impl ClosedPosition {
+    // Return from the market price move alone, before fees and slippage
+    pub fn gross_return_percent(&self) -> Option<f64> {
+        if self.entry_price <= 0.0 || self.exit_price <= 0.0 {
+            return None;
+        }
+        Some((self.exit_price - self.entry_price) / self.entry_price * 100.0)
+    }
+
+    // Return actually realized on the SOL put in, after fees and slippage
+    pub fn net_return_percent(&self) -> Option<f64> {
+        if self.sol_in <= 0.0 {
+            return None;
+        }
+        Some(self.realized_pnl / self.sol_in * 100.0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExitReason {
+    TakeProfit { change_percent: f64 },
+    StopLoss { change_percent: f64 },
+    CreatorLiquidityRemoval { signature: String },
+}
+
+impl std::fmt::Display for ExitReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExitReason::TakeProfit { change_percent } => write!(f, "take-profit hit at {:+.2}%", change_percent),
+            ExitReason::StopLoss { change_percent } => write!(f, "stop-loss hit at {:+.2}%", change_percent),
+            ExitReason::CreatorLiquidityRemoval { signature } => write!(f, "creator removed liquidity in {}", signature),
+        }
+    }
+}
+
+// Whether the current price crosses either configured threshold for this position
+pub fn exit_signal(position: &Position, current_price: f64, take_profit_percent: Option<f64>, stop_loss_percent: Option<f64>) -> Option<ExitReason> {
+    let change_percent = position.change_percent(current_price)?;
+    if let Some(take_profit_percent) = take_profit_percent {
+        if change_percent >= take_profit_percent {
+            return Some(ExitReason::TakeProfit { change_percent });
+        }
+    }
+    if let Some(stop_loss_percent) = stop_loss_percent {
+        if change_percent <= -stop_loss_percent {
+            return Some(ExitReason::StopLoss { change_percent });
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(entry_amount_sol: f64, token_amount: u64) -> Position {
+        Position {
+            wallet_index: 0,
+            token_address: "mint".to_string(),
+            entry_amount_sol,
+            token_amount,
+            entry_price: 0.001,
+            opened_at: 0,
+            pool_address: None,
+            pool_creator: None,
+            last_pool_signature: None,
+            strategy: None,
+            last_price: 0.0015,
+        }
+    }
+
+    #[test]
+    fn closing_a_position_realizes_proceeds_minus_cost_and_fees() {
+        let mut position = position(1.0, 1_000);
+        let closed = position.close(1_000, 1.5, 0.01, 60, "sig");
+
+        assert_eq!((closed.sol_in, closed.sol_out, closed.fees_sol), (1.0, 1.5, 0.01));
+        assert!((closed.realized_pnl - 0.49).abs() < 1e-9);
+        assert_eq!(closed.token_amount, 1_000);
+        assert_eq!((closed.entry_price, closed.exit_price), (0.001, 0.0015));
+        assert_eq!(position.token_amount, 0);
+        assert_eq!(position.entry_amount_sol, 0.0);
+    }
+
+    #[test]
+    fn partial_close_takes_a_proportional_cost_basis() {
+        let mut position = position(1.0, 1_000);
+        let closed = position.close(250, 0.2, 0.0, 60, "sig");
+
+        assert_eq!(closed.sol_in, 0.25);
+        assert!((closed.realized_pnl + 0.05).abs() < 1e-9);
+        assert_eq!(position.token_amount, 750);
+        assert_eq!(position.entry_amount_sol, 0.75);
+    }
+
+    #[test]
+    fn closing_more_than_held_sells_only_what_is_held() {
+        let mut position = position(1.0, 1_000);
+        let closed = position.close(5_000, 1.0, 0.0, 60, "sig");
+        assert_eq!(closed.token_amount, 1_000);
+        assert_eq!(closed.sol_in, 1.0);
+    }
+
+    #[test]
+    fn unrealized_pnl_scales_the_cost_basis_by_the_price_move() {
+        let position = position(1.0, 1_000);
+        assert!((position.unrealized_pnl(0.0015).unwrap() - 0.5).abs() < 1e-9);
+        assert!((position.unrealized_pnl(0.0008).unwrap() + 0.2).abs() < 1e-9);
+        assert_eq!(Position { entry_price: 0.0, ..position }.unrealized_pnl(0.0015), None);
+    }
+
+    fn closed_position(sol_in: f64, sol_out: f64, fees_sol: f64, entry_price: f64, exit_price: f64) -> ClosedPosition {
+        ClosedPosition {
+            wallet_index: 0,
+            token_address: "mint".to_string(),
+            opened_at: 0,
+            closed_at: 60,
+            token_amount: 1_000,
+            sol_in,
+            sol_out,
+            fees_sol,
+            realized_pnl: sol_out - sol_in - fees_sol,
+            signature: "sig".to_string(),
+            strategy: None,
+            entry_price,
+            exit_price,
+        }
+    }
+
+    #[test]
+    fn gross_return_is_the_price_move_and_net_return_is_after_costs() {
+        // Price up 50%, but slippage and 0.05 SOL of fees leave 1.0 SOL in, 1.4 SOL out
+        let closed = closed_position(1.0, 1.4, 0.05, 0.002, 0.003);
+        assert_eq!(closed.gross_return_percent(), Some(50.0));
+        assert!((closed.net_return_percent().unwrap() - 35.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn returns_are_unknown_without_prices_or_cost_basis() {
+        assert_eq!(closed_position(1.0, 1.4, 0.05, 0.0, 0.003).gross_return_percent(), None);
+        assert_eq!(closed_position(1.0, 1.4, 0.05, 0.002, 0.0).gross_return_percent(), None);
+        assert_eq!(closed_position(0.0, 1.4, 0.05, 0.002, 0.003).net_return_percent(), None);
+    }
+}
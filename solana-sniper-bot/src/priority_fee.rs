@@ -0,0 +1,47 @@
+use anyhow::{anyhow, Result};
+use solana_sdk::{compute_budget::{self, ComputeBudgetInstruction}, instruction::Instruction};
+
+// Compute unit prices for the named gas_priority tiers, in micro-lamports
+const LOW_COMPUTE_UNIT_PRICE: u64 = 10_000;
+const MEDIUM_COMPUTE_UNIT_PRICE: u64 = 100_000;
+const HIGH_COMPUTE_UNIT_PRICE: u64 = 1_000_000;
+// A SOL transfer plus the two compute budget instructions fits well under this
+pub const TRANSFER_COMPUTE_UNIT_LIMIT: u32 = 1_000;
+// A swap that doesn't say how much it needs; Jupiter simulates its swaps and asks for less
+pub const SWAP_COMPUTE_UNIT_LIMIT: u32 = 400_000;
+// Creating an associated token account, with headroom for Token-2022 mints
+pub const CREATE_TOKEN_ACCOUNT_COMPUTE_UNIT_LIMIT: u32 = 40_000;
+// SetComputeUnitLimit's tag in the compute budget program's instruction data, followed by a u32 limit
+const SET_COMPUTE_UNIT_LIMIT: u8 = 2;
+
+// Compute unit price for a gas_priority tier, with an explicit price taking precedence
+pub fn compute_unit_price(gas_priority: &str, override_micro_lamports: Option<u64>) -> Result<u64> {
+    if let Some(price) = override_micro_lamports {
+        return Ok(price);
+    }
+    match gas_priority.to_lowercase().as_str() {
+        "low" => Ok(LOW_COMPUTE_UNIT_PRICE),
+        "medium" => Ok(MEDIUM_COMPUTE_UNIT_PRICE),
+        "high" => Ok(HIGH_COMPUTE_UNIT_PRICE),
+        other => Err(anyhow!("Unknown gas_priority {:?}, expected low, medium or high", other)),
+    }
+}
+
+// Instructions to prepend to a transaction so it bids the given priority fee
+pub fn compute_budget_instructions(compute_unit_price: u64, compute_unit_limit: u32) -> Vec<Instruction> {
+    vec![
+        ComputeBudgetInstruction::set_compute_unit_limit(compute_unit_limit),
+        ComputeBudgetInstruction::set_compute_unit_price(compute_unit_price),
+    ]
+}
+
+// The unit limit a SetComputeUnitLimit instruction requests, None for any other instruction
+pub fn requested_compute_unit_limit(instruction: &Instruction) -> Option<u32> {
+    if instruction.program_id != compute_budget::id() {
+        return None;
+    }
+    match instruction.data.split_first()? {
+        (&SET_COMPUTE_UNIT_LIMIT, limit) => Some(u32::from_le_bytes(limit.get(..4)?.try_into().ok()?)),
+        _ => None,
+    }
+}
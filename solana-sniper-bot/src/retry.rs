@@ -0,0 +1,88 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::error;
+
+// Exponential backoff shared by anything that retries transient failures
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub base_delay_ms: u64,
+    pub max_delay_ms: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 3,
+            base_delay_ms: 200,
+            max_delay_ms: 5000,
+        }
+    }
+}
+
+impl RetryPolicy {
+    // Delay before the given retry attempt (1-based), doubling each time up to max_delay_ms
+    pub fn delay_for(&self, attempt: u32) -> Duration {
+        let factor = 2_u64.saturating_pow(attempt.saturating_sub(1));
+        Duration::from_millis(self.base_delay_ms.saturating_mul(factor).min(self.max_delay_ms))
+    }
+
+    // delay_for with its upper half randomized, so clients that failed together don't retry together
+    pub fn jittered_delay_for(&self, attempt: u32) -> Duration {
+        let delay_ms = self.delay_for(attempt).as_millis() as u64;
+        Duration::from_millis(delay_ms / 2 + rand::thread_rng().gen_range(0..=delay_ms - delay_ms / 2))
+    }
+}
+
+// Lowercase fragments of errors where the trade never landed and a fresh quote and blockhash may succeed:
+// an expired or unknown blockhash, a node that is behind, or a network or upstream hiccup
+const TRANSIENT_TRADE_ERRORS: &[&str] = &[
+    "blockhash not found",
+    "block height exceeded",
+    "unable to confirm transaction",
+    "node is behind",
+    "node is unhealthy",
+    "error sending request",
+    "connection reset",
+    "connection refused",
+    "timed out",
+    "429 too many requests",
+    "500 internal server error",
+    "502 bad gateway",
+    "503 service unavailable",
+    "504 gateway timeout",
+];
+
+// Lowercase fragments of a swap rejected because the price moved past its slippage limit: Jupiter's
+// SlippageToleranceExceeded (custom error 6001) and Raydium's "exceeds desired slippage limit"
+const SLIPPAGE_ERRORS: &[&str] = &[
+    "custom program error: 0x1771",
+    "slippage",
+];
+
+// Whether a failed trade ran out of slippage, so a wider quote might fill
+pub fn is_slippage_error(error: &anyhow::Error) -> bool {
+    let message = format!("{:#}", error).to_lowercase();
+    SLIPPAGE_ERRORS.iter().any(|fragment| message.contains(fragment))
+}
+
+// Whether an error message names one of the transient failures above
+pub fn is_transient_message(message: &str) -> bool {
+    let message = message.to_lowercase();
+    TRANSIENT_TRADE_ERRORS.iter().any(|fragment| message.contains(fragment))
+}
+
+// Whether a failed trade is worth retrying. Logical rejections (safety checks, balance, price impact, on-chain
+// failures) are not, and neither is a transaction that merely went unconfirmed, since it may still land.
+// Typed errors decide for themselves; anything else is judged by its network cause or message.
+pub fn is_transient_trade_error(error: &anyhow::Error) -> bool {
+    if let Some(sniper_error) = error::sniper_error(error) {
+        return sniper_error.is_transient();
+    }
+    let network_error = error.chain()
+        .filter_map(|cause| cause.downcast_ref::<reqwest::Error>())
+        .any(|e| e.is_timeout() || e.is_connect());
+    network_error || is_transient_message(&format!("{:#}", error))
+}
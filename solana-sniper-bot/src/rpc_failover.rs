@@ -0,0 +1,187 @@
+use anyhow::Result;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind},
+    rpc_client::RpcClient,
+    rpc_request::RpcError,
+};
+use solana_sdk::commitment_config::CommitmentConfig;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use crate::rpc_lag;
+
+// JSON-RPC error code of a node that is behind or otherwise unhealthy
+const NODE_UNHEALTHY: i64 = -32005;
+
+struct Endpoint {
+    client: Arc<RpcClient>,
+    consecutive_failures: AtomicU64,
+    requests: AtomicU64,
+    failures: AtomicU64,
+}
+
+// How one endpoint has been doing, for logs and status
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EndpointHealth {
+    pub endpoint: String,
+    pub active: bool,
+    pub consecutive_failures: u64,
+    pub requests: u64,
+    pub failures: u64,
+}
+
+// The configured RPC endpoints, one active at a time. Calls made through `call` are counted per endpoint,
+// and after `failure_threshold` consecutive endpoint errors the next healthy endpoint takes over.
+pub struct RpcFailover {
+    endpoints: Vec<Endpoint>,
+    active: AtomicUsize,
+    failure_threshold: u64,
+}
+
+impl RpcFailover {
+    pub fn new(rpc_urls: &[String], commitment: CommitmentConfig, failure_threshold: u32) -> Self {
+        RpcFailover {
+            endpoints: rpc_urls.iter().map(|rpc_url| Endpoint {
+                client: Arc::new(RpcClient::new_with_commitment(rpc_url.clone(), commitment)),
+                consecutive_failures: AtomicU64::new(0),
+                requests: AtomicU64::new(0),
+                failures: AtomicU64::new(0),
+            }).collect(),
+            active: AtomicUsize::new(0),
+            failure_threshold: failure_threshold as u64,
+        }
+    }
+
+    // The active endpoint's client, for its url and commitment. Requests go through `call` so they can fail over.
+    pub fn client(&self) -> Arc<RpcClient> {
+        self.endpoints[self.active.load(Ordering::Relaxed)].client.clone()
+    }
+
+    // Every endpoint's client, active or not
+    pub fn clients(&self) -> Vec<Arc<RpcClient>> {
+        self.endpoints.iter().map(|endpoint| endpoint.client.clone()).collect()
+    }
+
+    // Run a request against the active endpoint. An endpoint error that makes it fail over is retried once on
+    // each endpoint it fails over to, so callers only see it when no endpoint could serve the request.
+    pub fn call<T>(&self, request: impl Fn(&RpcClient) -> Result<T>) -> Result<T> {
+        let mut tried = 1;
+        loop {
+            let index = self.active.load(Ordering::Relaxed);
+            let endpoint = &self.endpoints[index];
+            endpoint.requests.fetch_add(1, Ordering::Relaxed);
+            let result = request(&endpoint.client);
+            match &result {
+                Ok(_) => endpoint.consecutive_failures.store(0, Ordering::Relaxed),
+                Err(e) if is_endpoint_error(e) => {
+                    endpoint.failures.fetch_add(1, Ordering::Relaxed);
+                    let consecutive_failures = endpoint.consecutive_failures.fetch_add(1, Ordering::Relaxed) + 1;
+                    if consecutive_failures >= self.failure_threshold && self.fail_over(index, e) && tried < self.endpoints.len() {
+                        tried += 1;
+                        continue;
+                    }
+                }
+                Err(_) => {}
+            }
+            return result;
+        }
+    }
+
+    pub fn health(&self) -> Vec<EndpointHealth> {
+        let active = self.active.load(Ordering::Relaxed);
+        self.endpoints.iter().enumerate().map(|(index, endpoint)| EndpointHealth {
+            endpoint: rpc_lag::endpoint_label(&endpoint.client.url()),
+            active: index == active,
+            consecutive_failures: endpoint.consecutive_failures.load(Ordering::Relaxed),
+            requests: endpoint.requests.load(Ordering::Relaxed),
+            failures: endpoint.failures.load(Ordering::Relaxed),
+        }).collect()
+    }
+
+    // Switch away from a failing endpoint, preferring the next one that isn't failing itself. Returns false
+    // when there is nothing to switch to, or another call already switched.
+    fn fail_over(&self, from: usize, error: &anyhow::Error) -> bool {
+        let count = self.endpoints.len();
+        if count < 2 {
+            return false;
+        }
+        let to = (1..count).map(|offset| (from + offset) % count)
+            .find(|index| self.endpoints[*index].consecutive_failures.load(Ordering::Relaxed) < self.failure_threshold)
+            .unwrap_or((from + 1) % count);
+        if self.active.compare_exchange(from, to, Ordering::Relaxed, Ordering::Relaxed).is_err() {
+            return false;
+        }
+        // The endpoint taking over gets a fresh run before it can be failed over in turn
+        self.endpoints[to].consecutive_failures.store(0, Ordering::Relaxed);
+        warn!("RPC {} failed {} times in a row ({}), switching to {}",
+              rpc_lag::endpoint_label(&self.endpoints[from].client.url()), self.failure_threshold, error,
+              rpc_lag::endpoint_label(&self.endpoints[to].client.url()));
+        true
+    }
+}
+
+// Errors that say the endpoint itself is unreachable, overloaded or unhealthy, as opposed to a bad request
+fn is_endpoint_error(error: &anyhow::Error) -> bool {
+    let Some(error) = error.downcast_ref::<ClientError>() else {
+        return false;
+    };
+    match error.kind() {
+        ClientErrorKind::Io(_) | ClientErrorKind::Reqwest(_) => true,
+        ClientErrorKind::RpcError(RpcError::RpcRequestError(_)) => true,
+        ClientErrorKind::RpcError(RpcError::RpcResponseError { code, .. }) => *code == NODE_UNHEALTHY,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Nothing listens on port 1, so requests there fail with a connection error
+    const UNREACHABLE: &str = "http://127.0.0.1:1";
+    const REACHABLE: &str = "http://127.0.0.1:2";
+
+    fn failover(failure_threshold: u32) -> RpcFailover {
+        RpcFailover::new(&[UNREACHABLE.to_string(), REACHABLE.to_string()], CommitmentConfig::confirmed(), failure_threshold)
+    }
+
+    // Stands in for a working endpoint at REACHABLE and really asks the unreachable one
+    fn request(client: &RpcClient) -> Result<String> {
+        if client.url() == REACHABLE {
+            return Ok(client.url());
+        }
+        client.get_slot()?;
+        Ok(client.url())
+    }
+
+    #[test]
+    fn endpoint_error_fails_over_and_retries_on_the_next_endpoint() {
+        let rpc = failover(1);
+        assert_eq!(rpc.call(request).unwrap(), REACHABLE);
+        assert_eq!(rpc.client().url(), REACHABLE);
+
+        let health = rpc.health();
+        assert!(!health[0].active);
+        assert_eq!((health[0].requests, health[0].failures), (1, 1));
+        assert!(health[1].active);
+        assert_eq!((health[1].requests, health[1].failures), (1, 0));
+    }
+
+    #[test]
+    fn endpoint_errors_below_the_threshold_stay_on_the_active_endpoint() {
+        let rpc = failover(2);
+        assert!(rpc.call(request).is_err());
+        assert_eq!(rpc.client().url(), UNREACHABLE);
+        assert_eq!(rpc.call(request).unwrap(), REACHABLE);
+    }
+
+    #[test]
+    fn request_errors_do_not_fail_over() {
+        let rpc = failover(1);
+        assert!(rpc.call(|_| -> Result<()> { Err(anyhow::anyhow!("bad request")) }).is_err());
+        assert_eq!(rpc.client().url(), UNREACHABLE);
+        assert_eq!(rpc.health()[0].consecutive_failures, 0);
+    }
+}
@@ -0,0 +1,36 @@
+// How far each RPC's slot trails the furthest-ahead endpoint, in the order given
+pub fn slot_lags(slots: &[(String, u64)]) -> Vec<(String, u64)> {
+    let Some(max_slot) = slots.iter().map(|(_, slot)| *slot).max() else {
+        return Vec::new();
+    };
+    slots.iter().map(|(rpc_url, slot)| (rpc_url.clone(), max_slot - slot)).collect()
+}
+
+// Host of an RPC URL for alerts and status, so API keys in paths or query strings aren't shown
+pub fn endpoint_label(rpc_url: &str) -> String {
+    reqwest::Url::parse(rpc_url).ok()
+        .and_then(|url| url.host_str().map(|host| host.to_string()))
+        .unwrap_or_else(|| rpc_url.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lag_is_measured_from_the_furthest_ahead_endpoint() {
+        let slots = vec![("a".to_string(), 1_000), ("b".to_string(), 1_050), ("c".to_string(), 990)];
+        assert_eq!(slot_lags(&slots), vec![("a".to_string(), 50), ("b".to_string(), 0), ("c".to_string(), 60)]);
+    }
+
+    #[test]
+    fn no_slots_mean_no_lags() {
+        assert!(slot_lags(&[]).is_empty());
+    }
+
+    #[test]
+    fn endpoint_label_hides_paths_and_queries() {
+        assert_eq!(endpoint_label("https://rpc.example.com/v1/secret-key?api-key=abc"), "rpc.example.com");
+        assert_eq!(endpoint_label("not a url"), "not a url");
+    }
+}
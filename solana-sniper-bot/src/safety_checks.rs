@@ -0,0 +1,305 @@
+use anyhow::Result;
+use futures_util::future::BoxFuture;
+use log::warn;
+use serde::Serialize;
+use solana_client::rpc_request::TokenAccountsFilter;
+use solana_sdk::pubkey::Pubkey;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::dex_monitor::{DexMonitor, TokenMetadata, SOL_MINT};
+use crate::rpc_failover::RpcFailover;
+use crate::token_accounts;
+use crate::SafetyConfig;
+
+// What a safety check can use besides the token's metadata
+pub struct CheckContext<'a> {
+    pub token_address: &'a str,
+    pub safety: &'a SafetyConfig,
+    pub rpc: &'a RpcFailover,
+    pub dex_monitor: &'a DexMonitor,
+    pub slippage_bps: u16,
+}
+
+// A check's verdict on a token, with what it measured against its threshold (e.g. "8000 < 10000")
+#[derive(Debug, Clone, PartialEq)]
+pub enum CheckResult {
+    Pass(String),
+    Fail(String),
+}
+
+// One check's outcome within a token analysis
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckOutcome {
+    pub name: String,
+    pub passed: bool,
+    pub detail: String,
+}
+
+// Every check analyze_token_detailed ran on a token, in order. Analysis stops at the first failure, so a
+// rejected token's last outcome is the reason.
+#[derive(Debug, Clone, Serialize)]
+pub struct TokenAnalysis {
+    pub token_address: String,
+    pub checks: Vec<CheckOutcome>,
+}
+
+impl TokenAnalysis {
+    pub fn new(token_address: &str) -> Self {
+        TokenAnalysis { token_address: token_address.to_string(), checks: Vec::new() }
+    }
+
+    // Add a check's outcome, logging it when it failed; returns whether it passed
+    pub fn record(&mut self, name: &str, result: CheckResult) -> bool {
+        let (passed, detail) = match result {
+            CheckResult::Pass(detail) => (true, detail),
+            CheckResult::Fail(detail) => {
+                warn!("Token {} failed the {} check: {}", self.token_address, name, detail);
+                (false, detail)
+            }
+        };
+        self.checks.push(CheckOutcome { name: name.to_string(), passed, detail });
+        passed
+    }
+
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|check| check.passed)
+    }
+
+    // The failed check as "name: detail", or None when the token passed
+    pub fn failure(&self) -> Option<String> {
+        self.checks.iter().find(|check| !check.passed).map(|check| format!("{}: {}", check.name, check.detail))
+    }
+}
+
+impl fmt::Display for TokenAnalysis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Token {}: {}", self.token_address, if self.passed() { "PASSED" } else { "REJECTED" })?;
+        for check in &self.checks {
+            writeln!(f, "  {} {}: {}", if check.passed { "pass" } else { "FAIL" }, check.name, check.detail)?;
+        }
+        Ok(())
+    }
+}
+
+// One heuristic analyze_token runs against a token's metadata. The built-in checks run first, in order, then any
+// registered with SolanaSniperBot::with_safety_check; the first failure rejects the token. An Err aborts the
+// analysis the way a failed lookup always has.
+pub trait SafetyCheck: Send + Sync {
+    fn name(&self) -> &str;
+    fn evaluate<'a>(&'a self, token: &'a TokenMetadata, ctx: &'a CheckContext<'a>) -> BoxFuture<'a, Result<CheckResult>>;
+}
+
+// The checks analyze_token has always run, in their original order
+pub fn default_checks() -> Vec<Box<dyn SafetyCheck>> {
+    vec![
+        Box::new(CreatorHoldingsCheck),
+        Box::new(VerifiedCheck),
+        Box::new(MarketCapCheck),
+        Box::new(VolumeCheck),
+        Box::new(HoldersCheck),
+        Box::new(TokenTaxCheck),
+        Box::new(LiquidityCheck),
+        Box::new(HoneypotCheck),
+    ]
+}
+
+// How much of the supply the creator still holds; an unknown creator or supply, or a failed lookup, passes
+pub struct CreatorHoldingsCheck;
+
+impl SafetyCheck for CreatorHoldingsCheck {
+    fn name(&self) -> &str {
+        "creator_holdings"
+    }
+
+    fn evaluate<'a>(&'a self, token: &'a TokenMetadata, ctx: &'a CheckContext<'a>) -> BoxFuture<'a, Result<CheckResult>> {
+        Box::pin(async move {
+            match creator_holdings_percent(ctx.rpc, ctx.token_address, token) {
+                Ok(Some(holdings_percent)) if holdings_percent > ctx.safety.max_creator_holdings_percent => {
+                    Ok(CheckResult::Fail(format!("creator {} holds {:.2}% > {}%",
+                                                 token.creator, holdings_percent, ctx.safety.max_creator_holdings_percent)))
+                }
+                Ok(Some(holdings_percent)) => {
+                    Ok(CheckResult::Pass(format!("creator holds {:.2}% <= {}%", holdings_percent, ctx.safety.max_creator_holdings_percent)))
+                }
+                Ok(None) => Ok(CheckResult::Pass("creator or supply unknown".to_string())),
+                Err(e) => {
+                    warn!("Could not check creator holdings for token {}: {}", ctx.token_address, e);
+                    Ok(CheckResult::Pass(format!("lookup failed: {}", e)))
+                }
+            }
+        })
+    }
+}
+
+// Share of the token's supply in the creator's token accounts, or None when creator or supply is unknown
+fn creator_holdings_percent(rpc: &RpcFailover, token_address: &str, metadata: &TokenMetadata) -> Result<Option<f64>> {
+    if metadata.creator.is_empty() || metadata.total_supply == 0 {
+        return Ok(None);
+    }
+    let creator = Pubkey::from_str(&metadata.creator)?;
+    let mint = Pubkey::from_str(token_address)?;
+    let accounts = rpc.call(|client| token_accounts::list_token_accounts(client, &creator, TokenAccountsFilter::Mint(mint)))?;
+    let creator_balance: u64 = accounts.iter().map(|account| account.amount).sum();
+    Ok(Some(creator_balance as f64 / metadata.total_supply as f64 * 100.0))
+}
+
+pub struct VerifiedCheck;
+
+impl SafetyCheck for VerifiedCheck {
+    fn name(&self) -> &str {
+        "verified"
+    }
+
+    fn evaluate<'a>(&'a self, token: &'a TokenMetadata, _ctx: &'a CheckContext<'a>) -> BoxFuture<'a, Result<CheckResult>> {
+        Box::pin(async move {
+            if token.is_verified {
+                Ok(CheckResult::Pass("verified".to_string()))
+            } else {
+                Ok(CheckResult::Fail("not verified".to_string()))
+            }
+        })
+    }
+}
+
+pub struct MarketCapCheck;
+
+impl SafetyCheck for MarketCapCheck {
+    fn name(&self) -> &str {
+        "market_cap"
+    }
+
+    fn evaluate<'a>(&'a self, token: &'a TokenMetadata, ctx: &'a CheckContext<'a>) -> BoxFuture<'a, Result<CheckResult>> {
+        Box::pin(async move {
+            if token.market_cap < ctx.safety.min_market_cap {
+                Ok(CheckResult::Fail(format!("{} < {}", token.market_cap, ctx.safety.min_market_cap)))
+            } else {
+                Ok(CheckResult::Pass(format!("{} >= {}", token.market_cap, ctx.safety.min_market_cap)))
+            }
+        })
+    }
+}
+
+pub struct VolumeCheck;
+
+impl SafetyCheck for VolumeCheck {
+    fn name(&self) -> &str {
+        "volume_24h"
+    }
+
+    fn evaluate<'a>(&'a self, token: &'a TokenMetadata, ctx: &'a CheckContext<'a>) -> BoxFuture<'a, Result<CheckResult>> {
+        Box::pin(async move {
+            if token.volume_24h < ctx.safety.min_volume_24h {
+                Ok(CheckResult::Fail(format!("{} < {}", token.volume_24h, ctx.safety.min_volume_24h)))
+            } else {
+                Ok(CheckResult::Pass(format!("{} >= {}", token.volume_24h, ctx.safety.min_volume_24h)))
+            }
+        })
+    }
+}
+
+pub struct HoldersCheck;
+
+impl SafetyCheck for HoldersCheck {
+    fn name(&self) -> &str {
+        "holders"
+    }
+
+    fn evaluate<'a>(&'a self, token: &'a TokenMetadata, ctx: &'a CheckContext<'a>) -> BoxFuture<'a, Result<CheckResult>> {
+        Box::pin(async move {
+            if token.holders < ctx.safety.min_holders {
+                Ok(CheckResult::Fail(format!("{} < {}", token.holders, ctx.safety.min_holders)))
+            } else {
+                Ok(CheckResult::Pass(format!("{} >= {}", token.holders, ctx.safety.min_holders)))
+            }
+        })
+    }
+}
+
+// Estimated buy/sell tax, when max_token_tax_percent is set
+pub struct TokenTaxCheck;
+
+impl SafetyCheck for TokenTaxCheck {
+    fn name(&self) -> &str {
+        "token_tax"
+    }
+
+    fn evaluate<'a>(&'a self, _token: &'a TokenMetadata, ctx: &'a CheckContext<'a>) -> BoxFuture<'a, Result<CheckResult>> {
+        Box::pin(async move {
+            let Some(max_token_tax_percent) = ctx.safety.max_token_tax_percent else {
+                return Ok(CheckResult::Pass("not configured".to_string()));
+            };
+            let tax_percent = ctx.dex_monitor.estimate_token_tax_percent(ctx.token_address, ctx.slippage_bps).await?;
+            if tax_percent > max_token_tax_percent {
+                Ok(CheckResult::Fail(format!("estimated {:.2}% > {}%", tax_percent, max_token_tax_percent)))
+            } else {
+                Ok(CheckResult::Pass(format!("estimated {:.2}% <= {}%", tax_percent, max_token_tax_percent)))
+            }
+        })
+    }
+}
+
+// Pool liquidity, on its own and against the market cap; a failed liquidity lookup passes
+pub struct LiquidityCheck;
+
+impl SafetyCheck for LiquidityCheck {
+    fn name(&self) -> &str {
+        "liquidity"
+    }
+
+    fn evaluate<'a>(&'a self, token: &'a TokenMetadata, ctx: &'a CheckContext<'a>) -> BoxFuture<'a, Result<CheckResult>> {
+        Box::pin(async move {
+            let liquidity_sol = match ctx.dex_monitor.get_pool_liquidity_sol(ctx.token_address).await {
+                Ok(liquidity_sol) => liquidity_sol,
+                Err(e) => {
+                    warn!("Could not check liquidity for token {}: {}", ctx.token_address, e);
+                    return Ok(CheckResult::Pass(format!("lookup failed: {}", e)));
+                }
+            };
+            if liquidity_sol < ctx.safety.min_liquidity_sol {
+                return Ok(CheckResult::Fail(format!("{} SOL < {} SOL", liquidity_sol, ctx.safety.min_liquidity_sol)));
+            }
+            let mut detail = format!("{} SOL >= {} SOL", liquidity_sol, ctx.safety.min_liquidity_sol);
+            if let Some(min_ratio) = ctx.safety.min_liquidity_to_market_cap_ratio {
+                let ratio = liquidity_to_market_cap_ratio(ctx.dex_monitor, liquidity_sol, token.market_cap).await?;
+                if ratio < min_ratio {
+                    return Ok(CheckResult::Fail(format!("{:.4} of market cap < {}", ratio, min_ratio)));
+                }
+                detail = format!("{}, {:.4} of market cap >= {}", detail, ratio, min_ratio);
+            }
+            Ok(CheckResult::Pass(detail))
+        })
+    }
+}
+
+// Pool liquidity as a fraction of market cap, pricing the SOL-side liquidity in USD like the market cap
+async fn liquidity_to_market_cap_ratio(dex_monitor: &DexMonitor, liquidity_sol: f64, market_cap: f64) -> Result<f64> {
+    if market_cap <= 0.0 {
+        return Ok(f64::INFINITY);
+    }
+    let sol_price = dex_monitor.get_token_metadata(SOL_MINT).await?.price;
+    Ok(liquidity_sol * sol_price / market_cap)
+}
+
+// Simulated sell of a small buy, when honeypot_check is set; a failed simulation passes
+pub struct HoneypotCheck;
+
+impl SafetyCheck for HoneypotCheck {
+    fn name(&self) -> &str {
+        "honeypot"
+    }
+
+    fn evaluate<'a>(&'a self, _token: &'a TokenMetadata, ctx: &'a CheckContext<'a>) -> BoxFuture<'a, Result<CheckResult>> {
+        Box::pin(async move {
+            if !ctx.safety.honeypot_check {
+                return Ok(CheckResult::Pass("not configured".to_string()));
+            }
+            // analyze_token_safety returns true when the token is sellable
+            match ctx.dex_monitor.analyze_token_safety(ctx.token_address, ctx.slippage_bps, ctx.safety.max_sell_price_impact).await {
+                Ok(true) => Ok(CheckResult::Pass("simulated sell succeeded".to_string())),
+                Ok(false) => Ok(CheckResult::Fail("simulated sell failed; appears to be a honeypot".to_string())),
+                Err(e) => Ok(CheckResult::Pass(format!("simulation failed: {}", e))),
+            }
+        })
+    }
+}
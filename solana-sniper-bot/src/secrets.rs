@@ -0,0 +1,231 @@
+use anyhow::{anyhow, Result};
+use log::{debug, warn};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use zeroize::Zeroizing;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecretsConfig {
+    pub sources: Vec<String>,
+    pub keyring_service: String,
+}
+
+impl Default for SecretsConfig {
+    fn default() -> Self {
+        SecretsConfig {
+            sources: vec!["env".to_string()],
+            keyring_service: "solana-sniper-bot".to_string(),
+        }
+    }
+}
+
+// A secret config value (Telegram token, API key) that is zeroed when dropped and redacted from Debug output.
+// It stays wrapped until the moment it goes into a request.
+#[derive(Clone, PartialEq)]
+pub struct SecretString(Zeroizing<String>);
+
+impl SecretString {
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl Default for SecretString {
+    fn default() -> Self {
+        SecretString(Zeroizing::new(String::new()))
+    }
+}
+
+impl From<Zeroizing<String>> for SecretString {
+    fn from(value: Zeroizing<String>) -> Self {
+        SecretString(value)
+    }
+}
+
+impl From<&str> for SecretString {
+    fn from(value: &str) -> Self {
+        SecretString(Zeroizing::new(value.to_string()))
+    }
+}
+
+impl fmt::Debug for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(if self.is_empty() { "\"\"" } else { "[redacted]" })
+    }
+}
+
+impl Serialize for SecretString {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.expose())
+    }
+}
+
+impl<'de> Deserialize<'de> for SecretString {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        Ok(SecretString(Zeroizing::new(String::deserialize(deserializer)?)))
+    }
+}
+
+// A place secrets (Telegram token, API keys, wallet passphrases) can be looked up by key
+pub trait SecretSource: Send + Sync {
+    fn name(&self) -> &str;
+    fn get_secret(&self, key: &str) -> Result<Option<Zeroizing<String>>>;
+}
+
+pub struct EnvSecretSource;
+
+impl SecretSource for EnvSecretSource {
+    fn name(&self) -> &str {
+        "env"
+    }
+
+    fn get_secret(&self, key: &str) -> Result<Option<Zeroizing<String>>> {
+        match std::env::var(key) {
+            Ok(value) if !value.is_empty() => Ok(Some(Zeroizing::new(value))),
+            _ => Ok(None),
+        }
+    }
+}
+
+pub struct KeyringSecretSource {
+    service: String,
+}
+
+impl KeyringSecretSource {
+    pub fn new(service: String) -> Self {
+        KeyringSecretSource { service }
+    }
+}
+
+impl SecretSource for KeyringSecretSource {
+    fn name(&self) -> &str {
+        "keyring"
+    }
+
+    fn get_secret(&self, key: &str) -> Result<Option<Zeroizing<String>>> {
+        let entry = keyring::Entry::new(&self.service, key)
+            .map_err(|e| anyhow!("Failed to open keyring entry {}/{}: {}", self.service, key, e))?;
+        match entry.get_password() {
+            Ok(value) => Ok(Some(Zeroizing::new(value))),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(anyhow!("Failed to read keyring entry {}/{}: {}", self.service, key, e)),
+        }
+    }
+}
+
+// Consults each configured source in order and returns the first hit
+pub struct SecretResolver {
+    sources: Vec<Box<dyn SecretSource>>,
+}
+
+impl SecretResolver {
+    pub fn new(sources: Vec<Box<dyn SecretSource>>) -> Self {
+        SecretResolver { sources }
+    }
+
+    pub fn from_config(config: &SecretsConfig) -> Result<Self> {
+        let mut sources: Vec<Box<dyn SecretSource>> = Vec::new();
+        for source in &config.sources {
+            match source.as_str() {
+                "env" => sources.push(Box::new(EnvSecretSource)),
+                "keyring" => sources.push(Box::new(KeyringSecretSource::new(config.keyring_service.clone()))),
+                other => return Err(anyhow!("Unknown secret source: {} (expected env or keyring)", other)),
+            }
+        }
+        Ok(SecretResolver::new(sources))
+    }
+
+    pub fn resolve(&self, key: &str) -> Option<Zeroizing<String>> {
+        for source in &self.sources {
+            match source.get_secret(key) {
+                Ok(Some(value)) => {
+                    debug!("Resolved secret {} from {}", key, source.name());
+                    return Some(value);
+                }
+                Ok(None) => {}
+                Err(e) => warn!("Secret source {} failed for {}: {}", source.name(), key, e),
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    struct MockSecretSource {
+        name: &'static str,
+        secrets: HashMap<&'static str, &'static str>,
+        lookups: Arc<Mutex<Vec<String>>>,
+        fail: bool,
+    }
+
+    impl SecretSource for MockSecretSource {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn get_secret(&self, key: &str) -> Result<Option<Zeroizing<String>>> {
+            self.lookups.lock().unwrap().push(format!("{}:{}", self.name, key));
+            if self.fail {
+                return Err(anyhow!("backend unavailable"));
+            }
+            Ok(self.secrets.get(key).map(|value| Zeroizing::new(value.to_string())))
+        }
+    }
+
+    fn mock(name: &'static str, secrets: &[(&'static str, &'static str)], fail: bool, lookups: &Arc<Mutex<Vec<String>>>) -> Box<dyn SecretSource> {
+        Box::new(MockSecretSource { name, secrets: secrets.iter().copied().collect(), lookups: lookups.clone(), fail })
+    }
+
+    #[test]
+    fn resolves_through_sources_in_order() {
+        let lookups = Arc::new(Mutex::new(Vec::new()));
+        let resolver = SecretResolver::new(vec![
+            mock("first", &[("BIRDEYE_API_KEY", "from-first")], false, &lookups),
+            mock("second", &[("BIRDEYE_API_KEY", "from-second"), ("TELEGRAM_BOT_TOKEN", "token")], false, &lookups),
+        ]);
+
+        assert_eq!(resolver.resolve("BIRDEYE_API_KEY").as_deref().map(String::as_str), Some("from-first"));
+        assert_eq!(resolver.resolve("TELEGRAM_BOT_TOKEN").as_deref().map(String::as_str), Some("token"));
+        assert_eq!(resolver.resolve("JUPITER_API_KEY"), None);
+        assert_eq!(*lookups.lock().unwrap(), vec![
+            "first:BIRDEYE_API_KEY",
+            "first:TELEGRAM_BOT_TOKEN",
+            "second:TELEGRAM_BOT_TOKEN",
+            "first:JUPITER_API_KEY",
+            "second:JUPITER_API_KEY",
+        ]);
+    }
+
+    #[test]
+    fn failing_source_falls_through_to_the_next() {
+        let lookups = Arc::new(Mutex::new(Vec::new()));
+        let resolver = SecretResolver::new(vec![
+            mock("broken", &[], true, &lookups),
+            mock("working", &[("JUPITER_API_KEY", "key")], false, &lookups),
+        ]);
+        assert_eq!(resolver.resolve("JUPITER_API_KEY").as_deref().map(String::as_str), Some("key"));
+    }
+
+    #[test]
+    fn secret_string_is_redacted_from_debug() {
+        let secret = SecretString::from("hunter2");
+        assert_eq!(format!("{:?}", secret), "[redacted]");
+        assert_eq!(secret.expose(), "hunter2");
+        assert_eq!(format!("{:?}", SecretString::default()), "\"\"");
+    }
+
+    #[test]
+    fn unknown_source_is_rejected() {
+        let config = SecretsConfig { sources: vec!["vault".to_string()], ..SecretsConfig::default() };
+        assert!(SecretResolver::from_config(&config).is_err());
+    }
+}
@@ -0,0 +1,135 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SizingConfig {
+    pub enabled: bool,
+    pub step_percent: f64,
+    pub min_multiplier: f64,
+    pub max_multiplier: f64,
+    pub lookback: usize,
+    pub max_portfolio_fraction: f64,
+    // Draw each buy's base size uniformly from [buy_amount_min, buy_amount_max] instead of using max_sol_per_trade
+    #[serde(default)]
+    pub randomize: bool,
+    #[serde(default)]
+    pub buy_amount_min: f64,
+    #[serde(default)]
+    pub buy_amount_max: f64,
+}
+
+impl Default for SizingConfig {
+    fn default() -> Self {
+        SizingConfig {
+            enabled: false,
+            step_percent: 10.0,
+            min_multiplier: 0.5,
+            max_multiplier: 2.0,
+            lookback: 20,
+            max_portfolio_fraction: 1.0,
+            randomize: false,
+            buy_amount_min: 0.0,
+            buy_amount_max: 0.0,
+        }
+    }
+}
+
+// Step sizing: each consecutive win grows the size by step_percent, each consecutive loss shrinks it
+pub fn size_multiplier(recent_pnl: &[f64], config: &SizingConfig) -> f64 {
+    if !config.enabled {
+        return 1.0;
+    }
+
+    let step = config.step_percent / 100.0;
+    let mut results = recent_pnl.iter().rev().take(config.lookback);
+    let multiplier = match results.next() {
+        Some(&last) if last > 0.0 => {
+            let wins = 1 + results.take_while(|&&pnl| pnl > 0.0).count();
+            (1.0 + step).powi(wins as i32)
+        }
+        Some(_) => {
+            let losses = 1 + results.take_while(|&&pnl| pnl <= 0.0).count();
+            (1.0 - step).max(0.0).powi(losses as i32)
+        }
+        None => 1.0,
+    };
+
+    multiplier.clamp(config.min_multiplier, config.max_multiplier)
+}
+
+// Base size of the next buy: a fresh random draw from the configured range when randomized
+pub fn base_amount(max_sol_per_trade: f64, config: &SizingConfig) -> f64 {
+    if !config.randomize {
+        return max_sol_per_trade;
+    }
+    rand::thread_rng().gen_range(config.buy_amount_min..=config.buy_amount_max)
+}
+
+// Scale the base size and cap it so it never dips into the reserve or exceeds the portfolio fraction
+pub fn adjusted_amount(base_amount: f64, recent_pnl: &[f64], balance: f64, reserve: f64, config: &SizingConfig) -> f64 {
+    let amount = base_amount * size_multiplier(recent_pnl, config);
+    let spendable = (balance - reserve).max(0.0);
+    let fraction_cap = balance * config.max_portfolio_fraction;
+    amount.min(spendable).min(fraction_cap)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> SizingConfig {
+        SizingConfig { enabled: true, ..SizingConfig::default() }
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-9, "expected {}, got {}", expected, actual);
+    }
+
+    #[test]
+    fn consecutive_wins_grow_the_size() {
+        assert_close(size_multiplier(&[-1.0, 1.0, 1.0], &config()), 1.21);
+    }
+
+    #[test]
+    fn consecutive_losses_shrink_the_size() {
+        assert_close(size_multiplier(&[1.0, -1.0, 0.0], &config()), 0.81);
+    }
+
+    #[test]
+    fn multiplier_is_clamped_to_the_bounds() {
+        assert_close(size_multiplier(&[1.0; 20], &config()), 2.0);
+        assert_close(size_multiplier(&[-1.0; 20], &config()), 0.5);
+    }
+
+    #[test]
+    fn only_the_lookback_counts() {
+        let config = SizingConfig { lookback: 2, ..config() };
+        assert_close(size_multiplier(&[1.0; 10], &config), 1.21);
+    }
+
+    #[test]
+    fn disabled_or_empty_history_keeps_the_base_size() {
+        assert_close(size_multiplier(&[1.0, 1.0], &SizingConfig::default()), 1.0);
+        assert_close(size_multiplier(&[], &config()), 1.0);
+    }
+
+    #[test]
+    fn adjusted_amount_respects_reserve_and_portfolio_fraction() {
+        let config = SizingConfig { max_portfolio_fraction: 0.1, ..config() };
+        // 1.0 * 1.1 is capped at 10% of a 5 SOL balance
+        assert_close(adjusted_amount(1.0, &[1.0], 5.0, 0.0, &config), 0.5);
+        // and never spends into the reserve
+        assert_close(adjusted_amount(1.0, &[1.0], 10.0, 9.8, &config), 0.2);
+        assert_close(adjusted_amount(1.0, &[], 100.0, 0.0, &config), 1.0);
+    }
+
+    #[test]
+    fn randomized_base_amount_stays_in_range() {
+        let config = SizingConfig { randomize: true, buy_amount_min: 0.1, buy_amount_max: 0.3, ..SizingConfig::default() };
+        for _ in 0..100 {
+            let amount = base_amount(1.0, &config);
+            assert!((0.1..=0.3).contains(&amount), "{} outside [0.1, 0.3]", amount);
+        }
+        assert_close(base_amount(1.0, &SizingConfig::default()), 1.0);
+    }
+}
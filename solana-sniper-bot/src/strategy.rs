@@ -0,0 +1,41 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+// One strategy reacting to the shared detection stream with its own wallets, size and exits
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StrategyConfig {
+    pub name: String,
+    pub wallets: Vec<usize>,
+    // SOL per buy; falls back to max_sol_per_trade (and adaptive sizing) when unset
+    #[serde(default)]
+    pub amount_sol: Option<f64>,
+    // Exit thresholds for this strategy's positions; fall back to the trading config when unset
+    #[serde(default)]
+    pub take_profit_percent: Option<f64>,
+    #[serde(default)]
+    pub stop_loss_percent: Option<f64>,
+}
+
+// Strategies must have unique names and own disjoint, existing wallets, which keeps their positions apart
+pub fn validate_strategies(strategies: &[StrategyConfig], wallet_count: usize) -> Result<()> {
+    let mut names = HashSet::new();
+    let mut owned_wallets = HashSet::new();
+    for strategy in strategies {
+        if !names.insert(strategy.name.as_str()) {
+            return Err(anyhow!("Duplicate strategy name {}", strategy.name));
+        }
+        if strategy.wallets.is_empty() {
+            return Err(anyhow!("Strategy {} has no wallets", strategy.name));
+        }
+        for &wallet_index in &strategy.wallets {
+            if wallet_index >= wallet_count {
+                return Err(anyhow!("Strategy {} uses wallet {}, but only {} wallets are configured", strategy.name, wallet_index, wallet_count));
+            }
+            if !owned_wallets.insert(wallet_index) {
+                return Err(anyhow!("Wallet {} is assigned to more than one strategy", wallet_index));
+            }
+        }
+    }
+    Ok(())
+}
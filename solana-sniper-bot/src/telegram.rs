@@ -1,34 +1,124 @@
 use std::collections::HashMap;
 
 use serde::{Deserialize, Serialize};
-use anyhow::{Result};
-use log::{info};
+use anyhow::{anyhow, Result};
+use log::{info, warn};
+
+use crate::secrets::SecretString;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TelegramConfig {
-    pub bot_token: String,
+    pub bot_token: SecretString,
     pub chat_id: String,
+    // Resend as plain text when Telegram rejects a message's MarkdownV2
+    #[serde(default = "default_plain_text_fallback")]
+    pub plain_text_fallback: bool,
+    // Also alert on buys and sells whose swap failed
+    #[serde(default)]
+    pub notify_failed_trades: bool,
+    // Attach Buy/Ignore buttons to new-pool alerts and act on presses from the configured chat
+    #[serde(default)]
+    pub interactive_buttons: bool,
+    // SOL spent when the Buy button is pressed
+    #[serde(default = "default_button_buy_amount_sol")]
+    pub button_buy_amount_sol: f64,
+    // Bot API server; a self-hosted one can stand in for Telegram's
+    #[serde(default = "default_telegram_api_url")]
+    pub api_url: String,
+}
+
+// An operator's answer to a new-pool alert, carried in the pressed button's callback data
+#[derive(Debug, Clone, PartialEq)]
+pub enum PoolDecision {
+    Buy { token_address: String, amount_sol: f64 },
+    Ignore { token_address: String },
+}
+
+impl PoolDecision {
+    // Encoded as "buy:<mint>:<amount>" or "ignore:<mint>", within Telegram's 64-byte limit
+    pub fn callback_data(&self) -> String {
+        match self {
+            PoolDecision::Buy { token_address, amount_sol } => format!("buy:{}:{}", token_address, amount_sol),
+            PoolDecision::Ignore { token_address } => format!("ignore:{}", token_address),
+        }
+    }
+
+    pub fn parse(callback_data: &str) -> Option<Self> {
+        let mut parts = callback_data.split(':');
+        let decision = match (parts.next()?, parts.next()?) {
+            ("buy", token_address) => PoolDecision::Buy { token_address: token_address.to_string(), amount_sol: parts.next()?.parse().ok()? },
+            ("ignore", token_address) => PoolDecision::Ignore { token_address: token_address.to_string() },
+            _ => return None,
+        };
+        parts.next().is_none().then_some(decision)
+    }
+}
+
+// What a real buy or sell did, for trade alerts
+pub struct TradeAlert<'a> {
+    pub side: &'a str,
+    pub token_address: &'a str,
+    pub wallet_index: usize,
+    // SOL spent on a buy or received from a sell
+    pub sol_amount: f64,
+    pub token_amount: u64,
+    pub price_impact: Option<f64>,
+}
+
+pub fn default_plain_text_fallback() -> bool {
+    true
+}
+
+pub fn default_button_buy_amount_sol() -> f64 {
+    0.05
+}
+
+pub fn default_telegram_api_url() -> String {
+    "https://api.telegram.org".to_string()
+}
+
+// Telegram's own explanation of a failed call (e.g. "Bad Request: chat not found"), else the raw body
+async fn error_description(response: reqwest::Response) -> String {
+    let body = response.text().await.unwrap_or_default();
+    serde_json::from_str::<serde_json::Value>(&body).ok()
+        .and_then(|error| error.get("description").and_then(|description| description.as_str()).map(|description| description.to_string()))
+        .unwrap_or(body)
+}
+
+// Telegram's wording for a 400 caused by bad MarkdownV2 entities
+fn is_parse_error(status: reqwest::StatusCode, description: &str) -> bool {
+    status == reqwest::StatusCode::BAD_REQUEST && description.contains("can't parse entities")
 }
 
 #[derive(Clone)]
 
 pub struct TelegramSender {
   telegram_config: TelegramConfig,
+  // False when the bot token or chat id is missing; every send is then a no-op
+  enabled: bool,
 }
 
 impl TelegramSender {
   pub fn new(telegram_config: TelegramConfig) -> Self {
-    // Check if the bot token and chat id are valid
-    if telegram_config.bot_token.is_empty() || telegram_config.chat_id.is_empty() {
-      // Throw error
-      panic!("Telegram config is invalid: {:?}", telegram_config);
+    let enabled = !telegram_config.bot_token.is_empty() && !telegram_config.chat_id.is_empty();
+    if !enabled {
+      info!("Telegram bot token or chat id not set; Telegram alerts are disabled");
     }
 
-    TelegramSender { telegram_config }
+    TelegramSender { telegram_config, enabled }
   }
 
-  pub async fn send_new_pool_telegram_message(&self, tx_hash: &str, pool_addr: &str, token_a: &str, token_b: &str) -> Result<()> {
-    let bot_token = self.telegram_config.bot_token.clone();
+  pub fn is_enabled(&self) -> bool {
+    self.enabled
+  }
+
+  fn method_url(&self, bot_token: &str, method: &str) -> String {
+    format!("{}/bot{}/{}", self.telegram_config.api_url.trim_end_matches('/'), bot_token, method)
+  }
+
+  // Buy/Ignore buttons are only offered for pools with an identifiable new token
+  pub async fn send_new_pool_telegram_message(&self, dex: &str, tx_hash: &str, pool_addr: &str, token_a: &str, token_b: &str, new_token: Option<&str>) -> Result<()> {
+    let bot_token = self.telegram_config.bot_token.expose();
     // Pool, token link in explorer is https://explorer.solana.com/address/xx
     let pool_link = format!("https://explorer.solana.com/address/{}", pool_addr);
     let token_a_link = format!("https://explorer.solana.com/address/{}", token_a);
@@ -36,29 +126,275 @@ impl TelegramSender {
     let tx_link = format!("https://explorer.solana.com/tx/{}", tx_hash);
     let chat_id = self.telegram_config.chat_id.clone();
       let msg = format!(
-        "*New Raydium Pool Detected!*
+        "*New {} Pool Detected!*
         Tx Hash: `{}`
         Pool: `{}`
         Token A: `{}`
         Token B: `{}`",
-        tx_link, pool_link, token_a_link, token_b_link
+        dex, tx_link, pool_link, token_a_link, token_b_link
+      );
+      let reply_markup = new_token.filter(|_| self.telegram_config.interactive_buttons).map(|token_address| {
+        let amount_sol = self.telegram_config.button_buy_amount_sol;
+        let buy = PoolDecision::Buy { token_address: token_address.to_string(), amount_sol };
+        let ignore = PoolDecision::Ignore { token_address: token_address.to_string() };
+        serde_json::json!({
+          "inline_keyboard": [[
+            { "text": format!("Buy {}", amount_sol), "callback_data": buy.callback_data() },
+            { "text": "Ignore", "callback_data": ignore.callback_data() },
+          ]]
+        })
+      });
+      self.send_telegram_message_with_markup(bot_token, &chat_id, &msg, reply_markup).await.map_err(|e| anyhow::anyhow!("Failed to send Telegram message: {}", e))
+  }
+
+  pub async fn send_dump_alert_telegram_message(&self, token_address: &str, reason: &str, exited: bool) -> Result<()> {
+    let bot_token = self.telegram_config.bot_token.expose();
+    let token_link = format!("https://explorer.solana.com/address/{}", token_address);
+    let chat_id = self.telegram_config.chat_id.clone();
+      let msg = format!(
+        "*Dump Detected!*
+        Token: `{}`
+        Reason: `{}`
+        Action: `{}`",
+        token_link, reason, if exited { "position sold" } else { "alert only" }
+      );
+      self.send_telegram_message(bot_token, &chat_id, &msg).await.map_err(|e| anyhow::anyhow!("Failed to send Telegram message: {}", e))
+  }
+
+  pub async fn send_position_exit_telegram_message(&self, token_address: &str, reason: &str, signature: &str) -> Result<()> {
+    let bot_token = self.telegram_config.bot_token.expose();
+    let token_link = format!("https://explorer.solana.com/address/{}", token_address);
+    let tx_link = format!("https://explorer.solana.com/tx/{}", signature);
+    let chat_id = self.telegram_config.chat_id.clone();
+      let msg = format!(
+        "*Position Exited!*
+        Token: `{}`
+        Reason: `{}`
+        Tx Hash: `{}`",
+        token_link, reason, tx_link
+      );
+      self.send_telegram_message(bot_token, &chat_id, &msg).await.map_err(|e| anyhow::anyhow!("Failed to send Telegram message: {}", e))
+  }
+
+  pub async fn send_trade_telegram_message(&self, trade: &TradeAlert<'_>, signature: &str) -> Result<()> {
+    let bot_token = self.telegram_config.bot_token.expose();
+    let token_link = format!("https://explorer.solana.com/address/{}", trade.token_address);
+    let tx_link = format!("https://explorer.solana.com/tx/{}", signature);
+    let chat_id = self.telegram_config.chat_id.clone();
+    let price_impact = trade.price_impact.map(|impact| format!("{:.2}%", impact * 100.0)).unwrap_or_else(|| "unknown".to_string());
+      let msg = format!(
+        "*{} Executed!*
+        Token: `{}`
+        Wallet: `{}`
+        SOL: `{}`
+        Tokens: `{}`
+        Price impact: `{}`
+        Tx Hash: `{}`",
+        if trade.side == "buy" { "Buy" } else { "Sell" }, token_link, trade.wallet_index, trade.sol_amount, trade.token_amount, price_impact, tx_link
+      );
+      self.send_telegram_message(bot_token, &chat_id, &msg).await.map_err(|e| anyhow::anyhow!("Failed to send Telegram message: {}", e))
+  }
+
+  pub async fn send_failed_trade_telegram_message(&self, trade: &TradeAlert<'_>, error: &str) -> Result<()> {
+    if !self.telegram_config.notify_failed_trades {
+      return Ok(());
+    }
+    let bot_token = self.telegram_config.bot_token.expose();
+    let token_link = format!("https://explorer.solana.com/address/{}", trade.token_address);
+    let chat_id = self.telegram_config.chat_id.clone();
+      let msg = format!(
+        "*{} Failed!*
+        Token: `{}`
+        Wallet: `{}`
+        SOL: `{}`
+        Error: `{}`",
+        if trade.side == "buy" { "Buy" } else { "Sell" }, token_link, trade.wallet_index, trade.sol_amount, error
       );
-      self.send_telegram_message(&bot_token, &chat_id, &msg).await.map_err(|e| anyhow::anyhow!("Failed to send Telegram message: {}", e))
+      self.send_telegram_message(bot_token, &chat_id, &msg).await.map_err(|e| anyhow::anyhow!("Failed to send Telegram message: {}", e))
+  }
+
+  pub async fn send_capital_floor_telegram_message(&self, total_balance: f64, capital_floor: f64, paused: bool) -> Result<()> {
+    let bot_token = self.telegram_config.bot_token.expose();
+    let chat_id = self.telegram_config.chat_id.clone();
+      let msg = format!(
+        "*{}*
+        Total balance: `{:.4} SOL`
+        Capital floor: `{} SOL`",
+        if paused { "Auto-buy Paused!" } else { "Auto-buy Resumed" }, total_balance, capital_floor
+      );
+      self.send_telegram_message(bot_token, &chat_id, &msg).await.map_err(|e| anyhow::anyhow!("Failed to send Telegram message: {}", e))
+  }
+
+  pub async fn send_rpc_lag_telegram_message(&self, rpc: &str, lag: u64, max_slot_lag: u64, lagging: bool) -> Result<()> {
+    let bot_token = self.telegram_config.bot_token.expose();
+    let chat_id = self.telegram_config.chat_id.clone();
+      let msg = format!(
+        "*{}*
+        RPC: `{}`
+        Slots behind: `{}`
+        Limit: `{}`",
+        if lagging { "RPC Lagging!" } else { "RPC Caught Up" }, rpc, lag, max_slot_lag
+      );
+      self.send_telegram_message(bot_token, &chat_id, &msg).await.map_err(|e| anyhow::anyhow!("Failed to send Telegram message: {}", e))
+  }
+
+  // Long-poll for button presses after the given update offset, returning (callback query id, decision) pairs
+  // from the configured chat only; the offset is advanced past everything received
+  pub async fn get_pool_decisions(&self, offset: &mut i64, timeout_secs: u64) -> Result<Vec<(String, Option<PoolDecision>)>> {
+    let url = self.method_url(self.telegram_config.bot_token.expose(), "getUpdates");
+    let payload = serde_json::json!({
+      "offset": *offset,
+      "timeout": timeout_secs,
+      "allowed_updates": ["callback_query"],
+    });
+    let client = reqwest::Client::new();
+    let response = client.post(&url)
+      .timeout(std::time::Duration::from_secs(timeout_secs + 10))
+      .json(&payload)
+      .send().await?;
+    if !response.status().is_success() {
+      let status = response.status();
+      return Err(anyhow!("Telegram returned {}: {}", status, error_description(response).await));
+    }
+    let body: serde_json::Value = response.json().await?;
+
+    let mut decisions = Vec::new();
+    for update in body["result"].as_array().into_iter().flatten() {
+      if let Some(update_id) = update["update_id"].as_i64() {
+        *offset = (*offset).max(update_id + 1);
+      }
+      let callback_query = &update["callback_query"];
+      let (Some(id), Some(data)) = (callback_query["id"].as_str(), callback_query["data"].as_str()) else {
+        continue;
+      };
+      let chat_id = callback_query["message"]["chat"]["id"].as_i64().map(|id| id.to_string()).unwrap_or_default();
+      if chat_id != self.telegram_config.chat_id {
+        warn!("Ignoring Telegram button press from chat {}", chat_id);
+        continue;
+      }
+      decisions.push((id.to_string(), PoolDecision::parse(data)));
+    }
+    Ok(decisions)
+  }
+
+  // Acknowledge a button press so Telegram stops the loading spinner, with a short toast for the operator
+  pub async fn answer_callback_query(&self, callback_query_id: &str, text: &str) -> Result<()> {
+    let url = self.method_url(self.telegram_config.bot_token.expose(), "answerCallbackQuery");
+    let payload = serde_json::json!({ "callback_query_id": callback_query_id, "text": text });
+    let response = reqwest::Client::new().post(&url).json(&payload).send().await?;
+    if !response.status().is_success() {
+      let status = response.status();
+      return Err(anyhow!("Telegram returned {}: {}", status, error_description(response).await));
+    }
+    Ok(())
   }
 
   async fn send_telegram_message(&self,bot_token: &str, chat_id: &str, text: &str) -> Result<()> {
-    let url = format!("https://api.telegram.org/bot{}/sendMessage", bot_token);
+    self.send_telegram_message_with_markup(bot_token, chat_id, text, None).await
+  }
+
+  async fn send_telegram_message_with_markup(&self, bot_token: &str, chat_id: &str, text: &str, reply_markup: Option<serde_json::Value>) -> Result<()> {
+    if !self.enabled {
+      return Ok(());
+    }
+    let url = self.method_url(bot_token, "sendMessage");
     // let payload = [
     //     ("chat_id", chat_id),
     //     ("text", text),
     //     ("parse_mode", "MarkdownV2"),
     // ];
-    let mut payload = HashMap::new();
-    payload.insert("chat_id", chat_id);
-    payload.insert("text", text);
-    payload.insert("parse_mode", "MarkdownV2");
+    let mut payload: HashMap<&str, serde_json::Value> = HashMap::new();
+    // Tag every notification with the run so it can be matched against the logs
+    let text = match crate::trace::current_run_id() {
+      Some(run_id) => format!("{}\n        Run: `{}`", text, run_id),
+      None => text.to_string(),
+    };
+    payload.insert("chat_id", chat_id.into());
+    payload.insert("text", text.into());
+    payload.insert("parse_mode", "MarkdownV2".into());
+    if let Some(reply_markup) = reply_markup {
+      payload.insert("reply_markup", reply_markup);
+    }
     info!("Sending Telegram message: {:?}", payload);
-    reqwest::Client::new().post(&url).header("Content-Type", "application/json").json(&payload).send().await?;
+    let client = reqwest::Client::new();
+    let response = client.post(&url).header("Content-Type", "application/json").json(&payload).send().await?;
+    let status = response.status();
+    if status.is_success() {
+      return Ok(());
+    }
+    let description = error_description(response).await;
+    if !self.telegram_config.plain_text_fallback || !is_parse_error(status, &description) {
+      return Err(anyhow!("Telegram returned {}: {}", status, description));
+    }
+
+    warn!("Telegram rejected MarkdownV2 ({}), resending as plain text", description);
+    payload.remove("parse_mode");
+    let response = client.post(&url).header("Content-Type", "application/json").json(&payload).send().await?;
+    if !response.status().is_success() {
+      let status = response.status();
+      return Err(anyhow!("Telegram returned {}: {}", status, error_description(response).await));
+    }
     Ok(())
   } 
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::mock_server::MockServer;
+
+  fn sender(api_url: &str, plain_text_fallback: bool) -> TelegramSender {
+    TelegramSender::new(TelegramConfig {
+      bot_token: SecretString::from("test-token"),
+      chat_id: "42".to_string(),
+      plain_text_fallback,
+      notify_failed_trades: false,
+      interactive_buttons: false,
+      button_buy_amount_sol: default_button_buy_amount_sol(),
+      api_url: api_url.to_string(),
+    })
+  }
+
+  // Telegram rejecting every MarkdownV2 message, as it does when an entity isn't escaped
+  async fn markdown_rejecting_telegram() -> MockServer {
+    MockServer::start(|request| {
+      if request.body.get("parse_mode").is_some() {
+        (400, serde_json::json!({ "ok": false, "error_code": 400, "description": "Bad Request: can't parse entities: Character '.' is reserved" }))
+      } else {
+        (200, serde_json::json!({ "ok": true, "result": {} }))
+      }
+    }).await
+  }
+
+  #[test]
+  fn only_bad_requests_about_entities_are_parse_errors() {
+    assert!(is_parse_error(reqwest::StatusCode::BAD_REQUEST, "Bad Request: can't parse entities: Can't find end of the entity"));
+    assert!(!is_parse_error(reqwest::StatusCode::BAD_REQUEST, "Bad Request: chat not found"));
+    assert!(!is_parse_error(reqwest::StatusCode::INTERNAL_SERVER_ERROR, "can't parse entities"));
+  }
+
+  #[tokio::test]
+  async fn rejected_markdown_is_resent_as_plain_text() {
+    let telegram = markdown_rejecting_telegram().await;
+
+    sender(telegram.url(), true).send_dump_alert_telegram_message("Mint111", "holders fell 40%", false).await.unwrap();
+
+    let requests = telegram.requests();
+    assert_eq!(requests.len(), 2);
+    assert!(requests.iter().all(|request| request.method == "POST" && request.path == "/bottest-token/sendMessage"));
+    assert_eq!(requests[0].body["parse_mode"], "MarkdownV2");
+    assert!(requests[1].body.get("parse_mode").is_none());
+    assert_eq!(requests[1].body["text"], requests[0].body["text"]);
+    assert_eq!(requests[1].body["chat_id"], "42");
+  }
+
+  #[tokio::test]
+  async fn rejected_markdown_is_an_error_without_the_fallback() {
+    let telegram = markdown_rejecting_telegram().await;
+
+    let error = sender(telegram.url(), false).send_dump_alert_telegram_message("Mint111", "holders fell 40%", false).await.unwrap_err();
+
+    assert!(error.to_string().contains("can't parse entities"), "{}", error);
+    assert_eq!(telegram.requests().len(), 1);
+  }
+}
@@ -0,0 +1,61 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+const WINDOW: Duration = Duration::from_secs(1);
+
+// Caps how many detected pools and listings are fully evaluated in any one-second window.
+// Anything over the rate is shed rather than queued, so a listing storm can't back up the pipeline.
+pub struct EvaluationThrottle {
+    max_per_second: usize,
+    admitted: VecDeque<Instant>,
+}
+
+impl EvaluationThrottle {
+    pub fn new(max_per_second: usize) -> Self {
+        EvaluationThrottle { max_per_second, admitted: VecDeque::new() }
+    }
+
+    // Whether one more evaluation fits in the second leading up to `now`, recording it if so
+    pub fn try_admit(&mut self, now: Instant) -> bool {
+        while self.admitted.front().is_some_and(|admitted_at| now.duration_since(*admitted_at) >= WINDOW) {
+            self.admitted.pop_front();
+        }
+        if self.admitted.len() >= self.max_per_second {
+            return false;
+        }
+        self.admitted.push_back(now);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn admits_up_to_the_rate_within_one_window() {
+        let mut throttle = EvaluationThrottle::new(3);
+        let start = Instant::now();
+
+        assert!(throttle.try_admit(start));
+        assert!(throttle.try_admit(start + Duration::from_millis(100)));
+        assert!(throttle.try_admit(start + Duration::from_millis(200)));
+        assert!(!throttle.try_admit(start + Duration::from_millis(300)));
+        assert!(!throttle.try_admit(start + Duration::from_millis(999)));
+    }
+
+    #[test]
+    fn admits_again_once_the_window_has_passed() {
+        let mut throttle = EvaluationThrottle::new(2);
+        let start = Instant::now();
+        assert!(throttle.try_admit(start));
+        assert!(throttle.try_admit(start + Duration::from_millis(500)));
+        assert!(!throttle.try_admit(start + Duration::from_millis(900)));
+
+        // Only the first admission has left the window
+        assert!(throttle.try_admit(start + WINDOW));
+        assert!(!throttle.try_admit(start + WINDOW + Duration::from_millis(100)));
+        // Now both earlier ones have
+        assert!(throttle.try_admit(start + WINDOW + Duration::from_millis(500)));
+    }
+}
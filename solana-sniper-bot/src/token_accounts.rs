@@ -0,0 +1,183 @@
+use anyhow::{anyhow, Result};
+use solana_client::{rpc_client::RpcClient, rpc_request::TokenAccountsFilter};
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program,
+};
+use std::str::FromStr;
+
+pub const TOKEN_PROGRAM_ID: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+pub const TOKEN_2022_PROGRAM_ID: &str = "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb";
+pub const ASSOCIATED_TOKEN_PROGRAM_ID: &str = "ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL";
+// Decimals of native SOL, which has no SPL supply to read them from
+pub const SOL_DECIMALS: u8 = 9;
+// CloseAccount's instruction tag, shared by the SPL Token and Token-2022 programs
+const CLOSE_ACCOUNT: u8 = 9;
+// The associated token program's CreateIdempotent instruction tag
+const CREATE_IDEMPOTENT: u8 = 1;
+
+// Byte ranges of the authority fields in a mint account, the same for SPL Token and Token-2022. Each is a
+// COption<Pubkey>: a 4-byte little-endian tag (1 when set) followed by the key.
+const MINT_AUTHORITY_OFFSET: usize = 0;
+const FREEZE_AUTHORITY_OFFSET: usize = 46;
+const MINT_BASE_LEN: usize = 82;
+
+// Who can still mint more supply or freeze holders' accounts; None means revoked
+#[derive(Debug, Clone, PartialEq)]
+pub struct MintAuthorities {
+    pub mint_authority: Option<Pubkey>,
+    pub freeze_authority: Option<Pubkey>,
+}
+
+// One SPL token account of a wallet, as reported by the RPC's jsonParsed encoding
+#[derive(Debug, Clone)]
+pub struct TokenAccountBalance {
+    pub address: Pubkey,
+    pub program_id: Pubkey,
+    pub mint: String,
+    pub amount: u64,
+    pub lamports: u64,
+}
+
+pub fn list_token_accounts(client: &RpcClient, owner: &Pubkey, filter: TokenAccountsFilter) -> Result<Vec<TokenAccountBalance>> {
+    let mut balances = Vec::new();
+    for keyed_account in client.get_token_accounts_by_owner(owner, filter)? {
+        let data = serde_json::to_value(&keyed_account.account.data)?;
+        let info = &data["parsed"]["info"];
+        let amount = info["tokenAmount"]["amount"].as_str()
+            .ok_or_else(|| anyhow!("Token account {} is not jsonParsed", keyed_account.pubkey))?;
+        balances.push(TokenAccountBalance {
+            address: Pubkey::from_str(&keyed_account.pubkey)?,
+            program_id: Pubkey::from_str(&keyed_account.account.owner)?,
+            mint: info["mint"].as_str().unwrap_or_default().to_string(),
+            amount: amount.parse()?,
+            lamports: keyed_account.account.lamports,
+        });
+    }
+    Ok(balances)
+}
+
+// Every token account of a wallet under both token programs
+pub fn list_all_token_accounts(client: &RpcClient, owner: &Pubkey) -> Result<Vec<TokenAccountBalance>> {
+    let mut balances = Vec::new();
+    for program_id in [TOKEN_PROGRAM_ID, TOKEN_2022_PROGRAM_ID] {
+        let filter = TokenAccountsFilter::ProgramId(Pubkey::from_str(program_id)?);
+        balances.extend(list_token_accounts(client, owner, filter)?);
+    }
+    Ok(balances)
+}
+
+// Decimals of a mint, read from its on-chain supply
+pub fn mint_decimals(client: &RpcClient, mint: &str) -> Result<u8> {
+    Ok(client.get_token_supply(&Pubkey::from_str(mint)?)?.decimals)
+}
+
+// Read the mint and freeze authorities from a mint account's data
+pub fn decode_mint_authorities(data: &[u8]) -> Result<MintAuthorities> {
+    if data.len() < MINT_BASE_LEN {
+        return Err(anyhow!("Mint account data is {} bytes, expected at least {}", data.len(), MINT_BASE_LEN));
+    }
+    let authority = |offset: usize| -> Result<Option<Pubkey>> {
+        match u32::from_le_bytes(data[offset..offset + 4].try_into()?) {
+            0 => Ok(None),
+            1 => Ok(Some(Pubkey::try_from(&data[offset + 4..offset + 36])?)),
+            tag => Err(anyhow!("Invalid authority option tag {} in mint account", tag)),
+        }
+    };
+    Ok(MintAuthorities {
+        mint_authority: authority(MINT_AUTHORITY_OFFSET)?,
+        freeze_authority: authority(FREEZE_AUTHORITY_OFFSET)?,
+    })
+}
+
+pub fn mint_authorities(client: &RpcClient, mint: &Pubkey) -> Result<MintAuthorities> {
+    decode_mint_authorities(&client.get_account_data(mint)?)
+}
+
+// Token program that owns a mint, SPL Token or Token-2022
+pub fn mint_program_id(client: &RpcClient, mint: &Pubkey) -> Result<Pubkey> {
+    Ok(client.get_account(mint)?.owner)
+}
+
+// Associated token account of an owner for a mint under the given token program
+pub fn associated_token_address(owner: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Result<Pubkey> {
+    let associated_token_program = Pubkey::from_str(ASSOCIATED_TOKEN_PROGRAM_ID)?;
+    Ok(Pubkey::find_program_address(&[owner.as_ref(), token_program.as_ref(), mint.as_ref()], &associated_token_program).0)
+}
+
+// Create an owner's associated token account for a mint, succeeding without change if it already exists
+pub fn create_associated_token_account_idempotent(payer: &Pubkey, owner: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Result<Instruction> {
+    Ok(Instruction {
+        program_id: Pubkey::from_str(ASSOCIATED_TOKEN_PROGRAM_ID)?,
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(associated_token_address(owner, mint, token_program)?, false),
+            AccountMeta::new_readonly(*owner, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(*token_program, false),
+        ],
+        data: vec![CREATE_IDEMPOTENT],
+    })
+}
+
+// Whole-unit amount to the raw integer amount of a mint with the given decimals
+pub fn to_raw_amount(amount: f64, decimals: u8) -> u64 {
+    (amount * 10_f64.powi(decimals as i32)) as u64
+}
+
+// Raw integer amount of a mint with the given decimals to whole units
+pub fn from_raw_amount(raw_amount: u64, decimals: u8) -> f64 {
+    raw_amount as f64 / 10_f64.powi(decimals as i32)
+}
+
+// Close an empty token account, returning its rent to the owner
+pub fn close_account_instruction(account: &TokenAccountBalance, owner: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: account.program_id,
+        accounts: vec![
+            AccountMeta::new(account.address, false),
+            AccountMeta::new(*owner, false),
+            AccountMeta::new_readonly(*owner, true),
+        ],
+        data: vec![CLOSE_ACCOUNT],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn program_ids_are_valid_pubkeys() {
+        for program_id in [TOKEN_PROGRAM_ID, TOKEN_2022_PROGRAM_ID, ASSOCIATED_TOKEN_PROGRAM_ID] {
+            assert!(Pubkey::from_str(program_id).is_ok(), "{}", program_id);
+        }
+    }
+
+    #[test]
+    fn mint_authorities_are_read_from_mint_data() {
+        let (mint_authority, freeze_authority) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        let revoked = decode_mint_authorities(&crate::mock_server::mint_data(6, None, None)).unwrap();
+        assert_eq!(revoked, MintAuthorities { mint_authority: None, freeze_authority: None });
+
+        let held = decode_mint_authorities(&crate::mock_server::mint_data(6, Some(mint_authority), Some(freeze_authority))).unwrap();
+        assert_eq!(held, MintAuthorities { mint_authority: Some(mint_authority), freeze_authority: Some(freeze_authority) });
+
+        // Token-2022 mints carry extensions past the base layout
+        let mut extended = crate::mock_server::mint_data(6, None, Some(freeze_authority));
+        extended.resize(300, 7);
+        assert_eq!(decode_mint_authorities(&extended).unwrap().freeze_authority, Some(freeze_authority));
+    }
+
+    #[test]
+    fn malformed_mint_data_is_rejected() {
+        assert!(decode_mint_authorities(&[0; 40]).unwrap_err().to_string().contains("expected at least 82"));
+
+        let mut bad_tag = crate::mock_server::mint_data(6, None, None);
+        bad_tag[0] = 2;
+        assert_eq!(decode_mint_authorities(&bad_tag).unwrap_err().to_string(), "Invalid authority option tag 2 in mint account");
+    }
+}
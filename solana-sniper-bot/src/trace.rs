@@ -0,0 +1,25 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+
+static RUN_ID: OnceLock<String> = OnceLock::new();
+static NEXT_TRACE: AtomicU64 = AtomicU64::new(1);
+
+// Set the run ID for this process, generating one unless configured. Later calls keep the first ID.
+pub fn init_run_id(configured: Option<String>) -> &'static str {
+    RUN_ID.get_or_init(|| {
+        configured.filter(|id| !id.is_empty()).unwrap_or_else(|| {
+            format!("{:x}-{:x}", chrono::Utc::now().timestamp_millis(), std::process::id())
+        })
+    })
+}
+
+// The run ID if the bot has started, used by log formatters that may run before that
+pub fn current_run_id() -> Option<&'static str> {
+    RUN_ID.get().map(|id| id.as_str())
+}
+
+// A trace ID for one trade, unique within the run and prefixed by it so it's unique across runs too
+pub fn new_trace_id() -> String {
+    let sequence = NEXT_TRACE.fetch_add(1, Ordering::Relaxed);
+    format!("{}-{}", current_run_id().unwrap_or("norun"), sequence)
+}
@@ -0,0 +1,25 @@
+use anyhow::Result;
+use solana_sdk::{hash::Hash, message::VersionedMessage, pubkey::Pubkey};
+
+// What the bot is about to buy, handed to a custom transaction builder
+#[derive(Debug, Clone)]
+pub struct SnipeRequest {
+    pub wallet_index: usize,
+    pub payer: Pubkey,
+    pub token_address: String,
+    pub amount_sol: f64,
+    pub recent_blockhash: Hash,
+}
+
+// An unsigned transaction for the bot to sign with the snipe wallet and submit
+pub struct BuiltTransaction {
+    pub message: VersionedMessage,
+    // Raw token amount the transaction is expected to buy, used to size the tracked position
+    pub expected_token_amount: u64,
+}
+
+// Lets callers replace the Jupiter swap with their own transaction construction while the bot
+// keeps detection, safety checks, signing, submission and position tracking
+pub trait TransactionBuilder: Send + Sync {
+    fn build(&self, request: &SnipeRequest) -> Result<BuiltTransaction>;
+}
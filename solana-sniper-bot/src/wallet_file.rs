@@ -0,0 +1,115 @@
+use anyhow::{anyhow, Result};
+use solana_sdk::derivation_path::DerivationPath;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::keypair::keypair_from_seed_and_derivation_path;
+
+// Object keys other tools store the secret key under in JSON wallet files
+const SECRET_KEY_FIELDS: &[&str] = &["secretKey", "secret_key", "privateKey", "private_key"];
+
+// Load a wallet file in whichever format it was written: the Solana CLI's JSON byte array (or a JSON object
+// holding one), a base58 secret key, or a BIP39 seed phrase derived at the default path m/44'/501'/0'/0'.
+// Encrypted keystores aren't supported; decrypt them with the tool that wrote them first.
+pub fn load_keypair(path: &str) -> Result<Keypair> {
+    let contents = zeroize::Zeroizing::new(std::fs::read_to_string(path)
+        .map_err(|e| anyhow!("Failed to read wallet {}: {}", path, e))?);
+    let contents = contents.trim();
+
+    let mut failures = Vec::new();
+    match keypair_from_json(contents) {
+        Ok(keypair) => return Ok(keypair),
+        Err(e) => failures.push(format!("JSON byte array: {}", e)),
+    }
+    match keypair_from_base58(contents) {
+        Ok(keypair) => return Ok(keypair),
+        Err(e) => failures.push(format!("base58 secret key: {}", e)),
+    }
+    match keypair_from_mnemonic(contents, "", 0) {
+        Ok(keypair) => return Ok(keypair),
+        Err(e) => failures.push(format!("seed phrase: {}", e)),
+    }
+    Err(anyhow!("Wallet {} is not in a supported format. Tried:\n  {}", path, failures.join("\n  ")))
+}
+
+fn keypair_from_json(contents: &str) -> Result<Keypair> {
+    let value: serde_json::Value = serde_json::from_str(contents)?;
+    let secret = SECRET_KEY_FIELDS.iter()
+        .find_map(|field| value.get(field))
+        .unwrap_or(&value);
+    match secret {
+        serde_json::Value::String(encoded) => keypair_from_base58(encoded),
+        secret => keypair_from_bytes(&serde_json::from_value::<Vec<u8>>(secret.clone())?),
+    }
+}
+
+fn keypair_from_base58(contents: &str) -> Result<Keypair> {
+    keypair_from_bytes(&zeroize::Zeroizing::new(bs58::decode(contents).into_vec()?))
+}
+
+fn keypair_from_bytes(bytes: &[u8]) -> Result<Keypair> {
+    Keypair::from_bytes(bytes).map_err(|e| anyhow!("expected a 64-byte keypair, got {} bytes ({})", bytes.len(), e))
+}
+
+// Derive the keypair at m/44'/501'/<account>'/0' from a BIP39 seed phrase, as Phantom and the Solana CLI do
+pub fn keypair_from_mnemonic(mnemonic: &str, passphrase: &str, account_index: u32) -> Result<Keypair> {
+    let mnemonic = bip39::Mnemonic::from_phrase(mnemonic.trim(), bip39::Language::English)
+        .map_err(|e| anyhow!("Invalid BIP39 mnemonic: {}", e))?;
+    let seed = bip39::Seed::new(&mnemonic, passphrase);
+    let derivation_path = DerivationPath::new_bip44(Some(account_index), Some(0));
+    keypair_from_seed_and_derivation_path(seed.as_bytes(), Some(derivation_path))
+        .map_err(|e| anyhow!("Failed to derive keypair: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::signature::Signer;
+
+    const MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    // Write the contents to a scratch wallet file and load it back
+    fn load(name: &str, contents: &str) -> Result<Keypair> {
+        let path = std::env::temp_dir().join(format!("wallet-{}-{}.json", name, std::process::id()));
+        std::fs::write(&path, contents).unwrap();
+        let keypair = load_keypair(path.to_str().unwrap());
+        std::fs::remove_file(&path).unwrap();
+        keypair
+    }
+
+    #[test]
+    fn loads_a_json_byte_array() {
+        let keypair = Keypair::new();
+        let contents = serde_json::to_string(&keypair.to_bytes().to_vec()).unwrap();
+        assert_eq!(load("array", &contents).unwrap().pubkey(), keypair.pubkey());
+    }
+
+    #[test]
+    fn loads_a_base58_secret_key() {
+        let keypair = Keypair::new();
+        assert_eq!(load("base58", &format!("{}\n", keypair.to_base58_string())).unwrap().pubkey(), keypair.pubkey());
+    }
+
+    #[test]
+    fn loads_a_json_object_with_a_secret_key() {
+        let keypair = Keypair::new();
+        let as_array = serde_json::json!({ "publicKey": keypair.pubkey().to_string(), "secretKey": keypair.to_bytes().to_vec() });
+        let as_base58 = serde_json::json!({ "private_key": keypair.to_base58_string() });
+        assert_eq!(load("object-array", &as_array.to_string()).unwrap().pubkey(), keypair.pubkey());
+        assert_eq!(load("object-base58", &as_base58.to_string()).unwrap().pubkey(), keypair.pubkey());
+    }
+
+    #[test]
+    fn loads_a_seed_phrase_at_the_default_path() {
+        let keypair = load("mnemonic", MNEMONIC).unwrap();
+        assert_eq!(keypair.pubkey().to_string(), "HAgk14JpMQLgt6rVgv7cBQFJWFto5Dqxi472uT3DKpqk");
+        assert_eq!(keypair.pubkey(), keypair_from_mnemonic(MNEMONIC, "", 0).unwrap().pubkey());
+        assert_ne!(keypair.pubkey(), keypair_from_mnemonic(MNEMONIC, "", 1).unwrap().pubkey());
+    }
+
+    #[test]
+    fn unsupported_contents_list_every_attempted_format() {
+        let error = load("garbage", "not a wallet").unwrap_err().to_string();
+        assert!(error.contains("JSON byte array"), "{}", error);
+        assert!(error.contains("base58 secret key"), "{}", error);
+        assert!(error.contains("seed phrase"), "{}", error);
+    }
+}
@@ -1,11 +1,20 @@
 use anyhow::Result;
-use log::{info, warn, error};
+use log::{debug, info, warn, error};
 use serde::{Deserialize, Serialize};
-use tokio::sync::mpsc;
-use tokio_tungstenite::{connect_async, tungstenite::protocol::Message};
+use tokio::{sync::{mpsc, watch}, task::JoinHandle};
+use tokio_tungstenite::{connect_async, tungstenite::{self, client::IntoClientRequest, handshake::client::Request, http::HeaderValue, protocol::{CloseFrame, Message}}};
 use futures_util::{SinkExt, StreamExt};
 use url::Url;
-use std::time::Duration;
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+use crate::retry::RetryPolicy;
+use crate::secrets::SecretString;
+
+// A connection that stayed up this long counts as healthy, so the next reconnect starts from the base delay again
+const STABLE_CONNECTION: Duration = Duration::from_secs(60);
+// Birdeye's socket only accepts connections presenting this origin and subprotocol
+const BIRDEYE_ORIGIN: &str = "ws://public-api.birdeye.so";
+const BIRDEYE_PROTOCOL: &str = "echo-protocol";
 
 // WebSocket message types
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -16,14 +25,33 @@ pub enum WebSocketMessage {
     Error(String),
 }
 
+// Liquidity value for pools and listings whose liquidity isn't known, so it can't be mistaken for an empty pool
+pub const UNKNOWN_LIQUIDITY: f64 = -1.0;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PoolUpdate {
     pub pool_address: String,
     pub token_a: String,
     pub token_b: String,
+    // SOL-side liquidity in SOL, or UNKNOWN_LIQUIDITY when it couldn't be decoded
     pub liquidity: f64,
     pub volume_24h: f64,
     pub timestamp: u64,
+    #[serde(default)]
+    pub details: Option<PoolDetails>,
+    // DEX program the pool was created under
+    #[serde(default)]
+    pub program_id: Option<String>,
+}
+
+// Pool parameters decoded from the pool-creation instruction itself
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PoolDetails {
+    pub creator: Option<String>,
+    pub lp_mint: Option<String>,
+    pub open_time: u64,
+    pub init_base_amount: u64,
+    pub init_quote_amount: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -44,118 +72,407 @@ pub struct PriceUpdate {
     pub timestamp: u64,
 }
 
+// How a provider ended (or refused) a connection, which decides how long to wait before reconnecting
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Disconnect {
+    // Dropped connections and server hiccups: reconnect after the normal delay
+    Transient,
+    // The provider is throttling us: back off for the longer rate-limit delay
+    RateLimited,
+    // Bad or missing credentials, or an unusable URL: reconnecting can't help, so the monitor stops
+    Fatal,
+}
+
+// Close codes some providers use for rate limiting (1013 is the standard "try again later")
+const RATE_LIMIT_CLOSE_CODES: &[u16] = &[1013, 4008, 4029, 4290, 4429];
+// Close codes some providers use for failed authentication
+const AUTH_CLOSE_CODES: &[u16] = &[4001, 4003, 4004, 4401, 4403];
+
+// Classify a close frame by its code, falling back to the wording of its reason
+pub fn classify_close(code: u16, reason: &str) -> Disconnect {
+    if AUTH_CLOSE_CODES.contains(&code) {
+        return Disconnect::Fatal;
+    }
+    if RATE_LIMIT_CLOSE_CODES.contains(&code) {
+        return Disconnect::RateLimited;
+    }
+    classify_message(reason)
+}
+
+// Classify a failed connection attempt, using the HTTP status when the handshake was rejected
+pub fn classify_connect_error(error: &tungstenite::Error) -> Disconnect {
+    match error {
+        tungstenite::Error::Http(response) => match response.status().as_u16() {
+            401 | 403 => Disconnect::Fatal,
+            429 => Disconnect::RateLimited,
+            _ => Disconnect::Transient,
+        },
+        // A URL tungstenite can't connect to, such as a non-ws scheme, never starts working
+        tungstenite::Error::Url(_) => Disconnect::Fatal,
+        error => classify_message(&error.to_string()),
+    }
+}
+
+// How long to wait before reconnecting after a disconnect, or None when the monitor should stop
+fn reconnect_delay(url: &str, disconnect: Disconnect, backoff: Duration, rate_limit_delay: Duration, attempt: u32) -> Option<Duration> {
+    match disconnect {
+        Disconnect::Transient => {
+            info!("Reconnecting to WebSocket {} in {}ms (attempt {})", url, backoff.as_millis(), attempt);
+            Some(backoff)
+        }
+        Disconnect::RateLimited => {
+            let delay = backoff.max(rate_limit_delay);
+            warn!("WebSocket {} is rate limiting us; reconnecting in {}ms", url, delay.as_millis());
+            Some(delay)
+        }
+        Disconnect::Fatal => None,
+    }
+}
+
+// The text of a received frame, or how the connection ended; other frames yield neither
+fn frame_text(url: &str, frame: std::result::Result<Message, tungstenite::Error>) -> std::result::Result<Option<String>, Disconnect> {
+    match frame {
+        Ok(Message::Text(text)) => Ok(Some(text)),
+        Ok(Message::Close(frame)) => {
+            let Some(CloseFrame { code, reason }) = frame else {
+                info!("WebSocket connection closed: {}", url);
+                return Err(Disconnect::Transient);
+            };
+            info!("WebSocket connection closed: {} (code {}: {})", url, u16::from(code), reason);
+            Err(classify_close(code.into(), &reason))
+        }
+        Err(e) => {
+            error!("WebSocket error: {}", e);
+            Err(classify_message(&e.to_string()))
+        }
+        _ => Ok(None),
+    }
+}
+
+fn classify_message(message: &str) -> Disconnect {
+    let message = message.to_lowercase();
+    if ["unauthorized", "forbidden", "invalid api key", "authentication"].iter().any(|fragment| message.contains(fragment)) {
+        Disconnect::Fatal
+    } else if ["rate limit", "too many", "quota"].iter().any(|fragment| message.contains(fragment)) {
+        Disconnect::RateLimited
+    } else {
+        Disconnect::Transient
+    }
+}
+
+// What a monitor sends right after connecting; providers ignore subscriptions in a format they don't expect
+#[derive(Debug, Clone)]
+pub enum SubscriptionKind {
+    // A JSON-RPC subscribe to the pool, listing and price channels
+    Raydium,
+    // Birdeye's typed subscribe messages for new listings and new pairs, authenticated with an API key
+    Birdeye { api_key: SecretString },
+}
+
+impl SubscriptionKind {
+    pub fn messages(&self) -> Vec<serde_json::Value> {
+        match self {
+            SubscriptionKind::Raydium => vec![serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "subscribe",
+                "params": ["poolUpdates", "tokenListings", "priceUpdates"]
+            })],
+            SubscriptionKind::Birdeye { .. } => vec![
+                serde_json::json!({ "type": "SUBSCRIBE_TOKEN_NEW_LISTING" }),
+                serde_json::json!({ "type": "SUBSCRIBE_NEW_PAIR" }),
+            ],
+        }
+    }
+
+    fn connect_request(&self, url: &Url) -> Result<Request> {
+        match self {
+            SubscriptionKind::Raydium => Ok(url.as_str().into_client_request()?),
+            SubscriptionKind::Birdeye { api_key } => birdeye_request(url, api_key),
+        }
+    }
+
+    // The bot message carried by a frame in this provider's format, if any
+    fn parse(&self, text: &str) -> Option<WebSocketMessage> {
+        match self {
+            SubscriptionKind::Raydium => parse_raydium_message(text),
+            SubscriptionKind::Birdeye { .. } => parse_birdeye_listing(text),
+        }
+    }
+}
+
+// Birdeye takes the API key in the query string and only accepts connections presenting its origin and subprotocol
+fn birdeye_request(url: &Url, api_key: &SecretString) -> Result<Request> {
+    let mut url = url.clone();
+    url.query_pairs_mut().append_pair("x-api-key", api_key.expose());
+    let mut request = url.as_str().into_client_request()?;
+    request.headers_mut().insert("Origin", HeaderValue::from_static(BIRDEYE_ORIGIN));
+    request.headers_mut().insert("Sec-WebSocket-Protocol", HeaderValue::from_static(BIRDEYE_PROTOCOL));
+    Ok(request)
+}
+
+// Raydium wraps each update in a JSON-RPC notification naming its channel
+fn parse_raydium_message(text: &str) -> Option<WebSocketMessage> {
+    let data: serde_json::Value = serde_json::from_str(text).ok()?;
+    let params = data.get("params")?;
+    let result = params.get("result")?.clone();
+    match params.get("method")?.as_str()? {
+        "poolUpdate" => serde_json::from_value(result).ok().map(WebSocketMessage::PoolUpdate),
+        "tokenListing" => serde_json::from_value(result).ok().map(WebSocketMessage::TokenListing),
+        "priceUpdate" => serde_json::from_value(result).ok().map(WebSocketMessage::PriceUpdate),
+        method => {
+            warn!("Unknown WebSocket method: {}", method);
+            None
+        }
+    }
+}
+
+// TOKEN_NEW_LISTING_DATA announces a token's first liquidity and NEW_PAIR_DATA a new pool. Birdeye reports
+// liquidity in USD, so it is left unknown rather than mistaken for SOL.
+fn parse_birdeye_listing(text: &str) -> Option<WebSocketMessage> {
+    let message: serde_json::Value = serde_json::from_str(text).ok()?;
+    let data = message.get("data")?;
+    let now = || chrono::Utc::now().timestamp() as u64;
+    let text_field = |field: &str| data.get(field).and_then(|v| v.as_str()).unwrap_or_default().to_string();
+    match message.get("type")?.as_str()? {
+        "TOKEN_NEW_LISTING_DATA" => Some(WebSocketMessage::TokenListing(TokenListing {
+            token_address: data.get("address")?.as_str()?.to_string(),
+            symbol: text_field("symbol"),
+            name: text_field("name"),
+            initial_liquidity: UNKNOWN_LIQUIDITY,
+            timestamp: data.get("liquidityAddedAt").and_then(|v| v.as_u64()).unwrap_or_else(now),
+        })),
+        "NEW_PAIR_DATA" => Some(WebSocketMessage::PoolUpdate(PoolUpdate {
+            pool_address: data.get("address")?.as_str()?.to_string(),
+            token_a: data.get("base")?.get("address")?.as_str()?.to_string(),
+            token_b: data.get("quote")?.get("address")?.as_str()?.to_string(),
+            liquidity: UNKNOWN_LIQUIDITY,
+            volume_24h: 0.0,
+            timestamp: data.get("blockTime").and_then(|v| v.as_u64()).unwrap_or_else(now),
+            details: None,
+            program_id: None,
+        })),
+        _ => None,
+    }
+}
+
 #[derive(Clone)]
 pub struct WebSocketMonitor {
     url: String,
-    reconnect_delay: Duration,
+    subscription: SubscriptionKind,
+    // Exponential backoff between reconnects; max_retries is unused since transient failures retry forever
+    reconnect_backoff: RetryPolicy,
+    rate_limit_reconnect_delay: Duration,
 }
 
 impl WebSocketMonitor {
-    pub fn new(url: String, reconnect_delay_ms: u64) -> Self {
+    pub fn new(url: String, subscription: SubscriptionKind, reconnect_backoff: RetryPolicy, rate_limit_reconnect_delay_ms: u64) -> Self {
         WebSocketMonitor {
             url,
-            reconnect_delay: Duration::from_millis(reconnect_delay_ms),
+            subscription,
+            reconnect_backoff,
+            rate_limit_reconnect_delay: Duration::from_millis(rate_limit_reconnect_delay_ms),
         }
     }
 
+    // Stream messages until the provider rejects our credentials; any other disconnect is retried
     pub async fn start_monitoring(&self, tx: mpsc::Sender<WebSocketMessage>) -> Result<()> {
         info!("Starting WebSocket monitoring for: {}", self.url);
         let url = Url::parse(&self.url)?;
+        let mut attempt = 0;
         loop {
-            match connect_async(url.clone()).await {
+            let disconnect = match connect_async(self.subscription.connect_request(&url)?).await {
                 Ok((ws_stream, _)) => {
                     info!("Connected to WebSocket: {}", self.url);
-                    let (mut write, mut read) = ws_stream.split();
-                    if let Err(e) = Self::send_subscription(&mut write).await {
-                        error!("Failed to send subscription: {}", e);
-                        tokio::time::sleep(self.reconnect_delay).await;
-                        continue;
-                    }
-                    while let Some(msg) = read.next().await {
-                        match msg {
-                            Ok(Message::Text(text)) => {
-                                if let Err(e) = Self::process_message(&text, &tx).await {
-                                    error!("Failed to process message: {}", e);
-                                }
-                            }
-                            Ok(Message::Close(_)) => {
-                                info!("WebSocket connection closed: {}", self.url);
-                                break;
-                            }
-                            Err(e) => {
-                                error!("WebSocket error: {}", e);
-                                break;
-                            }
-                            _ => {}
+                    let connected_at = Instant::now();
+                    let (mut write, read) = ws_stream.split();
+                    let disconnect = match self.send_subscription(&mut write).await {
+                        Ok(()) => self.read_until_disconnect(read, &tx).await,
+                        Err(e) => {
+                            error!("Failed to send subscription: {}", e);
+                            Disconnect::Transient
                         }
+                    };
+                    if connected_at.elapsed() >= STABLE_CONNECTION {
+                        attempt = 0;
                     }
+                    disconnect
                 }
                 Err(e) => {
                     error!("Failed to connect to WebSocket {}: {}", self.url, e);
+                    classify_connect_error(&e)
                 }
+            };
+
+            attempt += 1;
+            let backoff = self.reconnect_backoff.jittered_delay_for(attempt);
+            let Some(delay) = reconnect_delay(&self.url, disconnect, backoff, self.rate_limit_reconnect_delay, attempt) else {
+                let message = format!("WebSocket {} rejected our credentials or is unusable; not reconnecting", self.url);
+                let _ = tx.send(WebSocketMessage::Error(message.clone())).await;
+                return Err(anyhow::anyhow!(message));
+            };
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    async fn read_until_disconnect<S>(&self, mut read: S, tx: &mpsc::Sender<WebSocketMessage>) -> Disconnect
+    where
+        S: StreamExt<Item = std::result::Result<Message, tungstenite::Error>> + Unpin,
+    {
+        while let Some(frame) = read.next().await {
+            match frame_text(&self.url, frame) {
+                Ok(Some(text)) => {
+                    if let Some(message) = self.subscription.parse(&text) {
+                        if let Err(e) = tx.send(message).await {
+                            error!("Failed to forward WebSocket message: {}", e);
+                        }
+                    }
+                }
+                Ok(None) => {}
+                Err(disconnect) => return disconnect,
             }
-            tokio::time::sleep(self.reconnect_delay).await;
         }
+        Disconnect::Transient
     }
 
-    async fn send_subscription<S>(write: &mut S) -> Result<()> 
+    async fn send_subscription<S>(&self, write: &mut S) -> Result<()> 
     where
         S: SinkExt<Message> + Unpin,
         S::Error: std::error::Error + Send + Sync + 'static,
     {
-        let subscribe_msg = serde_json::json!({
-            "jsonrpc": "2.0",
-            "id": 1,
-            "method": "subscribe",
-            "params": ["poolUpdates", "tokenListings", "priceUpdates"]
-        });
-        write.send(Message::Text(subscribe_msg.to_string())).await
-            .map_err(|e| anyhow::anyhow!("Failed to send subscription: {}", e))?;
+        for subscribe_msg in self.subscription.messages() {
+            write.send(Message::Text(subscribe_msg.to_string())).await
+                .map_err(|e| anyhow::anyhow!("Failed to send subscription: {}", e))?;
+        }
         Ok(())
     }
+}
 
-    async fn process_message(text: &str, tx: &mpsc::Sender<WebSocketMessage>) -> Result<()> {
-        if let Ok(data) = serde_json::from_str::<serde_json::Value>(text) {
-            if let Some(params) = data.get("params") {
-                if let Some(result) = params.get("result") {
-                    if let Some(method) = params.get("method").and_then(|v| v.as_str()) {
-                        match method {
-                            "poolUpdate" => {
-                                if let Ok(pool_update) = serde_json::from_value::<PoolUpdate>(result.clone()) {
-                                    if let Err(e) = tx.send(WebSocketMessage::PoolUpdate(pool_update)).await {
-                                        error!("Failed to send pool update: {}", e);
-                                    }
-                                }
-                            }
-                            "tokenListing" => {
-                                if let Ok(token_listing) = serde_json::from_value::<TokenListing>(result.clone()) {
-                                    if let Err(e) = tx.send(WebSocketMessage::TokenListing(token_listing)).await {
-                                        error!("Failed to send token listing: {}", e);
-                                    }
-                                }
-                            }
-                            "priceUpdate" => {
-                                if let Ok(price_update) = serde_json::from_value::<PriceUpdate>(result.clone()) {
-                                    if let Err(e) = tx.send(WebSocketMessage::PriceUpdate(price_update)).await {
-                                        error!("Failed to send price update: {}", e);
-                                    }
+// Streams Birdeye prices for a watchlist of mints, subscribing and unsubscribing as the watchlist changes
+#[derive(Clone)]
+pub struct BirdeyePriceMonitor {
+    url: String,
+    api_key: SecretString,
+    watchlist: watch::Receiver<HashSet<String>>,
+    reconnect_backoff: RetryPolicy,
+    rate_limit_reconnect_delay: Duration,
+}
+
+impl BirdeyePriceMonitor {
+    pub fn new(url: String, api_key: SecretString, watchlist: watch::Receiver<HashSet<String>>, reconnect_backoff: RetryPolicy, rate_limit_reconnect_delay_ms: u64) -> Self {
+        BirdeyePriceMonitor {
+            url,
+            api_key,
+            watchlist,
+            reconnect_backoff,
+            rate_limit_reconnect_delay: Duration::from_millis(rate_limit_reconnect_delay_ms),
+        }
+    }
+
+    // Stream prices until Birdeye rejects our key; any other disconnect is retried
+    pub async fn start_monitoring(&self, tx: mpsc::Sender<WebSocketMessage>) -> Result<()> {
+        info!("Starting Birdeye price monitoring for: {}", self.url);
+        let url = Url::parse(&self.url)?;
+        let mut watchlist = self.watchlist.clone();
+        let mut attempt = 0;
+        loop {
+            let disconnect = match connect_async(birdeye_request(&url, &self.api_key)?).await {
+                Ok((ws_stream, _)) => {
+                    info!("Connected to Birdeye price feed: {}", self.url);
+                    let connected_at = Instant::now();
+                    let disconnect = self.stream_prices(ws_stream, &mut watchlist, &tx).await;
+                    if connected_at.elapsed() >= STABLE_CONNECTION {
+                        attempt = 0;
+                    }
+                    disconnect
+                }
+                Err(e) => {
+                    error!("Failed to connect to Birdeye price feed {}: {}", self.url, e);
+                    classify_connect_error(&e)
+                }
+            };
+
+            attempt += 1;
+            let backoff = self.reconnect_backoff.jittered_delay_for(attempt);
+            let Some(delay) = reconnect_delay(&self.url, disconnect, backoff, self.rate_limit_reconnect_delay, attempt) else {
+                let message = format!("Birdeye price feed {} rejected our key or is unusable; not reconnecting", self.url);
+                let _ = tx.send(WebSocketMessage::Error(message.clone())).await;
+                return Err(anyhow::anyhow!(message));
+            };
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    // Keep the connection's subscriptions in step with the watchlist while forwarding price messages.
+    // Every connection starts with no subscriptions, so the whole watchlist is subscribed again on reconnect.
+    async fn stream_prices<S>(&self, ws_stream: S, watchlist: &mut watch::Receiver<HashSet<String>>, tx: &mpsc::Sender<WebSocketMessage>) -> Disconnect
+    where
+        S: StreamExt<Item = std::result::Result<Message, tungstenite::Error>> + SinkExt<Message, Error = tungstenite::Error> + Unpin,
+    {
+        let (mut write, mut read) = ws_stream.split();
+        let mut subscribed = HashSet::new();
+        loop {
+            let wanted = watchlist.borrow_and_update().clone();
+            let changes = wanted.difference(&subscribed).map(|mint| ("SUBSCRIBE_PRICE", mint))
+                .chain(subscribed.difference(&wanted).map(|mint| ("UNSUBSCRIBE_PRICE", mint)));
+            for (kind, mint) in changes {
+                let message = serde_json::json!({
+                    "type": kind,
+                    "data": { "queryType": "simple", "chartType": "1m", "address": mint, "currency": "usd" }
+                });
+                if let Err(e) = write.send(Message::Text(message.to_string())).await {
+                    error!("Failed to update Birdeye subscription for {}: {}", mint, e);
+                    return Disconnect::Transient;
+                }
+                debug!("Birdeye {} {}", kind, mint);
+            }
+            subscribed = wanted;
+
+            tokio::select! {
+                // A closed watchlist (the bot shutting down) just stops subscription changes
+                Ok(()) = watchlist.changed() => {}
+                frame = read.next() => {
+                    let Some(frame) = frame else {
+                        return Disconnect::Transient;
+                    };
+                    match frame_text(&self.url, frame) {
+                        Ok(Some(text)) => {
+                            if let Some(price_update) = parse_birdeye_price(&text) {
+                                if let Err(e) = tx.send(WebSocketMessage::PriceUpdate(price_update)).await {
+                                    error!("Failed to send price update: {}", e);
                                 }
                             }
-                            _ => {
-                                warn!("Unknown WebSocket method: {}", method);
-                            }
                         }
+                        Ok(None) => {}
+                        Err(disconnect) => return disconnect,
                     }
                 }
             }
         }
-        Ok(())
     }
 }
 
+// A PRICE_DATA message carries a one-minute candle for a mint; its close is the latest price.
+// Birdeye doesn't send 24h change or volume on this channel, so those are left at zero.
+fn parse_birdeye_price(text: &str) -> Option<PriceUpdate> {
+    let message: serde_json::Value = serde_json::from_str(text).ok()?;
+    if message.get("type")?.as_str()? != "PRICE_DATA" {
+        return None;
+    }
+    let data = message.get("data")?;
+    Some(PriceUpdate {
+        token_address: data.get("address")?.as_str()?.to_string(),
+        price: data.get("c")?.as_f64()?,
+        price_change_24h: 0.0,
+        volume_24h: 0.0,
+        timestamp: data.get("unixTime").and_then(|v| v.as_u64()).unwrap_or_else(|| chrono::Utc::now().timestamp() as u64),
+    })
+}
+
 #[derive(Clone)]
 pub struct DexWebSocketManager {
     monitors: Vec<WebSocketMonitor>,
+    price_monitor: Option<BirdeyePriceMonitor>,
     message_tx: mpsc::Sender<WebSocketMessage>,
     message_rx: std::sync::Arc<tokio::sync::Mutex<mpsc::Receiver<WebSocketMessage>>>,
 }
@@ -165,34 +482,46 @@ impl DexWebSocketManager {
         let (message_tx, message_rx) = mpsc::channel(1000);
         DexWebSocketManager {
             monitors: Vec::new(),
+            price_monitor: None,
             message_tx,
             message_rx: std::sync::Arc::new(tokio::sync::Mutex::new(message_rx)),
         }
     }
 
-    pub fn add_monitor(&mut self, url: String, reconnect_delay_ms: u64) {
-        let monitor = WebSocketMonitor::new(url, reconnect_delay_ms);
+    pub fn add_monitor(&mut self, url: String, subscription: SubscriptionKind, reconnect_backoff: RetryPolicy, rate_limit_reconnect_delay_ms: u64) {
+        let monitor = WebSocketMonitor::new(url, subscription, reconnect_backoff, rate_limit_reconnect_delay_ms);
         self.monitors.push(monitor);
     }
 
-    pub async fn start_all_monitors(&self) -> Result<()> {
-        let mut handles = Vec::new();
-        for monitor in &self.monitors {
+    pub fn set_price_monitor(&mut self, price_monitor: BirdeyePriceMonitor) {
+        self.price_monitor = Some(price_monitor);
+    }
+
+    pub fn monitor_count(&self) -> usize {
+        self.monitors.len() + usize::from(self.price_monitor.is_some())
+    }
+
+    // Start up to max_monitors of the monitors on the shared message channel, each as its own task, with the
+    // price monitor last
+    pub fn spawn_monitors(&self, max_monitors: usize) -> Vec<JoinHandle<()>> {
+        let mut tasks: Vec<JoinHandle<()>> = self.monitors.iter().take(max_monitors).map(|monitor| {
+            let monitor = monitor.clone();
             let tx = self.message_tx.clone();
-            let monitor_clone = monitor.clone();
-            let handle = tokio::spawn(async move {
-                if let Err(e) = monitor_clone.start_monitoring(tx).await {
+            tokio::spawn(async move {
+                if let Err(e) = monitor.start_monitoring(tx).await {
                     error!("Monitor failed: {}", e);
                 }
-            });
-            handles.push(handle);
-        }
-        for handle in handles {
-            if let Err(e) = handle.await {
-                error!("Monitor task failed: {}", e);
-            }
+            })
+        }).collect();
+        if let Some(price_monitor) = self.price_monitor.clone().filter(|_| tasks.len() < max_monitors) {
+            let tx = self.message_tx.clone();
+            tasks.push(tokio::spawn(async move {
+                if let Err(e) = price_monitor.start_monitoring(tx).await {
+                    error!("Price monitor failed: {}", e);
+                }
+            }));
         }
-        Ok(())
+        tasks
     }
 
     pub fn get_message_receiver(&self) -> std::sync::Arc<tokio::sync::Mutex<mpsc::Receiver<WebSocketMessage>>> {
@@ -213,4 +542,100 @@ impl Default for DexWebSocketManager {
     fn default() -> Self {
         Self::new()
     }
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BACKOFF: Duration = Duration::from_millis(200);
+    const RATE_LIMIT_DELAY: Duration = Duration::from_secs(30);
+
+    fn delay_after(disconnect: Disconnect) -> Option<Duration> {
+        reconnect_delay("wss://example.invalid", disconnect, BACKOFF, RATE_LIMIT_DELAY, 1)
+    }
+
+    fn handshake_rejected(status: u16) -> tungstenite::Error {
+        tungstenite::Error::Http(tungstenite::http::Response::builder().status(status).body(None).unwrap())
+    }
+
+    #[test]
+    fn rate_limit_closes_back_off_for_the_rate_limit_delay() {
+        for code in RATE_LIMIT_CLOSE_CODES {
+            assert_eq!(classify_close(*code, ""), Disconnect::RateLimited, "close code {}", code);
+        }
+        assert_eq!(classify_close(1008, "Too many connections"), Disconnect::RateLimited);
+        assert_eq!(classify_connect_error(&handshake_rejected(429)), Disconnect::RateLimited);
+        assert_eq!(delay_after(Disconnect::RateLimited), Some(RATE_LIMIT_DELAY));
+    }
+
+    #[test]
+    fn auth_failures_stop_the_monitor() {
+        for code in AUTH_CLOSE_CODES {
+            assert_eq!(classify_close(*code, ""), Disconnect::Fatal, "close code {}", code);
+        }
+        assert_eq!(classify_close(1008, "Invalid API key"), Disconnect::Fatal);
+        assert_eq!(classify_connect_error(&handshake_rejected(401)), Disconnect::Fatal);
+        assert_eq!(classify_connect_error(&handshake_rejected(403)), Disconnect::Fatal);
+        assert_eq!(classify_connect_error(&tungstenite::Error::Url(tungstenite::error::UrlError::UnsupportedUrlScheme)), Disconnect::Fatal);
+        assert_eq!(delay_after(Disconnect::Fatal), None);
+    }
+
+    #[test]
+    fn transient_failures_reconnect_after_the_normal_backoff() {
+        assert_eq!(classify_close(1000, ""), Disconnect::Transient);
+        assert_eq!(classify_close(1011, "internal error"), Disconnect::Transient);
+        assert_eq!(classify_connect_error(&handshake_rejected(502)), Disconnect::Transient);
+        assert_eq!(classify_connect_error(&tungstenite::Error::ConnectionClosed), Disconnect::Transient);
+        assert_eq!(delay_after(Disconnect::Transient), Some(BACKOFF));
+    }
+
+    #[test]
+    fn birdeye_listings_and_pairs_become_bot_messages() {
+        let birdeye = SubscriptionKind::Birdeye { api_key: SecretString::default() };
+
+        let listing = birdeye.parse(r#"{"type":"TOKEN_NEW_LISTING_DATA","data":{"address":"Mint111","decimals":6,"name":"New Token","symbol":"NEW","liquidity":"1250.5","liquidityAddedAt":1700000000}}"#);
+        let Some(WebSocketMessage::TokenListing(listing)) = listing else {
+            panic!("expected a token listing, got {:?}", listing);
+        };
+        assert_eq!((listing.token_address.as_str(), listing.symbol.as_str(), listing.name.as_str()), ("Mint111", "NEW", "New Token"));
+        assert_eq!(listing.initial_liquidity, UNKNOWN_LIQUIDITY);
+        assert_eq!(listing.timestamp, 1_700_000_000);
+
+        let pair = birdeye.parse(r#"{"type":"NEW_PAIR_DATA","data":{"address":"Pool111","name":"NEW-SOL","source":"raydium","base":{"address":"Mint111","symbol":"NEW","decimals":6},"quote":{"address":"So11111111111111111111111111111111111111112","symbol":"SOL","decimals":9},"txHash":"Sig111","blockTime":1700000001}}"#);
+        let Some(WebSocketMessage::PoolUpdate(pool)) = pair else {
+            panic!("expected a pool update, got {:?}", pair);
+        };
+        assert_eq!((pool.pool_address.as_str(), pool.token_a.as_str()), ("Pool111", "Mint111"));
+        assert_eq!(pool.token_b, "So11111111111111111111111111111111111111112");
+        assert_eq!(pool.liquidity, UNKNOWN_LIQUIDITY);
+        assert_eq!(pool.timestamp, 1_700_000_001);
+
+        assert!(birdeye.parse(r#"{"type":"WELCOME"}"#).is_none());
+        assert!(birdeye.parse(r#"{"params":{"method":"tokenListing","result":{}}}"#).is_none());
+    }
+
+    #[test]
+    fn raydium_notifications_become_bot_messages() {
+        let message = SubscriptionKind::Raydium.parse(r#"{"jsonrpc":"2.0","params":{"method":"tokenListing","result":{"token_address":"Mint111","symbol":"NEW","name":"New Token","initial_liquidity":12.5,"timestamp":1700000000}}}"#);
+        let Some(WebSocketMessage::TokenListing(listing)) = message else {
+            panic!("expected a token listing, got {:?}", message);
+        };
+        assert_eq!(listing.token_address, "Mint111");
+        assert_eq!(listing.initial_liquidity, 12.5);
+
+        assert!(SubscriptionKind::Raydium.parse(r#"{"type":"TOKEN_NEW_LISTING_DATA","data":{"address":"Mint111"}}"#).is_none());
+    }
+
+    #[test]
+    fn birdeye_connections_carry_the_key_origin_and_subprotocol() {
+        let api_key = SecretString::from("test-key");
+        let url = Url::parse("wss://public-api.birdeye.so/socket/solana").unwrap();
+
+        let request = SubscriptionKind::Birdeye { api_key }.connect_request(&url).unwrap();
+
+        assert_eq!(request.uri().query(), Some("x-api-key=test-key"));
+        assert_eq!(request.headers()["Origin"], BIRDEYE_ORIGIN);
+        assert_eq!(request.headers()["Sec-WebSocket-Protocol"], BIRDEYE_PROTOCOL);
+        assert!(SubscriptionKind::Raydium.connect_request(&url).unwrap().headers().get("Origin").is_none());
+    }
+}